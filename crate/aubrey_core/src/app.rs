@@ -4,7 +4,10 @@ pub use crate::ecs::entity::Entity;
 pub use crate::ecs::Commands;
 pub use crate::ecs::{Bundle, One as OneComponent};
 use crate::ecs::Ecs;
+use crate::ecs::snapshot::Snapshot;
+use crate::ecs::prefab::{Prefab, PrefabOverrides, PrefabInstance};
 use crate::ecs::schedule::Schedules;
+use crate::ecs::event::{Events, update_events};
 
 // Appを終了させるためのリソース。存在すればrunループを抜ける。
 pub struct AppExit;
@@ -32,6 +35,17 @@ pub struct AppExit;
 pub struct App {
     ecs: Ecs,
     schedules: Schedules,
+    sub_apps: Vec<SubAppSlot>,
+}
+
+/// Copies data from the parent world into a sub-app world before the sub-app runs.
+pub type ExtractFn = Box<dyn FnMut(&Ecs, &mut Ecs) + Send>;
+
+struct SubAppSlot {
+    name: String,
+    app: App,
+    extract: Option<ExtractFn>,
+    running: bool,
 }
 
 impl Default for App {
@@ -42,7 +56,7 @@ impl Default for App {
 
 impl App {
     pub fn new() -> Self {
-        Self { ecs: Ecs::new(), schedules: Schedules::new() }
+        Self { ecs: Ecs::new(), schedules: Schedules::new(), sub_apps: Vec::new() }
     }
 
     // Bevy-like API surface
@@ -81,7 +95,7 @@ impl App {
     pub fn run(&mut self) {
         // 1フレーム分を実行（Startup系を未実行なら含む）
         loop {
-            self.schedules.run_frame(&mut self.ecs);
+            self.update();
             if self.ecs.get_resource::<AppExit>().is_some() { break; }
         }
     }
@@ -89,6 +103,61 @@ impl App {
     pub fn update(&mut self) {
         // run() と同じく1フレーム分を進める
         self.schedules.run_frame(&mut self.ecs);
+        // 続いて実行中のサブアプリを登録順に: extract -> 1フレーム
+        for slot in self.sub_apps.iter_mut().filter(|s| s.running) {
+            if let Some(extract) = slot.extract.as_mut() { extract(&self.ecs, &mut slot.app.ecs); }
+            slot.app.update();
+        }
+    }
+
+    // --- Sub-app APIs ---
+    /// Add (or replace) a named sub-app with its own world and schedules. Starts running.
+    pub fn insert_sub_app(&mut self, name: impl Into<String>, app: App) -> &mut Self {
+        let name = name.into();
+        self.sub_apps.retain(|s| s.name != name);
+        self.sub_apps.push(SubAppSlot { name, app, extract: None, running: true });
+        self
+    }
+
+    pub fn remove_sub_app(&mut self, name: &str) -> Option<App> {
+        let idx = self.sub_apps.iter().position(|s| s.name == name)?;
+        Some(self.sub_apps.remove(idx).app)
+    }
+
+    pub fn sub_app(&self, name: &str) -> Option<&App> {
+        self.sub_apps.iter().find(|s| s.name == name).map(|s| &s.app)
+    }
+
+    pub fn sub_app_mut(&mut self, name: &str) -> Option<&mut App> {
+        self.sub_apps.iter_mut().find(|s| s.name == name).map(|s| &mut s.app)
+    }
+
+    pub fn sub_app_names(&self) -> Vec<&str> { self.sub_apps.iter().map(|s| s.name.as_str()).collect() }
+
+    /// Set the extract step run before each frame of the sub-app: `f(parent_world, sub_world)`.
+    pub fn set_extract<F>(&mut self, name: &str, f: F) -> &mut Self
+    where
+        F: FnMut(&Ecs, &mut Ecs) + Send + 'static,
+    {
+        if let Some(slot) = self.sub_apps.iter_mut().find(|s| s.name == name) { slot.extract = Some(Box::new(f)); }
+        self
+    }
+
+    /// Resume updating the sub-app. Returns false if it does not exist.
+    pub fn start_sub_app(&mut self, name: &str) -> bool { self.set_sub_app_running(name, true) }
+
+    /// Pause the sub-app; its world is kept as is.
+    pub fn stop_sub_app(&mut self, name: &str) -> bool { self.set_sub_app_running(name, false) }
+
+    pub fn is_sub_app_running(&self, name: &str) -> bool {
+        self.sub_apps.iter().any(|s| s.name == name && s.running)
+    }
+
+    fn set_sub_app_running(&mut self, name: &str, running: bool) -> bool {
+        match self.sub_apps.iter_mut().find(|s| s.name == name) {
+            Some(slot) => { slot.running = running; true }
+            None => false,
+        }
     }
 
     // --- Resource APIs ---
@@ -105,6 +174,10 @@ impl App {
         self.ecs.get_resource_mut::<T>()
     }
 
+    pub fn remove_resource<T: 'static + Send + Sync>(&mut self) -> Option<T> {
+        self.ecs.remove_resource::<T>()
+    }
+
     // --- Entity/Component APIs ---
     pub fn spawn_empty(&mut self) -> Entity {
         self.ecs.spawn_empty()
@@ -126,11 +199,62 @@ impl App {
         self.ecs.get_mut::<T>(entity)
     }
 
+    pub fn remove_component<T: 'static + Send + Sync>(&mut self, entity: Entity) -> Option<T> {
+        self.ecs.remove::<T>(entity)
+    }
+
     pub fn spawn<T: Bundle>(&mut self, bundle: T) -> Entity { self.ecs.spawn(bundle) }
+    pub fn insert_bundle<T: Bundle>(&mut self, entity: Entity, bundle: T) { self.ecs.insert_bundle(entity, bundle) }
+    pub fn remove_bundle<T: Bundle>(&mut self, entity: Entity) { self.ecs.remove_bundle::<T>(entity) }
+    pub fn take<T: Bundle>(&mut self, entity: Entity) -> Option<T> { self.ecs.take::<T>(entity) }
     pub fn spawn_one<T: 'static + Send + Sync>(&mut self, component: T) -> Entity { self.ecs.spawn_one(component) }
 
     pub fn commands(&mut self) -> &mut Commands { self.ecs.commands() }
 
+    // --- Events ---
+    /// Insert `Events<T>` and swap its buffers at the start of every frame. Idempotent.
+    pub fn add_event<T: 'static + Send + Sync>(&mut self) -> &mut Self {
+        if self.ecs.get_resource::<Events<T>>().is_none() {
+            self.ecs.insert_resource(Events::<T>::default());
+            self.add_systems_ordered(Stage::First, i32::MIN, update_events::<T>);
+        }
+        self
+    }
+    pub fn send_event<T: 'static + Send + Sync>(&mut self, event: T) { self.ecs.send_event(event) }
+
+    // --- Cloning / prefabs ---
+    pub fn register_clone<T: 'static + Send + Sync + Clone>(&mut self) -> &mut Self {
+        self.ecs.register_clone::<T>();
+        self
+    }
+    pub fn clone_entity(&mut self, entity: Entity) -> Option<Entity> { self.ecs.clone_entity(entity) }
+    pub fn clone_subtree(&mut self, entity: Entity) -> Option<Entity> { self.ecs.clone_subtree(entity) }
+    pub fn instantiate(&mut self, prefab: &Prefab) -> PrefabInstance { self.ecs.instantiate(prefab) }
+    pub fn instantiate_with(&mut self, prefab: &Prefab, overrides: &PrefabOverrides) -> PrefabInstance {
+        self.ecs.instantiate_with(prefab, overrides)
+    }
+
+    // --- Relation APIs ---
+    pub fn relate<R: 'static + Send + Sync>(&mut self, source: Entity, target: Entity) -> bool { self.ecs.relate::<R>(source, target) }
+    pub fn unrelate<R: 'static + Send + Sync>(&mut self, source: Entity, target: Entity) -> bool { self.ecs.unrelate::<R>(source, target) }
+    pub fn targets_of<R: 'static + Send + Sync>(&self, source: Entity) -> &[Entity] { self.ecs.targets_of::<R>(source) }
+    pub fn sources_of<R: 'static + Send + Sync>(&self, target: Entity) -> &[Entity] { self.ecs.sources_of::<R>(target) }
+
+    // --- Snapshot APIs (editor undo / rollback) ---
+    pub fn register_snapshot<T: 'static + Send + Sync + Clone>(&mut self) -> &mut Self {
+        self.ecs.register_snapshot::<T>();
+        self
+    }
+
+    pub fn register_snapshot_resource<T: 'static + Send + Sync + Clone>(&mut self) -> &mut Self {
+        self.ecs.register_snapshot_resource::<T>();
+        self
+    }
+
+    pub fn snapshot(&self) -> Snapshot { self.ecs.snapshot() }
+
+    pub fn restore(&mut self, snapshot: &Snapshot) { self.ecs.restore(snapshot) }
+
     // 手動でアプリ終了を要求（エディタやテスト用）
     pub fn request_exit(&mut self) {
         self.ecs.insert_resource(AppExit);
@@ -144,3 +268,82 @@ pub fn request_app_exit(ecs: &mut crate::ecs::ecs::Ecs) {
 
 
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Frames(u32);
+    #[derive(Clone, Debug, PartialEq)]
+    struct ParentFrames(u32);
+
+    fn counting() -> App {
+        let mut app = App::new();
+        app.insert_resource(Frames(0)).add_systems(Stage::Update, |ecs: &mut Ecs| {
+            if let Some(f) = ecs.get_resource_mut::<Frames>() { f.0 += 1; }
+        });
+        app
+    }
+
+    fn frames(app: &App) -> u32 { app.resource::<Frames>().map_or(0, |f| f.0) }
+
+    #[test]
+    fn sub_apps_have_their_own_world() {
+        let mut app = counting();
+        let parent_entity = app.spawn_one(7i32);
+        let mut sub = counting();
+        sub.insert_resource(Frames(100));
+        app.insert_sub_app("render", sub);
+        // runs after the parent frame, so it sees this frame's parent state
+        app.set_extract("render", |main, sub| {
+            let f = main.get_resource::<Frames>().unwrap().0;
+            sub.insert_resource(ParentFrames(f));
+        });
+        app.update();
+        app.update();
+        assert_eq!(frames(&app), 2);
+        let sub = app.sub_app("render").unwrap();
+        assert_eq!(frames(sub), 102);
+        assert_eq!(sub.resource::<ParentFrames>(), Some(&ParentFrames(2)));
+        assert!(sub.get_component::<i32>(parent_entity).is_none());
+        assert!(app.resource::<ParentFrames>().is_none());
+        assert_eq!(app.get_component::<i32>(parent_entity), Some(&7));
+    }
+
+    #[test]
+    fn stopped_sub_apps_do_not_run_or_extract() {
+        let mut app = counting();
+        app.insert_sub_app("a", counting()).insert_sub_app("b", counting());
+        let extracted = std::sync::Arc::new(std::sync::atomic::AtomicU32::new(0));
+        let count = extracted.clone();
+        app.set_extract("a", move |_, _| { count.fetch_add(1, std::sync::atomic::Ordering::Relaxed); });
+        app.update();
+        assert!(app.stop_sub_app("a"));
+        assert!(!app.is_sub_app_running("a") && app.is_sub_app_running("b"));
+        app.update();
+        app.update();
+        // its world is kept while stopped
+        assert_eq!(frames(app.sub_app("a").unwrap()), 1);
+        assert_eq!(frames(app.sub_app("b").unwrap()), 3);
+        assert_eq!(extracted.load(std::sync::atomic::Ordering::Relaxed), 1);
+        assert!(app.start_sub_app("a"));
+        app.update();
+        assert_eq!(frames(app.sub_app("a").unwrap()), 2);
+        assert_eq!(extracted.load(std::sync::atomic::Ordering::Relaxed), 2);
+
+        assert!(!app.stop_sub_app("missing") && !app.start_sub_app("missing"));
+        assert!(!app.is_sub_app_running("missing"));
+        assert_eq!(app.sub_app_names(), ["a", "b"]);
+        // replacing one starts the new app, without the old extract step
+        app.stop_sub_app("a");
+        app.insert_sub_app("a", counting());
+        app.update();
+        assert_eq!(frames(app.sub_app("a").unwrap()), 1);
+        assert_eq!(extracted.load(std::sync::atomic::Ordering::Relaxed), 2);
+        assert_eq!(app.sub_app_names(), ["b", "a"]);
+        let removed = app.remove_sub_app("b").unwrap();
+        assert_eq!(frames(&removed), 5);
+        assert!(app.sub_app("b").is_none() && app.remove_sub_app("b").is_none());
+    }
+}
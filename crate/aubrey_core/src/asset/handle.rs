@@ -0,0 +1,56 @@
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::marker::PhantomData;
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+
+/// Identifies one asset slot in an `AssetServer`. Stays the same while any handle is alive.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct AssetId(pub(crate) u64);
+
+// Shared by every clone of a handle; dropping the last clone tells the server.
+pub(crate) struct HandleInner {
+    pub(crate) id: AssetId,
+    pub(crate) path: Arc<str>,
+    pub(crate) drops: Sender<AssetId>,
+}
+
+impl Drop for HandleInner {
+    fn drop(&mut self) { let _ = self.drops.send(self.id); }
+}
+
+/// Reference-counted handle to an asset of type `T`. Cheap to clone; the asset is unloaded
+/// (at the next `AssetServer::update`) once every handle to it has been dropped.
+pub struct Handle<T> {
+    pub(crate) inner: Arc<HandleInner>,
+    _m: PhantomData<fn() -> T>,
+}
+
+impl<T> Handle<T> {
+    pub(crate) fn new(inner: Arc<HandleInner>) -> Self { Self { inner, _m: PhantomData } }
+
+    pub fn id(&self) -> AssetId { self.inner.id }
+
+    /// Normalized VFS path the asset is loaded from.
+    pub fn path(&self) -> &str { &self.inner.path }
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self { Self::new(self.inner.clone()) }
+}
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool { self.inner.id == other.inner.id }
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T> Hash for Handle<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) { self.inner.id.hash(state); }
+}
+
+impl<T> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Handle<{}>({}, {:?})", std::any::type_name::<T>(), self.inner.id.0, self.inner.path)
+    }
+}
@@ -0,0 +1,476 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::fs::{norm, Vfs, VfsError};
+
+use super::loader::{extension_of, BoxError};
+use super::texture::TextureImporter;
+
+/// Import settings from a `.meta` file (`key = value` per line).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ImportSettings {
+    values: BTreeMap<String, String>,
+}
+
+impl ImportSettings {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn with(mut self, key: &str, value: impl ToString) -> Self { self.set(key, value); self }
+
+    pub fn set(&mut self, key: &str, value: impl ToString) { self.values.insert(key.to_string(), value.to_string()); }
+
+    pub fn get(&self, key: &str) -> Option<&str> { self.values.get(key).map(|s| s.as_str()) }
+
+    pub fn str_or<'a>(&'a self, key: &str, default: &'a str) -> &'a str { self.get(key).unwrap_or(default) }
+
+    /// Parsed value, or `default` when missing or unparsable (the latter is logged).
+    pub fn parse_or<T: std::str::FromStr>(&self, key: &str, default: T) -> T {
+        match self.get(key).map(|v| v.parse::<T>()) {
+            Some(Ok(v)) => v,
+            Some(Err(_)) => {
+                log::warn!("import setting {} = {:?} is not valid; using the default", key, self.get(key).unwrap_or_default());
+                default
+            }
+            None => default,
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> + '_ { self.values.iter().map(|(k, v)| (k.as_str(), v.as_str())) }
+}
+
+/// Contents of `<source>.meta`: which importer to use and its settings.
+/// `importer = none` excludes the file from processing.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ImportMeta {
+    pub importer: String,
+    pub settings: ImportSettings,
+}
+
+impl ImportMeta {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut importer = None;
+        let mut settings = ImportSettings::new();
+        for (n, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') { continue; }
+            let Some((k, v)) = line.split_once('=') else { return Err(format!("line {}: expected `key = value`", n + 1)) };
+            let (k, v) = (k.trim(), v.trim());
+            if k == "importer" { importer = Some(v.to_string()); } else { settings.set(k, v); }
+        }
+        Ok(Self { importer: importer.ok_or("missing `importer`")?, settings })
+    }
+
+    /// Canonical text (sorted keys), also what the cache key is computed from.
+    pub fn to_text(&self) -> String {
+        let mut out = format!("importer = {}\n", self.importer);
+        for (k, v) in self.settings.iter() { out.push_str(&format!("{} = {}\n", k, v)); }
+        out
+    }
+}
+
+/// Converts a source file into an engine-ready format, once, offline.
+/// The output is loaded at runtime with an `AssetLoader` for `output_extension`.
+pub trait Importer: Send + Sync + 'static {
+    /// Name used in meta files, e.g. `"texture"`.
+    fn name(&self) -> &str;
+    /// Source extensions (lowercase, no dot) this importer is picked for by default.
+    fn extensions(&self) -> &[&str];
+    fn output_extension(&self) -> &str;
+    /// Bump when the output changes for the same input; invalidates cached outputs.
+    fn version(&self) -> u32 { 1 }
+    /// Written to new meta files.
+    fn default_settings(&self) -> ImportSettings { ImportSettings::new() }
+    fn import(&self, path: &str, source: &[u8], settings: &ImportSettings) -> Result<Vec<u8>, BoxError>;
+}
+
+/// Normalizes `.scene` text: strips a UTF-8 BOM, converts CRLF to LF and rejects invalid UTF-8.
+pub struct TextImporter;
+
+impl Importer for TextImporter {
+    fn name(&self) -> &str { "text" }
+    fn extensions(&self) -> &[&str] { &["scene"] }
+    fn output_extension(&self) -> &str { "scene" }
+    fn import(&self, _path: &str, source: &[u8], _settings: &ImportSettings) -> Result<Vec<u8>, BoxError> {
+        let text = std::str::from_utf8(source)?;
+        let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+        Ok(text.replace("\r\n", "\n").into_bytes())
+    }
+}
+
+#[derive(Debug)]
+pub enum ImportError {
+    /// No importer for the file's extension, or the meta names an unknown one.
+    NoImporter(String),
+    Meta { path: String, message: String },
+    Vfs(VfsError),
+    Import { path: String, source: BoxError },
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::NoImporter(p) => write!(f, "no importer for: {}", p),
+            ImportError::Meta { path, message } => write!(f, "{}: {}", path, message),
+            ImportError::Vfs(e) => write!(f, "{}", e),
+            ImportError::Import { path, source } => write!(f, "{}: {}", path, source),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ImportError::Vfs(e) => Some(e),
+            ImportError::Import { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<VfsError> for ImportError {
+    fn from(e: VfsError) -> Self { ImportError::Vfs(e) }
+}
+
+/// Result of processing one source file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Processed {
+    pub source: String,
+    /// Output path in the cache directory.
+    pub output: String,
+    /// True if the output already existed for this content and settings.
+    pub cached: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct ProcessReport {
+    pub imported: Vec<Processed>,
+    pub cached: Vec<Processed>,
+    pub failed: Vec<(String, ImportError)>,
+    /// Cache files no longer referenced by the manifest that were deleted.
+    pub removed: usize,
+}
+
+/// Source path -> processed output, stored as `<cache_dir>/manifest` (`source<TAB>output` lines).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ImportManifest {
+    entries: BTreeMap<String, String>,
+}
+
+impl ImportManifest {
+    pub fn path(cache_dir: &str) -> String { format!("{}/manifest", norm(cache_dir).trim_end_matches('/')) }
+
+    /// Empty if the manifest doesn't exist yet.
+    pub fn read(vfs: &Vfs, cache_dir: &str) -> Result<Self, VfsError> {
+        let path = Self::path(cache_dir);
+        let bytes = match vfs.read(&path) {
+            Ok(b) => b,
+            Err(e) if e.is_not_found() => return Ok(Self::default()),
+            Err(e) => return Err(e),
+        };
+        let text = String::from_utf8_lossy(&bytes);
+        let entries = text.lines().filter_map(|l| l.split_once('\t')).map(|(s, o)| (s.to_string(), o.to_string())).collect();
+        Ok(Self { entries })
+    }
+
+    pub fn write(&self, vfs: &mut Vfs, cache_dir: &str) -> Result<(), VfsError> {
+        let text: String = self.entries.iter().map(|(s, o)| format!("{}\t{}\n", s, o)).collect();
+        vfs.write(&Self::path(cache_dir), text.as_bytes())
+    }
+
+    /// Processed output for a source path.
+    pub fn output_for(&self, source: &str) -> Option<&str> { self.entries.get(&norm(source)).map(|s| s.as_str()) }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> + '_ { self.entries.iter().map(|(s, o)| (s.as_str(), o.as_str())) }
+
+    pub fn len(&self) -> usize { self.entries.len() }
+
+    pub fn is_empty(&self) -> bool { self.entries.is_empty() }
+}
+
+// 64-bit FNV-1a, stable across builds and platforms
+fn fnv1a(hash: u64, data: &[u8]) -> u64 {
+    data.iter().fold(hash, |h, b| (h ^ *b as u64).wrapping_mul(0x0000_0100_0000_01b3))
+}
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// Runs importers over source files and caches their outputs by content hash.
+///
+/// For each source the importer comes from `<source>.meta` (created with the importer's
+/// default settings when missing), and the output is stored as
+/// `<cache_dir>/<hash>.<ext>`, where the hash covers the source bytes, the meta file and
+/// the importer version. Unchanged sources are not imported again.
+/// ```ignore
+/// let processor = AssetProcessor::new("/.cache/imported").with_importer(FontAtlasImporter).ignore("target");
+/// let report = processor.process_all(&mut vfs, "/");
+/// let manifest = ImportManifest::read(&vfs, "/.cache/imported")?;
+/// let atlas: Handle<GlyphAtlas> = assets.load(manifest.output_for("/fonts/ui.ttf").unwrap());
+/// ```
+pub struct AssetProcessor {
+    importers: Vec<Box<dyn Importer>>,
+    cache_dir: String,
+    ignore: Vec<String>,
+    force: bool,
+    write_meta: bool,
+}
+
+impl AssetProcessor {
+    /// With the built-in `TextureImporter` and `TextImporter`.
+    pub fn new(cache_dir: &str) -> Self {
+        Self { importers: Vec::new(), cache_dir: norm(cache_dir), ignore: Vec::new(), force: false, write_meta: true }
+            .with_importer(TextureImporter)
+            .with_importer(TextImporter)
+    }
+
+    /// Add an importer; a later one with the same name replaces the earlier one.
+    pub fn with_importer(mut self, importer: impl Importer) -> Self {
+        self.importers.retain(|i| i.name() != importer.name());
+        self.importers.push(Box::new(importer));
+        self
+    }
+
+    /// Skip entries with this name (at any depth) in `process_all`.
+    pub fn ignore(mut self, name: &str) -> Self { self.ignore.push(name.to_string()); self }
+
+    /// Import even if a cached output exists.
+    pub fn force(mut self, force: bool) -> Self { self.force = force; self }
+
+    /// Write default `.meta` files for sources that have none (default: on).
+    pub fn write_meta(mut self, write: bool) -> Self { self.write_meta = write; self }
+
+    pub fn cache_dir(&self) -> &str { &self.cache_dir }
+
+    fn importer_named(&self, name: &str) -> Option<&dyn Importer> {
+        self.importers.iter().find(|i| i.name() == name).map(|i| i.as_ref())
+    }
+
+    fn importer_for_ext(&self, path: &str) -> Option<&dyn Importer> {
+        let ext = extension_of(path);
+        self.importers.iter().find(|i| i.extensions().iter().any(|e| e.eq_ignore_ascii_case(&ext))).map(|i| i.as_ref())
+    }
+
+    /// Meta for `source`: from its `.meta` file, or the default for its extension.
+    /// `Ok(None)` if the file isn't processed (no importer, or `importer = none`).
+    pub fn meta_for(&self, vfs: &Vfs, source: &str) -> Result<Option<ImportMeta>, ImportError> {
+        let meta_path = format!("{}.meta", norm(source));
+        match vfs.read(&meta_path) {
+            Ok(bytes) => {
+                let meta = ImportMeta::parse(&String::from_utf8_lossy(&bytes)).map_err(|message| ImportError::Meta { path: meta_path, message })?;
+                if meta.importer == "none" { return Ok(None); }
+                Ok(Some(meta))
+            }
+            Err(e) if e.is_not_found() => {
+                Ok(self.importer_for_ext(source).map(|i| ImportMeta { importer: i.name().to_string(), settings: i.default_settings() }))
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Import one source file (or reuse its cached output).
+    pub fn process(&self, vfs: &mut Vfs, source: &str) -> Result<Processed, ImportError> {
+        let source = norm(source);
+        let meta = self.meta_for(vfs, &source)?.ok_or_else(|| ImportError::NoImporter(source.clone()))?;
+        let importer = self.importer_named(&meta.importer).ok_or_else(|| ImportError::NoImporter(format!("{} (importer `{}`)", source, meta.importer)))?;
+        let meta_path = format!("{}.meta", source);
+        if self.write_meta && !vfs.exists(&meta_path) && let Err(e) = vfs.write(&meta_path, meta.to_text().as_bytes()) {
+            log::warn!("could not write {}: {}", meta_path, e);
+        }
+        let data = vfs.read(&source)?;
+        let mut hash = fnv1a(FNV_OFFSET, importer.name().as_bytes());
+        hash = fnv1a(hash, &importer.version().to_le_bytes());
+        hash = fnv1a(hash, meta.to_text().as_bytes());
+        hash = fnv1a(hash, &data);
+        let output = format!("{}/{:016x}.{}", self.cache_dir.trim_end_matches('/'), hash, importer.output_extension());
+        if !self.force && vfs.is_file(&output) {
+            return Ok(Processed { source, output, cached: true });
+        }
+        let bytes = importer.import(&source, &data, &meta.settings).map_err(|e| ImportError::Import { path: source.clone(), source: e })?;
+        vfs.create_dir_all(&self.cache_dir)?;
+        vfs.write(&output, &bytes)?;
+        Ok(Processed { source, output, cached: false })
+    }
+
+    /// Process every file under `root` that has an importer, update the manifest and delete
+    /// cache files nothing refers to anymore.
+    pub fn process_all(&self, vfs: &mut Vfs, root: &str) -> ProcessReport {
+        let mut report = ProcessReport::default();
+        let root = norm(root);
+        let mut manifest = match ImportManifest::read(vfs, &self.cache_dir) {
+            Ok(m) => m,
+            Err(e) => {
+                report.failed.push((ImportManifest::path(&self.cache_dir), e.into()));
+                return report;
+            }
+        };
+        let sources = match self.sources(vfs, &root) {
+            Ok(s) => s,
+            Err(e) => {
+                report.failed.push((root, e.into()));
+                return report;
+            }
+        };
+        // sources under root that are gone or no longer processed drop out of the manifest
+        let prefix = if root == "/" { "/".to_string() } else { format!("{}/", root) };
+        manifest.entries.retain(|s, _| !(s == &root || s.starts_with(&prefix)));
+        for source in sources {
+            match self.meta_for(vfs, &source) {
+                Ok(None) => continue,
+                Ok(Some(_)) => {}
+                Err(e) => { report.failed.push((source, e)); continue; }
+            }
+            match self.process(vfs, &source) {
+                Ok(p) => {
+                    manifest.entries.insert(p.source.clone(), p.output.clone());
+                    if p.cached { report.cached.push(p) } else { report.imported.push(p) }
+                }
+                Err(e) => report.failed.push((source, e)),
+            }
+        }
+        if let Err(e) = vfs.create_dir_all(&self.cache_dir).and_then(|_| manifest.write(vfs, &self.cache_dir)) {
+            report.failed.push((ImportManifest::path(&self.cache_dir), e.into()));
+            return report;
+        }
+        // garbage-collect outputs of old versions
+        let manifest_path = ImportManifest::path(&self.cache_dir);
+        let live: std::collections::HashSet<&str> = manifest.entries.values().map(|s| s.as_str()).collect();
+        for name in vfs.list(&self.cache_dir).unwrap_or_default() {
+            let path = format!("{}/{}", self.cache_dir.trim_end_matches('/'), name);
+            if path == manifest_path || live.contains(path.as_str()) || !vfs.is_file(&path) { continue; }
+            match vfs.remove(&path) {
+                Ok(()) => report.removed += 1,
+                Err(e) => log::warn!("could not remove stale import {}: {}", path, e),
+            }
+        }
+        report
+    }
+
+    // Files under `root`, skipping the cache directory, meta files and ignored names.
+    fn sources(&self, vfs: &Vfs, root: &str) -> Result<Vec<String>, VfsError> {
+        let mut out = Vec::new();
+        let mut stack = vec![root.to_string()];
+        while let Some(dir) = stack.pop() {
+            for name in vfs.list(&dir)? {
+                if self.ignore.contains(&name) { continue; }
+                let path = if dir == "/" { format!("/{}", name) } else { format!("{}/{}", dir, name) };
+                if path == self.cache_dir { continue; }
+                if vfs.is_dir(&path) { stack.push(path); } else if !name.ends_with(".meta") { out.push(path); }
+            }
+        }
+        out.sort();
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset::Texture;
+    use crate::fs::MemBackend;
+    use crate::image::{png, RgbaImage};
+
+    fn vfs_with(files: &[(&str, &[u8])]) -> Vfs {
+        let mut vfs = Vfs::new();
+        vfs.mount("/", Box::new(MemBackend::new()));
+        for (path, data) in files {
+            if let Some((dir, _)) = path.rsplit_once('/') && !dir.is_empty() { vfs.create_dir_all(dir).unwrap(); }
+            vfs.write(path, data).unwrap();
+        }
+        vfs
+    }
+
+    fn half_transparent_png() -> Vec<u8> {
+        let mut img = RgbaImage::new(1, 1);
+        img.pixels.copy_from_slice(&[200, 100, 50, 128]);
+        png::encode(&img)
+    }
+
+    #[test]
+    fn cache_is_keyed_by_content() {
+        let mut vfs = vfs_with(&[("/a.scene", b"x\r\n"), ("/sub/b.scene", b"x\r\n"), ("/c.scene", b"y")]);
+        let processor = AssetProcessor::new("/.cache");
+        let a = processor.process(&mut vfs, "/a.scene").unwrap();
+        assert!(!a.cached);
+        assert_eq!(vfs.read(&a.output).unwrap(), b"x\n");
+        // same bytes elsewhere share the output; other bytes don't
+        let b = processor.process(&mut vfs, "/sub/b.scene").unwrap();
+        assert!(b.cached);
+        assert_eq!(b.output, a.output);
+        let c = processor.process(&mut vfs, "/c.scene").unwrap();
+        assert_ne!(c.output, a.output);
+        assert!(c.output.starts_with("/.cache/") && c.output.ends_with(".scene"));
+
+        assert!(processor.process(&mut vfs, "/a.scene").unwrap().cached);
+        vfs.write("/a.scene", b"z").unwrap();
+        let changed = processor.process(&mut vfs, "/a.scene").unwrap();
+        assert!(!changed.cached);
+        assert_ne!(changed.output, a.output);
+        // forced imports rewrite the same output
+        let forced = AssetProcessor::new("/.cache").force(true).process(&mut vfs, "/a.scene").unwrap();
+        assert!(!forced.cached);
+        assert_eq!(forced.output, changed.output);
+    }
+
+    #[test]
+    fn meta_settings_are_honoured() {
+        let mut vfs = vfs_with(&[("/t.png", &half_transparent_png())]);
+        let processor = AssetProcessor::new("/.cache");
+        let default = processor.process(&mut vfs, "/t.png").unwrap();
+        assert_eq!(vfs.read("/t.png.meta").unwrap(), b"importer = texture\npremultiply = true\n");
+        let tex = Texture::decode(&vfs.read(&default.output).unwrap()).unwrap();
+        assert!(tex.premultiplied);
+        assert_eq!(tex.image.pixel(0, 0), [100, 50, 25, 128]);
+
+        // a changed setting is a different output
+        vfs.write("/t.png.meta", b"importer = texture\npremultiply = false\n").unwrap();
+        let straight = processor.process(&mut vfs, "/t.png").unwrap();
+        assert!(!straight.cached);
+        assert_ne!(straight.output, default.output);
+        let tex = Texture::decode(&vfs.read(&straight.output).unwrap()).unwrap();
+        assert!(!tex.premultiplied);
+        assert_eq!(tex.image.pixel(0, 0), [200, 100, 50, 128]);
+        // formatting and comments don't matter, only the canonical text
+        vfs.write("/t.png.meta", b"# keep straight alpha\npremultiply=false\nimporter=texture\n").unwrap();
+        assert_eq!(processor.process(&mut vfs, "/t.png").unwrap(), Processed { cached: true, ..straight });
+
+        // the meta picks the importer
+        vfs.write("/t.png.meta", b"importer = text\n").unwrap();
+        assert!(matches!(processor.process(&mut vfs, "/t.png"), Err(ImportError::Import { .. })));
+        vfs.write("/t.png.meta", b"importer = nope\n").unwrap();
+        assert!(matches!(processor.process(&mut vfs, "/t.png"), Err(ImportError::NoImporter(_))));
+        vfs.write("/t.png.meta", b"premultiply = false\n").unwrap();
+        assert!(matches!(processor.process(&mut vfs, "/t.png"), Err(ImportError::Meta { .. })));
+        vfs.write("/t.png.meta", b"importer = none\n").unwrap();
+        assert_eq!(processor.meta_for(&vfs, "/t.png").unwrap(), None);
+    }
+
+    #[test]
+    fn process_all_writes_the_manifest_and_removes_stale_outputs() {
+        let mut vfs = vfs_with(&[("/a.scene", b"one"), ("/skip/b.scene", b"two"), ("/off.scene", b"three"), ("/off.scene.meta", b"importer = none\n"), ("/notes.txt", b"-")]);
+        let processor = AssetProcessor::new("/.cache").ignore("skip");
+        let report = processor.process_all(&mut vfs, "/");
+        assert!(report.failed.is_empty());
+        assert_eq!(report.imported.len(), 1);
+        let manifest = ImportManifest::read(&vfs, "/.cache").unwrap();
+        assert_eq!(manifest.len(), 1);
+        let first = manifest.output_for("/a.scene").unwrap().to_string();
+
+        let report = processor.process_all(&mut vfs, "/");
+        assert_eq!((report.imported.len(), report.cached.len(), report.removed), (0, 1, 0));
+        vfs.write("/a.scene", b"uno").unwrap();
+        let report = processor.process_all(&mut vfs, "/");
+        assert_eq!((report.imported.len(), report.removed), (1, 1));
+        assert!(!vfs.exists(&first));
+        assert_ne!(ImportManifest::read(&vfs, "/.cache").unwrap().output_for("/a.scene").unwrap(), first);
+    }
+
+    #[test]
+    fn meta_parse_errors() {
+        assert_eq!(ImportMeta::parse("importer = texture\nsize 3\n"), Err("line 2: expected `key = value`".to_string()));
+        assert_eq!(ImportMeta::parse("a = 1\n"), Err("missing `importer`".to_string()));
+        let meta = ImportMeta::parse("b = 2\nimporter = x\na = = 1\n").unwrap();
+        assert_eq!(meta.settings.get("a"), Some("= 1"));
+        assert_eq!(meta.to_text(), "importer = x\na = = 1\nb = 2\n");
+        assert_eq!(meta.settings.parse_or("b", 0u32), 2);
+        assert_eq!(meta.settings.parse_or("a", 7u32), 7);
+    }
+}
@@ -0,0 +1,129 @@
+use std::any::{Any, TypeId};
+use std::sync::Arc;
+
+use crate::fs::{norm, parent_of, split_scheme};
+
+use super::handle::HandleInner;
+use super::server::AssetServer;
+
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+pub(crate) type RequestDependency = Box<dyn FnOnce(&mut AssetServer) -> Arc<HandleInner> + Send>;
+
+// Declared by a loader; applied by the server when the load finishes.
+pub(crate) enum Dependency {
+    /// Plain file: changes to it reload the dependent.
+    File(String),
+    /// Another asset, loaded (and kept alive) for as long as the dependent is.
+    Asset(String, RequestDependency),
+}
+
+impl Dependency {
+    pub(crate) fn path(&self) -> &str {
+        match self { Dependency::File(p) | Dependency::Asset(p, _) => p }
+    }
+}
+
+/// What a loader gets to work with. Loaders run on worker threads.
+pub struct LoadContext<'a> {
+    pub(crate) path: &'a str,
+    pub(crate) bytes: &'a Arc<[u8]>,
+    pub(crate) dependencies: Vec<Dependency>,
+}
+
+impl LoadContext<'_> {
+    /// Normalized VFS path being loaded, as passed to `AssetServer::load` (possibly a `scheme://` URI).
+    pub fn path(&self) -> &str { self.path }
+
+    /// Lowercase extension without the dot ("" if none).
+    pub fn extension(&self) -> String { extension_of(self.path) }
+
+    pub fn bytes(&self) -> &[u8] { self.bytes }
+
+    /// The file contents without copying, for assets that keep the raw data.
+    pub fn shared_bytes(&self) -> Arc<[u8]> { self.bytes.clone() }
+
+    /// Absolute path for a reference found in the file; relative ones are taken from the
+    /// directory of the asset being loaded (keeping its `scheme://`, if any).
+    /// `..` goes up one directory (but never above `/` or the scheme root).
+    pub fn resolve(&self, path: &str) -> String {
+        let (scheme, base, path) = match split_scheme(path) {
+            (Some(scheme), rest) => (Some(scheme), "", rest),
+            (None, p) if p.starts_with('/') => (None, "", p),
+            (None, p) => {
+                let (scheme, dir) = split_scheme(parent_of(self.path));
+                (scheme, dir, p)
+            }
+        };
+        let mut parts: Vec<&str> = Vec::new();
+        for part in base.split('/').chain(path.split('/')) {
+            match part {
+                "" | "." => {}
+                ".." => { parts.pop(); }
+                p => parts.push(p),
+            }
+        }
+        match scheme {
+            Some(scheme) => format!("{}://{}", scheme, parts.join("/")),
+            None => norm(&parts.join("/")),
+        }
+    }
+
+    /// Reload this asset whenever the file at `path` changes. Returns the resolved path.
+    pub fn add_dependency(&mut self, path: &str) -> String {
+        let path = self.resolve(path);
+        self.dependencies.push(Dependency::File(path.clone()));
+        path
+    }
+
+    /// Load the asset at `path` as `T` as well and keep it loaded while this one is; this asset
+    /// is reloaded whenever that one is. Returns the resolved path, for `AssetServer::get_handle`.
+    /// A dependency that leads back to this asset (a cycle) still triggers reloads but is not kept loaded.
+    pub fn load_dependency<T: Send + Sync + 'static>(&mut self, path: &str) -> String {
+        let path = self.resolve(path);
+        let p = path.clone();
+        self.dependencies.push(Dependency::Asset(path.clone(), Box::new(move |server: &mut AssetServer| server.load::<T>(&p).inner)));
+        path
+    }
+}
+
+/// Turns file contents into an asset. Registered per extension with `AssetServer::add_loader`;
+/// `AssetServer::load::<T>` picks the loader whose `Asset` is `T` for the path's extension.
+/// ```ignore
+/// struct TextLoader;
+/// impl AssetLoader for TextLoader {
+///     type Asset = String;
+///     fn extensions(&self) -> &[&str] { &["txt", "json"] }
+///     fn load(&self, ctx: &mut LoadContext) -> Result<String, BoxError> { Ok(String::from_utf8(ctx.bytes().to_vec())?) }
+/// }
+/// ```
+pub trait AssetLoader: Send + Sync + 'static {
+    type Asset: Send + Sync + 'static;
+    /// Lowercase extensions without the dot.
+    fn extensions(&self) -> &[&str];
+    fn load(&self, ctx: &mut LoadContext) -> Result<Self::Asset, BoxError>;
+}
+
+pub(crate) type ErasedAsset = Box<dyn Any + Send + Sync>;
+
+pub(crate) trait ErasedLoader: Send + Sync {
+    fn asset_type(&self) -> TypeId;
+    fn extensions(&self) -> Vec<String>;
+    fn load_erased(&self, ctx: &mut LoadContext) -> Result<ErasedAsset, BoxError>;
+}
+
+impl<L: AssetLoader> ErasedLoader for L {
+    fn asset_type(&self) -> TypeId { TypeId::of::<L::Asset>() }
+    fn extensions(&self) -> Vec<String> { AssetLoader::extensions(self).iter().map(|e| e.to_ascii_lowercase()).collect() }
+    fn load_erased(&self, ctx: &mut LoadContext) -> Result<ErasedAsset, BoxError> {
+        Ok(Box::new(self.load(ctx)?))
+    }
+}
+
+pub(crate) fn extension_of(path: &str) -> String {
+    let name = &path[path.rfind('/').map(|i| i + 1).unwrap_or(0)..];
+    match name.rfind('.') {
+        Some(i) if i > 0 => name[i + 1..].to_ascii_lowercase(),
+        _ => String::new(),
+    }
+}
@@ -0,0 +1,115 @@
+use std::fmt;
+
+use crate::app::App;
+use crate::ecs::{Ecs, EventCursor, Events, Stage};
+use crate::fs::{Vfs, VfsError, VfsEvent};
+
+pub mod handle;
+pub mod loader;
+pub mod server;
+pub mod import;
+pub mod texture;
+
+pub use handle::{AssetId, Handle};
+pub use loader::{AssetLoader, BoxError, LoadContext};
+pub use server::AssetServer;
+pub use import::{AssetProcessor, ImportError, ImportManifest, ImportMeta, ImportSettings, Importer, ProcessReport, Processed, TextImporter};
+pub use texture::{Texture, TextureImporter, TextureLoader};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LoadState {
+    /// Queued, or not tracked by the server.
+    NotLoaded,
+    /// Being decoded on a worker thread.
+    Loading,
+    Loaded,
+    /// See `AssetServer::error`.
+    Failed,
+}
+
+/// Sent as `Events<AssetEvent>` by the asset system (`asset::register`).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum AssetEvent {
+    /// Loaded for the first time.
+    Loaded(AssetId),
+    /// Reloaded because its file or something it depends on changed.
+    Modified(AssetId),
+    /// Unloaded after its last handle was dropped.
+    Removed(AssetId),
+}
+
+impl AssetEvent {
+    pub fn id(&self) -> AssetId {
+        match self { AssetEvent::Loaded(id) | AssetEvent::Modified(id) | AssetEvent::Removed(id) => *id }
+    }
+
+    pub fn is<T>(&self, handle: &Handle<T>) -> bool { self.id() == handle.id() }
+}
+
+#[derive(Debug)]
+pub enum AssetError {
+    /// No loader registered for the requested type and the path's extension.
+    NoLoader(String),
+    Vfs(VfsError),
+    /// The loader rejected the data.
+    Load { path: String, source: BoxError },
+}
+
+impl AssetError {
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            AssetError::NoLoader(p) | AssetError::Load { path: p, .. } => Some(p),
+            AssetError::Vfs(e) => e.path(),
+        }
+    }
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssetError::NoLoader(p) => write!(f, "no asset loader for: {}", p),
+            AssetError::Vfs(e) => write!(f, "{}", e),
+            AssetError::Load { path, source } => write!(f, "{}: {}", path, source),
+        }
+    }
+}
+
+impl std::error::Error for AssetError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            AssetError::NoLoader(_) => None,
+            AssetError::Vfs(e) => Some(e),
+            AssetError::Load { source, .. } => Some(source.as_ref()),
+        }
+    }
+}
+
+/// Insert an `AssetServer` (unless one exists) and drive it from `Stage::PreUpdate`
+/// using the `Vfs` resource. Changes reported as `Events<VfsEvent>` (see `fs::watch::register`)
+/// reload the affected assets. Safe to call more than once.
+pub fn register(app: &mut App) {
+    if app.resource::<AssetServer>().is_none() { app.insert_resource(AssetServer::new()); }
+    let Some(server) = app.resource_mut::<AssetServer>() else { return };
+    if server.system_added { return; }
+    server.system_added = true;
+    app.add_event::<AssetEvent>();
+    let mut changes = EventCursor::<VfsEvent>::default();
+    app.add_systems_ordered(Stage::PreUpdate, i32::MIN, move |ecs: &mut Ecs| update_assets(ecs, &mut changes));
+}
+
+fn update_assets(ecs: &mut Ecs, changes: &mut EventCursor<VfsEvent>) {
+    // taken out so the server and the Vfs can be borrowed together
+    let Some(mut server) = ecs.remove_resource::<AssetServer>() else { return };
+    if let Some(events) = ecs.get_resource::<Events<VfsEvent>>() {
+        let vfs = ecs.get_resource::<Vfs>();
+        for ev in changes.read(events) {
+            server.file_changed(ev);
+            // assets loaded through a `scheme://` URI are keyed by it
+            for uri in vfs.map(|v| v.uris_for(&ev.path)).unwrap_or_default() { server.file_changed(&VfsEvent::new(ev.kind, uri)); }
+        }
+    }
+    if let Some(vfs) = ecs.get_resource::<Vfs>() { server.update(vfs); }
+    let events = server.drain_events();
+    ecs.insert_resource(server);
+    if let Some(out) = ecs.get_resource_mut::<Events<AssetEvent>>() { out.extend(events); }
+}
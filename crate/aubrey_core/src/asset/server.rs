@@ -0,0 +1,687 @@
+use std::any::TypeId;
+use std::collections::{HashMap, HashSet};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex, Weak};
+use std::thread::JoinHandle;
+
+use crate::fs::{norm, Vfs, VfsEvent, VfsEventKind};
+
+use super::handle::{AssetId, Handle, HandleInner};
+use super::loader::{extension_of, AssetLoader, Dependency, ErasedAsset, ErasedLoader, LoadContext};
+use super::{AssetError, AssetEvent, LoadState};
+
+struct Entry {
+    path: Arc<str>,
+    type_id: TypeId,
+    handle: Weak<HandleInner>,
+    state: LoadState,
+    asset: Option<ErasedAsset>,
+    error: Option<AssetError>,
+    loader: Option<Arc<dyn ErasedLoader>>,
+    // paths declared by the loader on the last successful load
+    dependencies: Vec<Arc<str>>,
+    // keeps asset dependencies loaded
+    dependency_handles: Vec<Arc<HandleInner>>,
+}
+
+struct Job {
+    id: AssetId,
+    path: Arc<str>,
+    bytes: Arc<[u8]>,
+    loader: Arc<dyn ErasedLoader>,
+}
+
+type Loaded = (ErasedAsset, Vec<Dependency>);
+type Done = (AssetId, Result<Loaded, AssetError>);
+
+// Fixed pool of decode threads; exits when the job sender is dropped.
+struct Workers {
+    jobs: Option<Sender<Job>>,
+    done: Mutex<Receiver<Done>>,
+    threads: Vec<JoinHandle<()>>,
+}
+
+impl Workers {
+    fn spawn(count: usize) -> Self {
+        let (jobs, job_rx) = channel::<Job>();
+        let (done_tx, done) = channel::<Done>();
+        let job_rx = Arc::new(Mutex::new(job_rx));
+        let threads = (0..count)
+            .map(|i| {
+                let job_rx = job_rx.clone();
+                let done_tx = done_tx.clone();
+                std::thread::Builder::new()
+                    .name(format!("aubrey-asset-{}", i))
+                    .spawn(move || loop {
+                        let job = match job_rx.lock() { Ok(rx) => rx.recv(), Err(_) => return };
+                        let Ok(job) = job else { return };
+                        let res = run_loader(&*job.loader, &job.path, &job.bytes);
+                        if done_tx.send((job.id, res)).is_err() { return; }
+                    })
+                    .expect("failed to spawn asset worker")
+            })
+            .collect();
+        Self { jobs: Some(jobs), done: Mutex::new(done), threads }
+    }
+}
+
+impl Drop for Workers {
+    fn drop(&mut self) {
+        self.jobs = None;
+        for t in self.threads.drain(..) { let _ = t.join(); }
+    }
+}
+
+fn run_loader(loader: &dyn ErasedLoader, path: &str, bytes: &Arc<[u8]>) -> Result<Loaded, AssetError> {
+    let mut ctx = LoadContext { path, bytes, dependencies: Vec::new() };
+    match catch_unwind(AssertUnwindSafe(|| loader.load_erased(&mut ctx))) {
+        Ok(Ok(asset)) => Ok((asset, ctx.dependencies)),
+        Ok(Err(source)) => Err(AssetError::Load { path: path.to_string(), source }),
+        Err(_) => Err(AssetError::Load { path: path.to_string(), source: "loader panicked".into() }),
+    }
+}
+
+/// Loads assets from the `Vfs` resource into typed, reference-counted handles.
+///
+/// `load` only queues the request and returns at once; the next `update` (run in
+/// `Stage::PreUpdate` by `asset::register`) reads the file and hands it to a worker thread
+/// for decoding. Loading the same path as the same type again returns the same asset.
+/// ```ignore
+/// let font: Handle<FontAsset> = app.resource_mut::<AssetServer>().unwrap().load("/fonts/ui.ttf");
+/// // later
+/// if let Some(f) = assets.get(&font) { /* ... */ }
+/// ```
+pub struct AssetServer {
+    next_id: u64,
+    entries: HashMap<AssetId, Entry>,
+    by_path: HashMap<(TypeId, Arc<str>), AssetId>,
+    // (asset type, extension) -> loader
+    loaders: HashMap<(TypeId, String), Arc<dyn ErasedLoader>>,
+    // path -> assets that declared it as a dependency
+    dependents: HashMap<Arc<str>, HashSet<AssetId>>,
+    queue: Vec<AssetId>,
+    events: Vec<AssetEvent>,
+    drops: Sender<AssetId>,
+    dropped: Mutex<Receiver<AssetId>>,
+    worker_count: usize,
+    workers: Option<Workers>,
+    pub(crate) system_added: bool,
+}
+
+impl Default for AssetServer {
+    fn default() -> Self { Self::new() }
+}
+
+impl AssetServer {
+    /// Uses up to 4 worker threads (fewer on small machines).
+    pub fn new() -> Self {
+        let workers = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1).clamp(1, 4);
+        Self::with_workers(workers)
+    }
+
+    /// `workers == 0` decodes on the calling thread inside `update`.
+    pub fn with_workers(workers: usize) -> Self {
+        let (drops, dropped) = channel();
+        Self {
+            next_id: 0,
+            entries: HashMap::new(),
+            by_path: HashMap::new(),
+            loaders: HashMap::new(),
+            dependents: HashMap::new(),
+            queue: Vec::new(),
+            events: Vec::new(),
+            drops,
+            dropped: Mutex::new(dropped),
+            worker_count: workers,
+            workers: None,
+            system_added: false,
+        }
+    }
+
+    /// Register a loader for its extensions; replaces an earlier loader of the same asset type
+    /// and extension.
+    pub fn add_loader<L: AssetLoader>(&mut self, loader: L) -> &mut Self {
+        let loader: Arc<dyn ErasedLoader> = Arc::new(loader);
+        for ext in loader.extensions() { self.loaders.insert((loader.asset_type(), ext), loader.clone()); }
+        self
+    }
+
+    pub fn has_loader<T: 'static>(&self, path: &str) -> bool {
+        self.loaders.contains_key(&(TypeId::of::<T>(), extension_of(path)))
+    }
+
+    /// Handle to the asset at `path`, queueing the load if it isn't loaded (or loading) already.
+    /// Without a loader for the extension the handle is `LoadState::Failed` right away.
+    pub fn load<T: Send + Sync + 'static>(&mut self, path: &str) -> Handle<T> {
+        let path: Arc<str> = Arc::from(norm(path));
+        let key = (TypeId::of::<T>(), path.clone());
+        if let Some(&id) = self.by_path.get(&key) {
+            let entry = self.entries.get_mut(&id).expect("asset entry for path");
+            if let Some(inner) = entry.handle.upgrade() { return Handle::new(inner); }
+            // last handle dropped but not processed yet: keep the slot (and the asset)
+            let inner = Arc::new(HandleInner { id, path, drops: self.drops.clone() });
+            entry.handle = Arc::downgrade(&inner);
+            return Handle::new(inner);
+        }
+        let id = AssetId(self.next_id);
+        self.next_id += 1;
+        let inner = Arc::new(HandleInner { id, path: path.clone(), drops: self.drops.clone() });
+        let loader = self.loaders.get(&(TypeId::of::<T>(), extension_of(&path))).cloned();
+        let mut entry = Entry {
+            path: path.clone(),
+            type_id: TypeId::of::<T>(),
+            handle: Arc::downgrade(&inner),
+            state: LoadState::NotLoaded,
+            asset: None,
+            error: None,
+            loader,
+            dependencies: Vec::new(),
+            dependency_handles: Vec::new(),
+        };
+        if entry.loader.is_none() {
+            entry.state = LoadState::Failed;
+            entry.error = Some(AssetError::NoLoader(path.to_string()));
+        } else {
+            self.queue.push(id);
+        }
+        self.entries.insert(id, entry);
+        self.by_path.insert(key, id);
+        Handle::new(inner)
+    }
+
+    /// Like `load`, but reads and decodes on the calling thread if the asset isn't available yet.
+    pub fn load_blocking<T: Send + Sync + 'static>(&mut self, vfs: &Vfs, path: &str) -> Handle<T> {
+        let handle = self.load::<T>(path);
+        let id = handle.id();
+        if self.entries.get(&id).is_some_and(|e| e.state == LoadState::NotLoaded) {
+            self.queue.retain(|q| *q != id);
+            if let Some(bytes) = self.read(vfs, id) {
+                let entry = &self.entries[&id];
+                let loader = entry.loader.clone().expect("queued asset has a loader");
+                let res = run_loader(&*loader, &entry.path, &bytes);
+                self.finish(id, res);
+            }
+        }
+        handle
+    }
+
+    /// The asset, once loaded.
+    pub fn get<T: 'static>(&self, handle: &Handle<T>) -> Option<&T> {
+        self.entries.get(&handle.id())?.asset.as_ref()?.downcast_ref::<T>()
+    }
+
+    pub fn get_mut<T: 'static>(&mut self, handle: &Handle<T>) -> Option<&mut T> {
+        self.entries.get_mut(&handle.id())?.asset.as_mut()?.downcast_mut::<T>()
+    }
+
+    pub fn load_state<T>(&self, handle: &Handle<T>) -> LoadState { self.load_state_of(handle.id()) }
+
+    /// `NotLoaded` for ids the server doesn't know (e.g. already unloaded).
+    pub fn load_state_of(&self, id: AssetId) -> LoadState {
+        self.entries.get(&id).map(|e| e.state).unwrap_or(LoadState::NotLoaded)
+    }
+
+    pub fn is_loaded<T>(&self, handle: &Handle<T>) -> bool { self.load_state(handle) == LoadState::Loaded }
+
+    /// Why the asset failed to load (`LoadState::Failed`).
+    pub fn error<T>(&self, handle: &Handle<T>) -> Option<&AssetError> {
+        self.entries.get(&handle.id())?.error.as_ref()
+    }
+
+    /// Id of the asset loaded from `path` as `T`, if it is tracked.
+    pub fn id_of<T: 'static>(&self, path: &str) -> Option<AssetId> {
+        self.by_path.get(&(TypeId::of::<T>(), Arc::from(norm(path)))).copied()
+    }
+
+    /// Number of tracked assets (queued, loading, loaded or failed).
+    pub fn len(&self) -> usize { self.entries.len() }
+
+    pub fn is_empty(&self) -> bool { self.entries.is_empty() }
+
+    /// Number of assets being read or decoded.
+    pub fn pending(&self) -> usize {
+        self.entries.values().filter(|e| matches!(e.state, LoadState::NotLoaded | LoadState::Loading)).count()
+    }
+
+    /// Reload every asset loaded from `path` and, transitively, every asset depending on it.
+    /// The current version stays available (and `Loaded`) until the new one is ready.
+    pub fn reload(&mut self, path: &str) {
+        let path = norm(path);
+        let mut todo: Vec<AssetId> = self.entries.iter().filter(|(_, e)| *e.path == *path).map(|(id, _)| *id).collect();
+        todo.extend(self.dependents.get(path.as_str()).into_iter().flatten().copied());
+        let mut seen = HashSet::new();
+        while let Some(id) = todo.pop() {
+            if !seen.insert(id) { continue; }
+            let Some(entry) = self.entries.get(&id) else { continue };
+            if entry.loader.is_none() { continue; }
+            if !self.queue.contains(&id) { self.queue.push(id); }
+            todo.extend(self.dependents.get(&entry.path).into_iter().flatten().copied());
+        }
+    }
+
+    /// Feed a change from a watched mount: created or modified files are reloaded
+    /// (along with their dependents). Removed files leave loaded assets as they are.
+    pub fn file_changed(&mut self, event: &VfsEvent) {
+        match event.kind {
+            VfsEventKind::Created | VfsEventKind::Modified => self.reload(&event.path),
+            VfsEventKind::Removed => {}
+        }
+    }
+
+    /// Paths the asset's loader declared as dependencies (files and assets).
+    pub fn dependencies<T>(&self, handle: &Handle<T>) -> Vec<&str> {
+        self.entries.get(&handle.id()).map(|e| e.dependencies.iter().map(|p| &**p).collect()).unwrap_or_default()
+    }
+
+    /// Assets that declared `path` as a dependency.
+    pub fn dependents(&self, path: &str) -> Vec<AssetId> {
+        let mut out: Vec<AssetId> = self.dependents.get(norm(path).as_str()).into_iter().flatten().copied().collect();
+        out.sort();
+        out
+    }
+
+    /// True once the asset and every asset it depends on (recursively) are loaded.
+    pub fn is_loaded_with_dependencies<T>(&self, handle: &Handle<T>) -> bool {
+        let mut todo = vec![handle.id()];
+        let mut seen = HashSet::new();
+        while let Some(id) = todo.pop() {
+            if !seen.insert(id) { continue; }
+            let Some(entry) = self.entries.get(&id) else { return false };
+            if entry.state != LoadState::Loaded { return false; }
+            todo.extend(entry.dependency_handles.iter().map(|h| h.id));
+        }
+        true
+    }
+
+    /// A new handle to an asset that is already tracked (e.g. one declared with
+    /// `LoadContext::load_dependency`).
+    pub fn get_handle<T: 'static>(&self, path: &str) -> Option<Handle<T>> {
+        let id = self.id_of::<T>(path)?;
+        self.entries.get(&id)?.handle.upgrade().map(Handle::new)
+    }
+
+    /// Path an asset was loaded from.
+    pub fn path_of(&self, id: AssetId) -> Option<&str> { self.entries.get(&id).map(|e| &*e.path) }
+
+    /// Take the events produced since the last call.
+    pub fn drain_events(&mut self) -> Vec<AssetEvent> { std::mem::take(&mut self.events) }
+
+    /// Unload assets whose handles are all gone, start queued loads and collect finished ones.
+    pub fn update(&mut self, vfs: &Vfs) {
+        self.unload_dropped();
+        for id in std::mem::take(&mut self.queue) {
+            let Some(bytes) = self.read(vfs, id) else { continue };
+            let entry = self.entries.get_mut(&id).expect("queued asset entry");
+            let loader = entry.loader.clone().expect("queued asset has a loader");
+            if self.worker_count == 0 {
+                let res = run_loader(&*loader, &entry.path, &bytes);
+                self.finish(id, res);
+                continue;
+            }
+            // a reloading asset stays Loaded
+            if entry.asset.is_none() { entry.state = LoadState::Loading; }
+            let job = Job { id, path: entry.path.clone(), bytes, loader };
+            let workers = self.workers.get_or_insert_with(|| Workers::spawn(self.worker_count));
+            if let Some(tx) = &workers.jobs && tx.send(job).is_err() {
+                log::error!("asset workers are gone; {} not loaded", self.entries[&id].path);
+            }
+        }
+        let done: Vec<Done> = match &self.workers {
+            Some(w) => w.done.lock().map(|rx| rx.try_iter().collect()).unwrap_or_default(),
+            None => Vec::new(),
+        };
+        for (id, res) in done { self.finish(id, res); }
+    }
+
+    // Read the file for a queued asset; marks it failed on error.
+    fn read(&mut self, vfs: &Vfs, id: AssetId) -> Option<Arc<[u8]>> {
+        let entry = self.entries.get_mut(&id)?;
+        match vfs.read_shared(&entry.path) {
+            Ok(bytes) => Some(bytes),
+            Err(e) if entry.asset.is_some() => {
+                log::warn!("asset {} not reloaded: {}", entry.path, e);
+                None
+            }
+            Err(e) => {
+                entry.state = LoadState::Failed;
+                entry.error = Some(AssetError::Vfs(e));
+                None
+            }
+        }
+    }
+
+    fn finish(&mut self, id: AssetId, res: Result<Loaded, AssetError>) {
+        // unloaded while the worker was busy
+        if !self.entries.contains_key(&id) { return; }
+        match res {
+            Ok((asset, deps)) => {
+                let mut paths = Vec::new();
+                let mut dependency_handles = Vec::new();
+                for dep in deps {
+                    paths.push(Arc::<str>::from(dep.path()));
+                    if let Dependency::Asset(_, request) = dep {
+                        let handle = request(self);
+                        // holding a handle back up the chain would keep the whole cycle loaded forever;
+                        // the path is still tracked, so changes reload it either way
+                        if self.depends_on(handle.id, id) {
+                            log::warn!("asset dependency cycle: {} -> {} (not kept loaded)", self.entries[&id].path, handle.path);
+                        } else {
+                            dependency_handles.push(handle);
+                        }
+                    }
+                }
+                self.set_dependencies(id, paths);
+                let entry = self.entries.get_mut(&id).expect("asset entry");
+                entry.dependency_handles = dependency_handles;
+                let event = if entry.asset.is_some() { AssetEvent::Modified(id) } else { AssetEvent::Loaded(id) };
+                entry.asset = Some(asset);
+                entry.error = None;
+                entry.state = LoadState::Loaded;
+                self.events.push(event);
+            }
+            Err(e) => {
+                let entry = self.entries.get_mut(&id).expect("asset entry");
+                if entry.asset.is_some() {
+                    // keep the last good version
+                    log::warn!("asset {} (keeping the previous version)", e);
+                } else {
+                    log::warn!("asset {}", e);
+                    entry.state = LoadState::Failed;
+                }
+                entry.error = Some(e);
+            }
+        }
+    }
+
+    // True if `from` is `to` or keeps it loaded through its asset dependencies.
+    fn depends_on(&self, from: AssetId, to: AssetId) -> bool {
+        let mut todo = vec![from];
+        let mut seen = HashSet::new();
+        while let Some(id) = todo.pop() {
+            if id == to { return true; }
+            if !seen.insert(id) { continue; }
+            if let Some(entry) = self.entries.get(&id) { todo.extend(entry.dependency_handles.iter().map(|h| h.id)); }
+        }
+        false
+    }
+
+    // Replace the dependency edges of `id`.
+    fn set_dependencies(&mut self, id: AssetId, paths: Vec<Arc<str>>) {
+        let Some(entry) = self.entries.get_mut(&id) else { return };
+        let old = std::mem::replace(&mut entry.dependencies, paths.clone());
+        for p in old {
+            if let Some(set) = self.dependents.get_mut(&p) {
+                set.remove(&id);
+                if set.is_empty() { self.dependents.remove(&p); }
+            }
+        }
+        for p in paths { self.dependents.entry(p).or_default().insert(id); }
+    }
+
+    fn unload_dropped(&mut self) {
+        // unloading can drop the last handle of a dependency, so repeat until nothing is left
+        loop {
+            let dropped: Vec<AssetId> = self.dropped.lock().map(|rx| rx.try_iter().collect()).unwrap_or_default();
+            if dropped.is_empty() { return; }
+            for id in dropped {
+                let Some(entry) = self.entries.get(&id) else { continue };
+                // a new handle may have been handed out since
+                if entry.handle.strong_count() > 0 { continue; }
+                self.set_dependencies(id, Vec::new());
+                let entry = self.entries.remove(&id).expect("asset entry");
+                self.by_path.remove(&(entry.type_id, entry.path.clone()));
+                self.queue.retain(|q| *q != id);
+                if entry.asset.is_some() { self.events.push(AssetEvent::Removed(id)); }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset::BoxError;
+    use crate::fs::{MemBackend, VfsError};
+
+    // "bad" fails to load
+    struct TextLoader;
+    impl AssetLoader for TextLoader {
+        type Asset = String;
+        fn extensions(&self) -> &[&str] { &["txt"] }
+        fn load(&self, ctx: &mut LoadContext) -> Result<String, BoxError> {
+            let text = String::from_utf8(ctx.bytes().to_vec())?;
+            if text == "bad" { return Err("bad text".into()); }
+            Ok(text)
+        }
+    }
+
+    struct LenLoader;
+    impl AssetLoader for LenLoader {
+        type Asset = usize;
+        fn extensions(&self) -> &[&str] { &["txt"] }
+        fn load(&self, ctx: &mut LoadContext) -> Result<usize, BoxError> { Ok(ctx.bytes().len()) }
+    }
+
+    // One path per line: `.dep` files become asset dependencies, anything else a file dependency.
+    #[derive(Debug, PartialEq)]
+    struct Deps(Vec<String>);
+    struct DepLoader;
+    impl AssetLoader for DepLoader {
+        type Asset = Deps;
+        fn extensions(&self) -> &[&str] { &["dep"] }
+        fn load(&self, ctx: &mut LoadContext) -> Result<Deps, BoxError> {
+            let text = String::from_utf8(ctx.bytes().to_vec())?;
+            let mut out = Vec::new();
+            for line in text.lines() {
+                out.push(if line.ends_with(".dep") { ctx.load_dependency::<Deps>(line) } else { ctx.add_dependency(line) });
+            }
+            Ok(Deps(out))
+        }
+    }
+
+    fn setup(files: &[(&str, &str)]) -> (AssetServer, Vfs) {
+        let mut vfs = Vfs::new();
+        vfs.mount("/", Box::new(MemBackend::new()));
+        for (path, data) in files { vfs.write(path, data.as_bytes()).unwrap(); }
+        let mut server = AssetServer::with_workers(0);
+        server.add_loader(TextLoader).add_loader(LenLoader).add_loader(DepLoader);
+        (server, vfs)
+    }
+
+    #[test]
+    fn loads_are_shared_by_path_and_type() {
+        let (mut server, vfs) = setup(&[("/a.txt", "hello")]);
+        let a = server.load::<String>("/a.txt");
+        assert_eq!(server.load_state(&a), LoadState::NotLoaded);
+        assert_eq!(server.load::<String>("a.txt"), a);
+        let len = server.load::<usize>("/a.txt");
+        assert_ne!(len.id(), a.id());
+        assert_eq!(server.len(), 2);
+        server.update(&vfs);
+        assert_eq!(server.get(&a).map(String::as_str), Some("hello"));
+        assert_eq!(server.get(&len), Some(&5));
+        assert_eq!(server.id_of::<String>("/a.txt"), Some(a.id()));
+        assert_eq!(server.drain_events(), [AssetEvent::Loaded(a.id()), AssetEvent::Loaded(len.id())]);
+    }
+
+    #[test]
+    fn unloads_after_the_last_handle_drops() {
+        let (mut server, vfs) = setup(&[("/a.txt", "hello")]);
+        let a = server.load::<String>("/a.txt");
+        let id = a.id();
+        server.update(&vfs);
+        let copy = a.clone();
+        drop(a);
+        server.update(&vfs);
+        assert!(server.is_loaded(&copy));
+        drop(copy);
+        // still there until the next update
+        assert_eq!(server.load_state_of(id), LoadState::Loaded);
+        server.update(&vfs);
+        assert_eq!(server.load_state_of(id), LoadState::NotLoaded);
+        assert!(server.is_empty());
+        assert_eq!(server.drain_events().last(), Some(&AssetEvent::Removed(id)));
+        // loading again starts over with a new id
+        let again = server.load::<String>("/a.txt");
+        assert_ne!(again.id(), id);
+    }
+
+    #[test]
+    fn reload_before_update_keeps_the_asset() {
+        let (mut server, vfs) = setup(&[("/a.txt", "hello")]);
+        let a = server.load::<String>("/a.txt");
+        server.update(&vfs);
+        server.drain_events();
+        let id = a.id();
+        drop(a);
+        let a = server.load::<String>("/a.txt");
+        assert_eq!(a.id(), id);
+        assert!(server.is_loaded(&a));
+        server.update(&vfs);
+        assert!(server.is_loaded(&a));
+        assert!(server.drain_events().is_empty());
+    }
+
+    #[test]
+    fn missing_loader_or_file_fails() {
+        let (mut server, vfs) = setup(&[("/a.txt", "hello"), ("/bad.txt", "bad")]);
+        let no_loader = server.load::<String>("/a.png");
+        assert_eq!(server.load_state(&no_loader), LoadState::Failed);
+        assert!(matches!(server.error(&no_loader), Some(AssetError::NoLoader(p)) if p == "/a.png"));
+        let wrong_type = server.load::<Deps>("/a.txt");
+        assert_eq!(server.load_state(&wrong_type), LoadState::Failed);
+
+        let missing = server.load::<String>("/missing.txt");
+        let bad = server.load::<String>("/bad.txt");
+        server.update(&vfs);
+        assert_eq!(server.load_state(&missing), LoadState::Failed);
+        assert!(matches!(server.error(&missing), Some(AssetError::Vfs(VfsError::NotFound(_)))));
+        assert_eq!(server.load_state(&bad), LoadState::Failed);
+        assert_eq!(server.error(&bad).unwrap().to_string(), "/bad.txt: bad text");
+        assert!(server.get(&bad).is_none());
+        assert_eq!(server.pending(), 0);
+        assert!(server.drain_events().is_empty());
+    }
+
+    #[test]
+    fn failed_reload_keeps_the_previous_version() {
+        let (mut server, mut vfs) = setup(&[("/a.txt", "good")]);
+        let a = server.load::<String>("/a.txt");
+        server.update(&vfs);
+        server.drain_events();
+
+        vfs.write("/a.txt", b"bad").unwrap();
+        server.reload("/a.txt");
+        server.update(&vfs);
+        assert_eq!(server.load_state(&a), LoadState::Loaded);
+        assert_eq!(server.get(&a).map(String::as_str), Some("good"));
+        assert!(server.error(&a).is_some());
+        // so does a file that went away
+        vfs.remove("/a.txt").unwrap();
+        server.reload("/a.txt");
+        server.update(&vfs);
+        assert_eq!(server.get(&a).map(String::as_str), Some("good"));
+        assert!(server.drain_events().is_empty());
+
+        vfs.write("/a.txt", b"better").unwrap();
+        server.reload("/a.txt");
+        server.update(&vfs);
+        assert_eq!(server.get(&a).map(String::as_str), Some("better"));
+        assert!(server.error(&a).is_none());
+        assert_eq!(server.drain_events(), [AssetEvent::Modified(a.id())]);
+    }
+
+    #[test]
+    fn dependency_cycles_are_unloaded() {
+        let (mut server, vfs) = setup(&[("/a.dep", "b.dep"), ("/b.dep", "a.dep\nc.dep"), ("/c.dep", "")]);
+        let a = server.load::<Deps>("/a.dep");
+        for _ in 0..3 { server.update(&vfs); }
+        assert!(server.is_loaded_with_dependencies(&a));
+        assert_eq!(server.len(), 3);
+        let b = server.id_of::<Deps>("/b.dep").unwrap();
+        // the cycle edge is still tracked for reloads
+        assert_eq!(server.dependents("/a.dep"), [b]);
+
+        drop(a);
+        server.update(&vfs);
+        assert!(server.is_empty());
+        assert!(server.dependents("/a.dep").is_empty());
+    }
+
+    #[test]
+    fn self_dependency_is_not_kept() {
+        let (mut server, vfs) = setup(&[("/self.dep", "self.dep")]);
+        let s = server.load::<Deps>("/self.dep");
+        server.update(&vfs);
+        assert_eq!(server.get(&s), Some(&Deps(vec!["/self.dep".into()])));
+        drop(s);
+        server.update(&vfs);
+        assert!(server.is_empty());
+    }
+
+    #[test]
+    fn reload_rederives_dependents_transitively() {
+        let (mut server, mut vfs) = setup(&[("/a.dep", "b.dep"), ("/b.dep", "c.txt"), ("/c.txt", "x"), ("/other.dep", "")]);
+        let a = server.load::<Deps>("/a.dep");
+        let other = server.load::<Deps>("/other.dep");
+        for _ in 0..2 { server.update(&vfs); }
+        let b = server.get_handle::<Deps>("/b.dep").unwrap();
+        assert!(server.is_loaded_with_dependencies(&a));
+        assert_eq!(server.dependencies(&b), ["/c.txt"]);
+        assert_eq!(server.dependents("/c.txt"), [b.id()]);
+        server.drain_events();
+
+        // a plain file two levels down
+        vfs.write("/c.txt", b"y").unwrap();
+        server.file_changed(&VfsEvent::new(VfsEventKind::Modified, "/c.txt"));
+        server.update(&vfs);
+        assert_eq!(server.get(&b), Some(&Deps(vec!["/c.txt".into()])));
+        let mut events = server.drain_events();
+        events.sort_by_key(|e| e.id());
+        let mut expected = vec![AssetEvent::Modified(a.id()), AssetEvent::Modified(b.id())];
+        expected.sort_by_key(|e| e.id());
+        assert_eq!(events, expected);
+
+        // the middle asset: only it and what depends on it
+        server.reload("/b.dep");
+        server.update(&vfs);
+        assert_eq!(server.drain_events().len(), 2);
+        // removed files keep what is loaded
+        server.file_changed(&VfsEvent::new(VfsEventKind::Removed, "/c.txt"));
+        server.update(&vfs);
+        assert!(server.drain_events().is_empty());
+        assert!(server.is_loaded(&a) && server.is_loaded(&other));
+    }
+
+    #[test]
+    fn register_sends_asset_events() {
+        use crate::app::App;
+        use crate::ecs::{EventCursor, Events, Stage};
+
+        let (mut server, mut vfs) = setup(&[("/a.txt", "one")]);
+        vfs.watch("/").unwrap();
+        let a = server.load::<String>("/a.txt");
+        let mut app = App::new();
+        app.insert_resource(vfs).insert_resource(server);
+        crate::fs::watch::register(&mut app);
+        crate::asset::register(&mut app);
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let out = seen.clone();
+        let mut cursor = EventCursor::<AssetEvent>::default();
+        app.add_systems(Stage::Update, move |ecs: &mut crate::ecs::Ecs| {
+            let events = ecs.get_resource::<Events<AssetEvent>>().unwrap();
+            out.lock().unwrap().extend(cursor.read(events).copied());
+        });
+        let frame = |app: &mut App| { seen.lock().unwrap().clear(); app.update(); seen.lock().unwrap().clone() };
+
+        assert_eq!(frame(&mut app), [AssetEvent::Loaded(a.id())]);
+        app.resource_mut::<Vfs>().unwrap().write("/a.txt", b"two").unwrap();
+        // the change is picked up and reloaded within the frame
+        assert_eq!(frame(&mut app), [AssetEvent::Modified(a.id())]);
+        assert_eq!(app.resource::<AssetServer>().unwrap().get(&a).map(String::as_str), Some("two"));
+        let id = a.id();
+        drop(a);
+        assert_eq!(frame(&mut app), [AssetEvent::Removed(id)]);
+    }
+}
@@ -0,0 +1,82 @@
+use crate::image::{png, RgbaImage};
+
+use super::import::{ImportSettings, Importer};
+use super::loader::{AssetLoader, BoxError, LoadContext};
+
+// .atex layout (little endian):
+//   magic "ATEX", version u16, flags u16 (bit 0: premultiplied), width u32, height u32,
+//   width * height RGBA8 pixels
+const MAGIC: &[u8; 4] = b"ATEX";
+const VERSION: u16 = 1;
+const HEADER_LEN: usize = 16;
+const FLAG_PREMULTIPLIED: u16 = 1;
+
+/// RGBA8 texture ready for upload.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Texture {
+    pub image: RgbaImage,
+    /// Color channels are already multiplied by alpha.
+    pub premultiplied: bool,
+}
+
+impl Texture {
+    pub fn width(&self) -> u32 { self.image.width }
+
+    pub fn height(&self) -> u32 { self.image.height }
+
+    /// Serialize as `.atex`.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(HEADER_LEN + self.image.pixels.len());
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&VERSION.to_le_bytes());
+        out.extend_from_slice(&(if self.premultiplied { FLAG_PREMULTIPLIED } else { 0 }).to_le_bytes());
+        out.extend_from_slice(&self.image.width.to_le_bytes());
+        out.extend_from_slice(&self.image.height.to_le_bytes());
+        out.extend_from_slice(&self.image.pixels);
+        out
+    }
+
+    /// Parse `.atex` data. None if malformed.
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        if data.len() < HEADER_LEN || &data[..4] != MAGIC { return None; }
+        if u16::from_le_bytes([data[4], data[5]]) != VERSION { return None; }
+        let flags = u16::from_le_bytes([data[6], data[7]]);
+        let width = u32::from_le_bytes(data[8..12].try_into().ok()?);
+        let height = u32::from_le_bytes(data[12..16].try_into().ok()?);
+        let len = (width as usize).checked_mul(height as usize)?.checked_mul(4)?;
+        let pixels = data.get(HEADER_LEN..HEADER_LEN.checked_add(len)?)?.to_vec();
+        Some(Self { image: RgbaImage { width, height, pixels }, premultiplied: flags & FLAG_PREMULTIPLIED != 0 })
+    }
+}
+
+/// PNG -> `.atex`. Setting: `premultiply` (default `true`).
+pub struct TextureImporter;
+
+impl Importer for TextureImporter {
+    fn name(&self) -> &str { "texture" }
+    fn extensions(&self) -> &[&str] { &["png"] }
+    fn output_extension(&self) -> &str { "atex" }
+    fn default_settings(&self) -> ImportSettings { ImportSettings::new().with("premultiply", true) }
+    fn import(&self, _path: &str, source: &[u8], settings: &ImportSettings) -> Result<Vec<u8>, BoxError> {
+        let mut image = png::decode(source)?;
+        let premultiplied = settings.parse_or("premultiply", true);
+        if premultiplied { image.premultiply(); }
+        Ok(Texture { image, premultiplied }.encode())
+    }
+}
+
+/// Loads `.atex` (processed) and `.png` (decoded and premultiplied on load) as `Texture`.
+pub struct TextureLoader;
+
+impl AssetLoader for TextureLoader {
+    type Asset = Texture;
+    fn extensions(&self) -> &[&str] { &["atex", "png"] }
+    fn load(&self, ctx: &mut LoadContext) -> Result<Texture, BoxError> {
+        if ctx.extension() == "png" {
+            let mut image = png::decode(ctx.bytes())?;
+            image.premultiply();
+            return Ok(Texture { image, premultiplied: true });
+        }
+        Texture::decode(ctx.bytes()).ok_or_else(|| "invalid .atex data".into())
+    }
+}
@@ -0,0 +1,328 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::Write as _;
+use std::time::{Duration, Instant};
+
+use crate::ecs::inspect::push_json_str;
+use crate::fs::{Vfs, VfsResult};
+
+/// Fixed-size window of duration samples.
+#[derive(Clone, Debug)]
+pub struct RollingStats {
+    samples: VecDeque<Duration>,
+    capacity: usize,
+    peak: Duration,
+}
+
+impl RollingStats {
+    pub fn new(capacity: usize) -> Self {
+        Self { samples: VecDeque::with_capacity(capacity.max(1)), capacity: capacity.max(1), peak: Duration::ZERO }
+    }
+
+    pub fn push(&mut self, d: Duration) {
+        if self.samples.len() == self.capacity { self.samples.pop_front(); }
+        self.samples.push_back(d);
+        if d > self.peak { self.peak = d; }
+    }
+
+    pub fn last(&self) -> Duration { self.samples.back().copied().unwrap_or_default() }
+
+    pub fn average(&self) -> Duration {
+        if self.samples.is_empty() { return Duration::ZERO; }
+        self.samples.iter().sum::<Duration>() / self.samples.len() as u32
+    }
+
+    // min/max over the window
+    pub fn min(&self) -> Duration { self.samples.iter().min().copied().unwrap_or_default() }
+    pub fn max(&self) -> Duration { self.samples.iter().max().copied().unwrap_or_default() }
+
+    /// Largest sample ever recorded (not limited to the window).
+    pub fn peak(&self) -> Duration { self.peak }
+
+    pub fn len(&self) -> usize { self.samples.len() }
+
+    pub fn is_empty(&self) -> bool { self.samples.is_empty() }
+}
+
+#[derive(Clone, Debug)]
+pub struct SystemStats {
+    pub stage: &'static str,
+    pub name: &'static str,
+    pub calls: u64,
+    pub time: RollingStats,
+}
+
+#[derive(Clone, Debug)]
+struct TraceEvent {
+    name: &'static str,
+    cat: &'static str,
+    start: Duration,
+    dur: Duration,
+}
+
+/// Per-frame timing collected by the scheduler.
+/// Profiling is enabled simply by inserting this resource:
+/// ```ignore
+/// app.insert_resource(FrameDiagnostics::new(120));
+/// ```
+pub struct FrameDiagnostics {
+    window: usize,
+    frame_count: u64,
+    last_frame_start: Option<Instant>,
+    frame_time: RollingStats,
+    update_time: RollingStats,
+    stages: BTreeMap<&'static str, RollingStats>,
+    // keyed by (stage, registration index) so unlabeled closures don't collide
+    systems: BTreeMap<(&'static str, usize), SystemStats>,
+    trace_origin: Option<Instant>,
+    trace: Vec<TraceEvent>,
+    max_trace_events: usize,
+}
+
+impl Default for FrameDiagnostics {
+    fn default() -> Self { Self::new(120) }
+}
+
+impl FrameDiagnostics {
+    /// `window`: number of frames kept for rolling statistics.
+    pub fn new(window: usize) -> Self {
+        Self {
+            window,
+            frame_count: 0,
+            last_frame_start: None,
+            frame_time: RollingStats::new(window),
+            update_time: RollingStats::new(window),
+            stages: BTreeMap::new(),
+            systems: BTreeMap::new(),
+            trace_origin: None,
+            trace: Vec::new(),
+            max_trace_events: 1_000_000,
+        }
+    }
+
+    pub fn frame_count(&self) -> u64 { self.frame_count }
+
+    /// Wall time between consecutive frame starts.
+    pub fn frame_time(&self) -> &RollingStats { &self.frame_time }
+
+    /// Time spent running schedules within a frame.
+    pub fn update_time(&self) -> &RollingStats { &self.update_time }
+
+    pub fn fps(&self) -> f64 {
+        let avg = self.frame_time.average().as_secs_f64();
+        if avg > 0.0 { 1.0 / avg } else { 0.0 }
+    }
+
+    pub fn stage(&self, stage: &str) -> Option<&RollingStats> { self.stages.get(stage) }
+
+    pub fn systems(&self) -> impl Iterator<Item = &SystemStats> { self.systems.values() }
+
+    /// Systems sorted by average time, slowest first.
+    pub fn slowest(&self, n: usize) -> Vec<&SystemStats> {
+        let mut v: Vec<&SystemStats> = self.systems.values().collect();
+        v.sort_by_key(|s| std::cmp::Reverse(s.time.average()));
+        v.truncate(n);
+        v
+    }
+
+    pub fn reset(&mut self) {
+        let tracing = self.is_tracing();
+        let max = self.max_trace_events;
+        *self = Self::new(self.window);
+        self.max_trace_events = max;
+        if tracing { self.start_trace(); }
+    }
+
+    // ----- trace capture -----
+    pub fn start_trace(&mut self) {
+        self.trace.clear();
+        self.trace_origin = Some(Instant::now());
+    }
+
+    pub fn stop_trace(&mut self) { self.trace_origin = None; }
+
+    pub fn is_tracing(&self) -> bool { self.trace_origin.is_some() }
+
+    /// Events beyond this count are dropped while tracing.
+    pub fn set_max_trace_events(&mut self, max: usize) { self.max_trace_events = max; }
+
+    pub fn trace_event_count(&self) -> usize { self.trace.len() }
+
+    /// Chrome trace-event JSON (load in chrome://tracing or Perfetto).
+    pub fn to_chrome_trace(&self) -> String {
+        let mut out = String::from("{\"traceEvents\":[");
+        for (i, ev) in self.trace.iter().enumerate() {
+            if i > 0 { out.push(','); }
+            out.push_str("{\"name\":");
+            push_json_str(&mut out, ev.name);
+            out.push_str(",\"cat\":");
+            push_json_str(&mut out, ev.cat);
+            let _ = write!(
+                out,
+                ",\"ph\":\"X\",\"ts\":{:.3},\"dur\":{:.3},\"pid\":1,\"tid\":1}}",
+                ev.start.as_secs_f64() * 1e6,
+                ev.dur.as_secs_f64() * 1e6
+            );
+        }
+        out.push_str("],\"displayTimeUnit\":\"ms\"}");
+        out
+    }
+
+    pub fn write_chrome_trace(&self, vfs: &mut Vfs, path: &str) -> VfsResult<()> {
+        vfs.write(path, self.to_chrome_trace().as_bytes())
+    }
+
+    // ----- recording (called by Schedules) -----
+    fn push_trace(&mut self, name: &'static str, cat: &'static str, start: Instant, dur: Duration) {
+        let Some(origin) = self.trace_origin else { return };
+        if self.trace.len() >= self.max_trace_events { return; }
+        self.trace.push(TraceEvent { name, cat, start: start.saturating_duration_since(origin), dur });
+    }
+
+    pub(crate) fn record_system(&mut self, stage: &'static str, index: usize, name: &'static str, start: Instant, dur: Duration) {
+        let window = self.window;
+        let st = self
+            .systems
+            .entry((stage, index))
+            .or_insert_with(|| SystemStats { stage, name, calls: 0, time: RollingStats::new(window) });
+        st.name = name;
+        st.calls += 1;
+        st.time.push(dur);
+        self.push_trace(name, "system", start, dur);
+    }
+
+    pub(crate) fn record_stage(&mut self, stage: &'static str, start: Instant, dur: Duration) {
+        let window = self.window;
+        self.stages.entry(stage).or_insert_with(|| RollingStats::new(window)).push(dur);
+        self.push_trace(stage, "stage", start, dur);
+    }
+
+    pub(crate) fn record_frame(&mut self, start: Instant, dur: Duration) {
+        if let Some(prev) = self.last_frame_start {
+            self.frame_time.push(start.saturating_duration_since(prev));
+        }
+        self.last_frame_start = Some(start);
+        self.update_time.push(dur);
+        self.frame_count += 1;
+        self.push_trace("frame", "frame", start, dur);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::{App, Stage};
+    use crate::ecs::Ecs;
+    use crate::fs::MemBackend;
+
+    fn ms(n: u64) -> Duration { Duration::from_millis(n) }
+
+    #[test]
+    fn rolling_stats_keep_a_window() {
+        let mut s = RollingStats::new(3);
+        assert_eq!((s.last(), s.average(), s.min(), s.max()), (Duration::ZERO, Duration::ZERO, Duration::ZERO, Duration::ZERO));
+        for n in [9, 1, 2, 3] { s.push(ms(n)); }
+        assert_eq!(s.len(), 3);
+        assert_eq!((s.last(), s.average(), s.min(), s.max(), s.peak()), (ms(3), ms(2), ms(1), ms(3), ms(9)));
+        assert_eq!(RollingStats::new(0).capacity, 1);
+    }
+
+    #[test]
+    fn systems_are_timed_per_stage_and_index() {
+        let mut app = App::new();
+        app.insert_resource(FrameDiagnostics::new(8))
+            .add_systems(Stage::First, |_: &mut Ecs| {})
+            .add_systems(Stage::Update, |_: &mut Ecs| {})
+            // same label twice: still two entries
+            .add_systems_with_label(Stage::Update, "dup", |_: &mut Ecs| {})
+            .add_systems_with_label(Stage::Update, "dup", |_: &mut Ecs| std::thread::sleep(ms(2)));
+        for _ in 0..3 { app.update(); }
+
+        let diag = app.resource::<FrameDiagnostics>().unwrap();
+        assert_eq!(diag.frame_count(), 3);
+        assert_eq!((diag.update_time().len(), diag.frame_time().len()), (3, 2));
+        let systems: Vec<(&str, &str, u64)> = diag.systems().map(|s| (s.stage, s.name, s.calls)).collect();
+        assert_eq!(systems.len(), 4);
+        assert_eq!(systems.iter().filter(|s| s.0 == "update").count(), 3);
+        assert_eq!(systems.iter().filter(|s| s.1 == "dup").count(), 2);
+        assert!(systems.iter().all(|s| s.2 == 3));
+        assert!(systems.iter().any(|s| s.0 == "first"));
+        let slowest = diag.slowest(1);
+        assert_eq!((slowest[0].name, slowest[0].stage), ("dup", "update"));
+        assert!(slowest[0].time.min() >= ms(2));
+        assert_eq!(diag.stage("update").unwrap().len(), 3);
+        assert!(diag.stage("update").unwrap().min() >= ms(2));
+        assert!(diag.stage("nope").is_none());
+    }
+
+    #[test]
+    fn nothing_is_timed_without_the_resource() {
+        let mut app = App::new();
+        app.add_systems(Stage::Update, |_: &mut Ecs| {});
+        app.update();
+        app.insert_resource(FrameDiagnostics::new(8));
+        app.update();
+        let diag = app.resource::<FrameDiagnostics>().unwrap();
+        assert_eq!(diag.systems().map(|s| s.calls).collect::<Vec<_>>(), [1]);
+        assert_eq!(diag.frame_count(), 1);
+    }
+
+    #[test]
+    fn chrome_trace_format() {
+        let mut diag = FrameDiagnostics::new(8);
+        assert_eq!(diag.to_chrome_trace(), "{\"traceEvents\":[],\"displayTimeUnit\":\"ms\"}");
+        let t0 = Instant::now();
+        // not tracing yet: stats only
+        diag.record_system("update", 0, "ignored", t0, ms(1));
+        diag.trace_origin = Some(t0);
+        diag.record_system("update", 1, "a\"b\\c", t0 + Duration::from_micros(1500), Duration::from_micros(250));
+        diag.record_stage("update", t0 + ms(1), ms(2));
+        diag.record_frame(t0, Duration::from_nanos(3_000_500));
+        assert_eq!(
+            diag.to_chrome_trace(),
+            concat!(
+                "{\"traceEvents\":[",
+                "{\"name\":\"a\\\"b\\\\c\",\"cat\":\"system\",\"ph\":\"X\",\"ts\":1500.000,\"dur\":250.000,\"pid\":1,\"tid\":1},",
+                "{\"name\":\"update\",\"cat\":\"stage\",\"ph\":\"X\",\"ts\":1000.000,\"dur\":2000.000,\"pid\":1,\"tid\":1},",
+                "{\"name\":\"frame\",\"cat\":\"frame\",\"ph\":\"X\",\"ts\":0.000,\"dur\":3000.500,\"pid\":1,\"tid\":1}",
+                "],\"displayTimeUnit\":\"ms\"}"
+            )
+        );
+        assert_eq!(diag.systems().count(), 2);
+    }
+
+    #[test]
+    fn trace_capture_limits_and_vfs_output() {
+        let mut app = App::new();
+        app.insert_resource(FrameDiagnostics::new(8)).add_systems_with_label(Stage::Update, "work", |_: &mut Ecs| {});
+        app.resource_mut::<FrameDiagnostics>().unwrap().start_trace();
+        app.update();
+        let diag = app.resource_mut::<FrameDiagnostics>().unwrap();
+        let per_frame = diag.trace_event_count();
+        let json = diag.to_chrome_trace();
+        assert_eq!(json.matches("\"ph\":\"X\"").count(), per_frame);
+        assert!(json.contains("{\"name\":\"work\",\"cat\":\"system\",\"ph\":\"X\",\"ts\":"));
+        assert_eq!(json.matches("\"cat\":\"frame\"").count(), 1);
+
+        diag.set_max_trace_events(per_frame + 1);
+        app.update();
+        app.update();
+        let diag = app.resource_mut::<FrameDiagnostics>().unwrap();
+        assert_eq!(diag.trace_event_count(), per_frame + 1);
+        // reset clears the stats but keeps capturing
+        diag.reset();
+        assert!(diag.is_tracing() && diag.trace_event_count() == 0 && diag.frame_count() == 0);
+        diag.stop_trace();
+        app.update();
+        let diag = app.resource::<FrameDiagnostics>().unwrap();
+        assert_eq!((diag.trace_event_count(), diag.frame_count()), (0, 1));
+
+        let mut vfs = Vfs::new();
+        vfs.mount("/", Box::new(MemBackend::new()));
+        let mut diag = FrameDiagnostics::new(8);
+        diag.start_trace();
+        diag.record_frame(Instant::now(), ms(1));
+        diag.write_chrome_trace(&mut vfs, "/trace.json").unwrap();
+        assert_eq!(vfs.read("/trace.json").unwrap(), diag.to_chrome_trace().into_bytes());
+        assert!(diag.write_chrome_trace(&mut vfs, "/missing/trace.json").is_err());
+    }
+}
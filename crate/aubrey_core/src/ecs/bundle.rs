@@ -5,9 +5,19 @@ use std::any::TypeId;
 use crate::ecs::entity::Entity;
 use crate::ecs::ecs::{Command, Ecs};
 
+/// A set of components inserted/removed together.
+/// Implemented for `Single<T>`, tuples, and structs via `#[derive(Bundle)]`
+/// (fields marked `#[bundle]` are nested bundles).
 pub trait Bundle: Send + 'static {
     fn insert_immediate(self, ecs: &mut Ecs, entity: Entity);
     fn write_commands(self, entity: Entity, out: &mut Vec<Command>, ecs: &mut Ecs);
+    /// True when `entity` has every component of the bundle.
+    fn contains(ecs: &Ecs, entity: Entity) -> bool;
+    /// Remove whichever of the bundle's components are present.
+    fn remove(ecs: &mut Ecs, entity: Entity);
+    /// Remove the components and rebuild the bundle. Callers check `contains` first;
+    /// on a partial match this returns None after removing what it found.
+    fn take_components(ecs: &mut Ecs, entity: Entity) -> Option<Self> where Self: Sized;
 }
 
 pub struct Single<T: 'static + Send + Sync>(pub T);
@@ -20,6 +30,9 @@ impl<T: 'static + Send + Sync> Bundle for Single<T> {
         ecs.ensure_store::<T>();
         out.push(Command::Insert { entity, type_id: TypeId::of::<T>(), value: Box::new(self.0) });
     }
+    fn contains(ecs: &Ecs, entity: Entity) -> bool { ecs.has::<T>(entity) }
+    fn remove(ecs: &mut Ecs, entity: Entity) { ecs.remove::<T>(entity); }
+    fn take_components(ecs: &mut Ecs, entity: Entity) -> Option<Self> { ecs.remove::<T>(entity).map(Single) }
 }
 
 macro_rules! impl_bundle_tuple {
@@ -39,6 +52,16 @@ macro_rules! impl_bundle_tuple {
                     out.push(Command::Insert { entity, type_id: TypeId::of::<$name>(), value: Box::new($name) });
                 } )+
             }
+            fn contains(ecs: &Ecs, entity: Entity) -> bool {
+                true $( && ecs.has::<$name>(entity) )+
+            }
+            fn remove(ecs: &mut Ecs, entity: Entity) {
+                $( ecs.remove::<$name>(entity); )+
+            }
+            fn take_components(ecs: &mut Ecs, entity: Entity) -> Option<Self> {
+                $( let $name = ecs.remove::<$name>(entity); )+
+                Some(( $( $name?, )+ ))
+            }
         }
     }
 }
@@ -47,3 +70,125 @@ impl_bundle_tuple!(A, B);
 impl_bundle_tuple!(A, B, C);
 impl_bundle_tuple!(A, B, C, D);
 impl_bundle_tuple!(A, B, C, D, E);
+impl_bundle_tuple!(A, B, C, D, E, F);
+impl_bundle_tuple!(A, B, C, D, E, F, G);
+impl_bundle_tuple!(A, B, C, D, E, F, G, H);
+
+// Bundle-level entity ops
+impl Ecs {
+    pub fn insert_bundle<B: Bundle>(&mut self, entity: Entity, bundle: B) {
+        bundle.insert_immediate(self, entity);
+    }
+
+    /// Remove every component of `B` present on `entity`.
+    pub fn remove_bundle<B: Bundle>(&mut self, entity: Entity) {
+        B::remove(self, entity);
+    }
+
+    /// Remove and return bundle `B`; None (and nothing removed) unless all its components are present.
+    pub fn take<B: Bundle>(&mut self, entity: Entity) -> Option<B> {
+        if !B::contains(self, entity) { return None; }
+        B::take_components(self, entity)
+    }
+
+    pub fn has_bundle<B: Bundle>(&self, entity: Entity) -> bool { B::contains(self, entity) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ecs::{Bundle, Commands};
+
+    #[derive(Debug, PartialEq)]
+    struct Pos(i32);
+    #[derive(Debug, PartialEq)]
+    struct Vel(i32);
+    #[derive(Debug, PartialEq)]
+    struct Name(&'static str);
+    #[derive(Debug, PartialEq)]
+    struct Tag;
+
+    #[derive(Bundle, Debug, PartialEq)]
+    #[bundle(crate = "crate")]
+    struct Body {
+        pos: Pos,
+        vel: Vel,
+    }
+
+    #[derive(Bundle, Debug, PartialEq)]
+    #[bundle(crate = "crate")]
+    struct Actor {
+        name: Name,
+        #[bundle]
+        body: Body,
+        #[bundle]
+        extra: (Tag, u8),
+    }
+
+    #[derive(Bundle, Debug, PartialEq)]
+    #[bundle(crate = "crate")]
+    struct Pair(Name, #[bundle] Body);
+
+    fn actor() -> Actor { Actor { name: Name("a"), body: Body { pos: Pos(1), vel: Vel(2) }, extra: (Tag, 7) } }
+
+    #[test]
+    fn flat_bundle_inserts_every_field() {
+        let mut ecs = Ecs::new();
+        let e = ecs.spawn(Body { pos: Pos(1), vel: Vel(2) });
+        assert_eq!(ecs.get::<Pos>(e), Some(&Pos(1)));
+        assert_eq!(ecs.get::<Vel>(e), Some(&Vel(2)));
+        assert!(ecs.has_bundle::<Body>(e));
+        assert_eq!(ecs.take::<Body>(e), Some(Body { pos: Pos(1), vel: Vel(2) }));
+        assert!(!ecs.has::<Pos>(e) && !ecs.has::<Vel>(e));
+
+        let t = ecs.spawn(Pair(Name("t"), Body { pos: Pos(3), vel: Vel(4) }));
+        assert_eq!(ecs.take::<Pair>(t), Some(Pair(Name("t"), Body { pos: Pos(3), vel: Vel(4) })));
+    }
+
+    #[test]
+    fn nested_bundles_are_flattened() {
+        let mut ecs = Ecs::new();
+        let e = ecs.spawn(actor());
+        assert_eq!(ecs.get::<Name>(e), Some(&Name("a")));
+        assert_eq!(ecs.get::<Pos>(e), Some(&Pos(1)));
+        assert_eq!(ecs.get::<u8>(e), Some(&7));
+        assert!(ecs.has::<Tag>(e));
+        assert!(ecs.has_bundle::<Actor>(e) && ecs.has_bundle::<Body>(e));
+        // the nested part alone can be taken back out
+        assert_eq!(ecs.take::<Body>(e), Some(Body { pos: Pos(1), vel: Vel(2) }));
+        assert!(!ecs.has_bundle::<Actor>(e));
+        assert!(ecs.has::<Name>(e));
+
+        // through commands as well
+        let mut commands = Commands::default();
+        let c = commands.spawn(&mut ecs, actor());
+        assert!(!ecs.has::<Name>(c));
+        commands.apply(&mut ecs);
+        assert_eq!(ecs.take::<Actor>(c), Some(actor()));
+    }
+
+    #[test]
+    fn remove_bundle_removes_what_is_present() {
+        let mut ecs = Ecs::new();
+        let e = ecs.spawn(actor());
+        ecs.insert(e, 1.5f32);
+        ecs.remove::<Vel>(e);
+        ecs.remove_bundle::<Actor>(e);
+        assert!(!ecs.has::<Name>(e) && !ecs.has::<Pos>(e) && !ecs.has::<Tag>(e) && !ecs.has::<u8>(e));
+        assert_eq!(ecs.get::<f32>(e), Some(&1.5));
+        assert!(ecs.is_alive(e));
+    }
+
+    #[test]
+    fn partial_matches() {
+        let mut ecs = Ecs::new();
+        let e = ecs.spawn(actor());
+        ecs.remove::<Vel>(e);
+        // take checks first and leaves everything in place
+        assert_eq!(ecs.take::<Actor>(e), None);
+        assert!(ecs.has::<Name>(e) && ecs.has::<Pos>(e) && ecs.has::<Tag>(e));
+        // take_components on its own removes every component it found, before and after the missing one
+        assert_eq!(Actor::take_components(&mut ecs, e), None);
+        assert!(!ecs.has::<Name>(e) && !ecs.has::<Pos>(e) && !ecs.has::<Tag>(e) && !ecs.has::<u8>(e));
+    }
+}
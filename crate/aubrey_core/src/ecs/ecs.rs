@@ -5,10 +5,13 @@ use crate::ecs::entity::Entity;
 use crate::resources::Resources;
 use crate::ecs::bundle::Bundle;
 use crate::ecs::registry::{Registry, ComponentId, ResourceId};
+use crate::ecs::snapshot::SnapshotTypes;
+use crate::ecs::relation::ErasedRelation;
+use crate::ecs::prefab::CloneTypes;
 
 pub struct Ecs {
-    next_id: u64,
-    alive: HashSet<Entity>,
+    pub(crate) next_id: u64,
+    pub(crate) alive: HashSet<Entity>,
     // Per-type component storage boxed behind Any
     components: HashMap<TypeId, Box<dyn ErasedStore>>, 
     pub(crate) resources: Resources,
@@ -16,6 +19,9 @@ pub struct Ecs {
     dyn_components: HashMap<ComponentId, DynStore>,
     dyn_resources: HashMap<ResourceId, Box<dyn Any + Send + Sync>>,
     registry: Registry,
+    pub(crate) snapshot_types: SnapshotTypes,
+    pub(crate) relations: HashMap<TypeId, Box<dyn ErasedRelation>>,
+    pub(crate) clone_types: CloneTypes,
 }
 
 impl Ecs {
@@ -28,6 +34,9 @@ impl Ecs {
             dyn_components: HashMap::new(),
             dyn_resources: HashMap::new(),
             registry: Registry::new(),
+            snapshot_types: SnapshotTypes::default(),
+            relations: HashMap::new(),
+            clone_types: CloneTypes::default(),
         }
     }
 
@@ -47,10 +56,8 @@ impl Ecs {
 
     pub fn despawn(&mut self, entity: Entity) {
         if self.alive.remove(&entity) {
-            // remove from all component stores
-            for store in self.components.values_mut() {
-                store.remove(entity);
-            }
+            // remove from all component stores and relations
+            self.remove_from_all_stores(entity);
         }
     }
 
@@ -65,6 +72,10 @@ impl Ecs {
         store.map.insert(entity, component);
     }
 
+    pub fn remove<T: 'static + Send + Sync>(&mut self, entity: Entity) -> Option<T> {
+        self.get_store_mut::<T>().and_then(|store| store.map.remove(&entity))
+    }
+
     pub fn get<T: 'static + Send + Sync>(&self, entity: Entity) -> Option<&T> {
         self.get_store::<T>()
             .and_then(|store| store.map.get(&entity))
@@ -217,6 +228,22 @@ impl Ecs {
         self.resources.get_mut::<Commands>().expect("Commands should be present")
     }
 
+    pub(crate) fn erased_stores(&self) -> impl Iterator<Item = (&TypeId, &dyn ErasedStore)> {
+        self.components.iter().map(|(id, s)| (id, &**s))
+    }
+
+    pub(crate) fn dyn_component_ids_of(&self, entity: Entity) -> Vec<ComponentId> {
+        self.dyn_components.iter().filter(|(_, s)| s.contains(entity)).map(|(id, _)| *id).collect()
+    }
+
+    pub(crate) fn registry_ref(&self) -> &Registry { &self.registry }
+
+    pub(crate) fn remove_from_all_stores(&mut self, entity: Entity) {
+        for store in self.components.values_mut() { store.remove(entity); }
+        for store in self.dyn_components.values_mut() { store.remove(entity); }
+        for rel in self.relations.values_mut() { rel.remove_entity(entity); }
+    }
+
     // Low-level component store accessors for Query/erased ops
     pub(crate) fn get_store<T: 'static + Send + Sync>(&self) -> Option<&ComponentStore<T>> {
         self.components
@@ -260,6 +287,12 @@ pub(crate) trait ErasedStore: Send + Sync + 'static {
     fn as_any_mut(&mut self) -> &mut dyn Any;
     fn insert_boxed(&mut self, entity: Entity, value: Box<dyn Any + Send + Sync>);
     fn remove(&mut self, entity: Entity);
+    // --- inspection ---
+    fn type_name(&self) -> &'static str;
+    fn len(&self) -> usize;
+    fn contains(&self, entity: Entity) -> bool;
+    fn component_size(&self) -> usize;
+    fn memory_estimate(&self) -> usize;
 }
 
 impl<T: 'static + Send + Sync> ErasedStore for ComponentStore<T> {
@@ -270,6 +303,14 @@ impl<T: 'static + Send + Sync> ErasedStore for ComponentStore<T> {
         self.map.insert(entity, *v);
     }
     fn remove(&mut self, entity: Entity) { self.map.remove(&entity); }
+    fn type_name(&self) -> &'static str { std::any::type_name::<T>() }
+    fn len(&self) -> usize { self.map.len() }
+    fn contains(&self, entity: Entity) -> bool { self.map.contains_key(&entity) }
+    fn component_size(&self) -> usize { std::mem::size_of::<T>() }
+    fn memory_estimate(&self) -> usize {
+        // capacity * (key + value + 1 control byte); rough HashMap footprint
+        self.map.capacity() * (std::mem::size_of::<Entity>() + std::mem::size_of::<T>() + 1)
+    }
 }
 
 // ----- Commands (deferred ops per-stage) -----
@@ -278,7 +319,9 @@ pub struct Commands {
     queue: Vec<Command>,
 }
 
-pub(crate) enum Command {
+// pub only so `#[derive(Bundle)]` output can name it in `Bundle::write_commands`
+#[doc(hidden)]
+pub enum Command {
     Spawn(Entity),
     Despawn(Entity),
     Insert { entity: Entity, type_id: TypeId, value: Box<dyn Any + Send + Sync> },
@@ -325,14 +368,8 @@ impl Commands {
                 }
                 Command::Despawn(e) => {
                     ecs.alive.remove(&e);
-                    // best-effort clean up per store
-                    for store in ecs.components.values_mut() {
-                        store.remove(e);
-                    }
-                    // clean up dynamic stores as well
-                    for store in ecs.dyn_components.values_mut() {
-                        store.remove(e);
-                    }
+                    // best-effort clean up per store (typed and dynamic)
+                    ecs.remove_from_all_stores(e);
                 }
                 Command::Insert { entity, type_id, value } => {
                     insert_erased(ecs, entity, type_id, value);
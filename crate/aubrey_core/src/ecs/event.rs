@@ -0,0 +1,90 @@
+use std::marker::PhantomData;
+
+use crate::ecs::ecs::Ecs;
+
+/// Double-buffered event queue resource. Events stay readable for the frame they were sent in
+/// and the next one; `update` (run at `Stage::First` by `App::add_event`) drops older ones.
+/// ```ignore
+/// app.add_event::<VfsEvent>();
+/// let mut cursor = EventCursor::<VfsEvent>::default();
+/// app.add_systems(Stage::Update, move |ecs: &mut Ecs| {
+///     let Some(events) = ecs.get_resource::<Events<VfsEvent>>() else { return };
+///     for ev in cursor.read(events) { /* ... */ }
+/// });
+/// ```
+pub struct Events<T> {
+    // (sequence id, event); `previous` is the last frame, `current` this frame
+    previous: Vec<(u64, T)>,
+    current: Vec<(u64, T)>,
+    next_id: u64,
+}
+
+impl<T> Default for Events<T> {
+    fn default() -> Self { Self { previous: Vec::new(), current: Vec::new(), next_id: 0 } }
+}
+
+impl<T> Events<T> {
+    pub fn send(&mut self, event: T) {
+        self.current.push((self.next_id, event));
+        self.next_id += 1;
+    }
+
+    pub fn extend(&mut self, events: impl IntoIterator<Item = T>) {
+        for e in events { self.send(e); }
+    }
+
+    /// Swap buffers: events sent before the previous `update` are dropped.
+    pub fn update(&mut self) {
+        self.previous = std::mem::take(&mut self.current);
+    }
+
+    /// All live events, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        self.previous.iter().chain(self.current.iter()).map(|(_, e)| e)
+    }
+
+    pub fn len(&self) -> usize { self.previous.len() + self.current.len() }
+
+    pub fn is_empty(&self) -> bool { self.previous.is_empty() && self.current.is_empty() }
+
+    pub fn clear(&mut self) {
+        self.previous.clear();
+        self.current.clear();
+    }
+
+    /// Remove and return all live events.
+    pub fn drain(&mut self) -> impl Iterator<Item = T> + '_ {
+        self.previous.drain(..).chain(self.current.drain(..)).map(|(_, e)| e)
+    }
+}
+
+/// Per-reader position in an `Events<T>`; each reader sees every event once.
+/// Keep it in the system closure (or a resource) between frames.
+pub struct EventCursor<T> {
+    next: u64,
+    _m: PhantomData<fn() -> T>,
+}
+
+impl<T> Default for EventCursor<T> {
+    fn default() -> Self { Self { next: 0, _m: PhantomData } }
+}
+
+impl<T> EventCursor<T> {
+    /// Events not yet seen by this cursor.
+    pub fn read<'a>(&mut self, events: &'a Events<T>) -> impl Iterator<Item = &'a T> + 'a {
+        let from = self.next;
+        self.next = events.next_id;
+        events.previous.iter().chain(events.current.iter()).filter(move |(id, _)| *id >= from).map(|(_, e)| e)
+    }
+}
+
+pub(crate) fn update_events<T: 'static + Send + Sync>(ecs: &mut Ecs) {
+    if let Some(events) = ecs.get_resource_mut::<Events<T>>() { events.update(); }
+}
+
+impl Ecs {
+    /// Queue an event; no-op if `Events<T>` was not added.
+    pub fn send_event<T: 'static + Send + Sync>(&mut self, event: T) {
+        if let Some(events) = self.get_resource_mut::<Events<T>>() { events.send(event); }
+    }
+}
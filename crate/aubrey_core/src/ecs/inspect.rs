@@ -0,0 +1,250 @@
+use std::any::TypeId;
+use std::fmt::Write as _;
+
+use crate::ecs::ecs::Ecs;
+use crate::ecs::entity::Entity;
+use crate::ecs::registry::ComponentId;
+
+/// Component attached to an entity (typed store).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ComponentInfo {
+    pub type_id: TypeId,
+    pub type_name: &'static str,
+}
+
+/// Per-type store statistics. `bytes_estimate` is a rough footprint from the map capacity.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StoreInfo {
+    pub type_id: TypeId,
+    pub type_name: &'static str,
+    pub count: usize,
+    pub component_size: usize,
+    pub bytes_estimate: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EntityDump {
+    pub entity: Entity,
+    pub components: Vec<&'static str>,
+    // dynamic components by registry name (or `#id` if unnamed)
+    pub dyn_components: Vec<String>,
+}
+
+/// Deterministic snapshot of the world layout (entity ids ascending, names sorted).
+/// Values are not included; this is meant for snapshot tests and the editor inspector.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct WorldDump {
+    pub entities: Vec<EntityDump>,
+    pub stores: Vec<StoreInfo>,
+    pub resources: Vec<&'static str>,
+}
+
+impl WorldDump {
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "entities: {}", self.entities.len());
+        for e in &self.entities {
+            let _ = writeln!(out, "  {}", e.entity.id());
+            for c in &e.components { let _ = writeln!(out, "    {}", c); }
+            for c in &e.dyn_components { let _ = writeln!(out, "    dyn {}", c); }
+        }
+        let _ = writeln!(out, "stores: {}", self.stores.len());
+        for s in &self.stores {
+            let _ = writeln!(out, "  {} count={} size={} bytes~{}", s.type_name, s.count, s.component_size, s.bytes_estimate);
+        }
+        let _ = writeln!(out, "resources: {}", self.resources.len());
+        for r in &self.resources { let _ = writeln!(out, "  {}", r); }
+        out
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut out = String::from("{\"entities\":[");
+        for (i, e) in self.entities.iter().enumerate() {
+            if i > 0 { out.push(','); }
+            let _ = write!(out, "{{\"id\":{},\"components\":[", e.entity.id());
+            push_json_strs(&mut out, e.components.iter().copied());
+            out.push_str("],\"dyn_components\":[");
+            push_json_strs(&mut out, e.dyn_components.iter().map(|s| s.as_str()));
+            out.push_str("]}");
+        }
+        out.push_str("],\"stores\":[");
+        for (i, s) in self.stores.iter().enumerate() {
+            if i > 0 { out.push(','); }
+            out.push_str("{\"type\":");
+            push_json_str(&mut out, s.type_name);
+            let _ = write!(out, ",\"count\":{},\"size\":{},\"bytes_estimate\":{}}}", s.count, s.component_size, s.bytes_estimate);
+        }
+        out.push_str("],\"resources\":[");
+        push_json_strs(&mut out, self.resources.iter().copied());
+        out.push_str("]}");
+        out
+    }
+}
+
+impl std::fmt::Display for WorldDump {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result { f.write_str(&self.to_text()) }
+}
+
+pub(crate) fn push_json_str(out: &mut String, s: &str) {
+    out.push('"');
+    for ch in s.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => { let _ = write!(out, "\\u{:04x}", c as u32); }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+fn push_json_strs<'a>(out: &mut String, items: impl Iterator<Item = &'a str>) {
+    for (i, s) in items.enumerate() {
+        if i > 0 { out.push(','); }
+        push_json_str(out, s);
+    }
+}
+
+// Inspection helpers on Ecs
+impl Ecs {
+    /// Alive entities sorted by id.
+    pub fn entities(&self) -> Vec<Entity> {
+        let mut out: Vec<Entity> = self.query_dyn(&[]);
+        out.sort_by_key(|e| e.id());
+        out
+    }
+
+    /// Typed components attached to `entity`, sorted by type name.
+    pub fn components_of(&self, entity: Entity) -> Vec<ComponentInfo> {
+        let mut out: Vec<ComponentInfo> = self
+            .erased_stores()
+            .filter(|(_, s)| s.contains(entity))
+            .map(|(id, s)| ComponentInfo { type_id: *id, type_name: s.type_name() })
+            .collect();
+        out.sort_by(|a, b| a.type_name.cmp(b.type_name));
+        out
+    }
+
+    /// Dynamic (id-based) components attached to `entity`, sorted by id.
+    pub fn dyn_components_of(&self, entity: Entity) -> Vec<ComponentId> {
+        let mut out = self.dyn_component_ids_of(entity);
+        out.sort_by_key(|c| c.0);
+        out
+    }
+
+    /// Statistics for every typed component store, sorted by type name.
+    pub fn store_infos(&self) -> Vec<StoreInfo> {
+        let mut out: Vec<StoreInfo> = self
+            .erased_stores()
+            .map(|(id, s)| StoreInfo {
+                type_id: *id,
+                type_name: s.type_name(),
+                count: s.len(),
+                component_size: s.component_size(),
+                bytes_estimate: s.memory_estimate(),
+            })
+            .collect();
+        out.sort_by(|a, b| a.type_name.cmp(b.type_name));
+        out
+    }
+
+    /// Sum of `StoreInfo::bytes_estimate` over all typed stores.
+    pub fn memory_estimate(&self) -> usize {
+        self.erased_stores().map(|(_, s)| s.memory_estimate()).sum()
+    }
+
+    pub fn dump(&self) -> WorldDump {
+        let entities = self
+            .entities()
+            .into_iter()
+            .map(|e| EntityDump {
+                entity: e,
+                components: self.components_of(e).into_iter().map(|c| c.type_name).collect(),
+                dyn_components: self
+                    .dyn_components_of(e)
+                    .into_iter()
+                    .map(|c| self.registry_ref().component_name(c).map(|n| n.to_string()).unwrap_or_else(|| format!("#{}", c.0)))
+                    .collect(),
+            })
+            .collect();
+        WorldDump { entities, stores: self.store_infos(), resources: self.resources.type_names() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Pos(f32);
+    struct Tag;
+    struct Score;
+
+    fn sample() -> WorldDump {
+        WorldDump {
+            entities: vec![
+                EntityDump { entity: Entity::new(0), components: vec!["game::Pos", "game::Tag"], dyn_components: vec!["health".into()] },
+                EntityDump { entity: Entity::new(3), components: vec![], dyn_components: vec![] },
+            ],
+            stores: vec![StoreInfo { type_id: TypeId::of::<Pos>(), type_name: "game::Pos", count: 1, component_size: 4, bytes_estimate: 64 }],
+            resources: vec!["game::Score"],
+        }
+    }
+
+    #[test]
+    fn to_text_layout() {
+        assert_eq!(
+            sample().to_text(),
+            "entities: 2\n  0\n    game::Pos\n    game::Tag\n    dyn health\n  3\n\
+             stores: 1\n  game::Pos count=1 size=4 bytes~64\nresources: 1\n  game::Score\n"
+        );
+        assert_eq!(sample().to_string(), sample().to_text());
+    }
+
+    #[test]
+    fn to_json_layout() {
+        assert_eq!(
+            sample().to_json(),
+            r#"{"entities":[{"id":0,"components":["game::Pos","game::Tag"],"dyn_components":["health"]},{"id":3,"components":[],"dyn_components":[]}],"#.to_string()
+                + r#""stores":[{"type":"game::Pos","count":1,"size":4,"bytes_estimate":64}],"resources":["game::Score"]}"#
+        );
+    }
+
+    #[test]
+    fn json_string_escaping() {
+        let esc = |s: &str| { let mut out = String::new(); push_json_str(&mut out, s); out };
+        assert_eq!(esc(r#"say "hi""#), r#""say \"hi\"""#);
+        assert_eq!(esc(r"a\b"), r#""a\\b""#);
+        assert_eq!(esc("l1\nl2\r\t"), r#""l1\nl2\r\t""#);
+        assert_eq!(esc("\u{0}\u{1b}\u{1f} "), r#""\u0000\u001b\u001f ""#);
+        assert_eq!(esc("héllo 世界 🦀"), "\"héllo 世界 🦀\"");
+    }
+
+    #[test]
+    fn dump_is_sorted_and_names_dyn_components() {
+        let mut ecs = Ecs::new();
+        let health = ecs.registry().register_component("health");
+        let unnamed = ComponentId(99);
+        let a = ecs.spawn_one(Pos(1.0));
+        ecs.insert(a, Tag);
+        ecs.insert_dyn(a, unnamed, Box::new(1u8));
+        ecs.insert_dyn(a, health, Box::new(10u32));
+        let gone = ecs.spawn_one(Tag);
+        let b = ecs.spawn_empty();
+        ecs.despawn(gone);
+        ecs.insert_resource(Score);
+
+        let dump = ecs.dump();
+        assert_eq!(ecs.get::<Pos>(a).map(|p| p.0), Some(1.0));
+        assert_eq!(dump.entities.iter().map(|e| e.entity).collect::<Vec<_>>(), vec![a, b]);
+        assert_eq!(dump.entities[0].components, vec![std::any::type_name::<Pos>(), std::any::type_name::<Tag>()]);
+        assert_eq!(dump.entities[0].dyn_components, vec!["health".to_string(), "#99".to_string()]);
+        assert!(dump.entities[1].components.is_empty());
+        assert_eq!(dump.stores.iter().map(|s| (s.type_name, s.count)).collect::<Vec<_>>(),
+                   vec![(std::any::type_name::<Pos>(), 1), (std::any::type_name::<Tag>(), 1)]);
+        assert_eq!(dump.resources, vec![std::any::type_name::<Score>()]);
+        assert_eq!(dump, ecs.dump());
+    }
+}
@@ -6,10 +6,20 @@ pub mod query;
 pub mod bundle;
 pub mod registry;
 pub mod children;
+pub mod inspect;
+pub mod snapshot;
+pub mod relation;
+pub mod prefab;
+pub mod event;
 
 pub use entity::Entity;
 pub use schedule::Stage;
 pub use ecs::{Ecs, Commands};
 pub use bundle::{Bundle, Single as One};
+pub use aubrey_macros::Bundle;
 pub use registry::{Registry, ComponentId, ResourceId};
 pub use children::Children;
+pub use inspect::{ComponentInfo, StoreInfo, EntityDump, WorldDump};
+pub use snapshot::{Snapshot, SnapshotDiff};
+pub use prefab::{Prefab, PrefabFormat, PrefabInstance, PrefabLoader, PrefabNode, PrefabOverrides};
+pub use event::{Events, EventCursor};
@@ -0,0 +1,570 @@
+use std::any::{Any, TypeId};
+use std::collections::{HashMap, HashSet};
+
+use crate::asset::{AssetLoader, BoxError, LoadContext};
+use crate::ecs::children::Children;
+use crate::ecs::ecs::Ecs;
+use crate::ecs::entity::Entity;
+use crate::save::SaveData;
+
+// .prefab layout (little endian):
+//   magic "APFB", format u16, node count u32,
+//   per node: child count u32, child node indices u32, component count u32,
+//             per component: key = len u16 + utf-8, data = len u32 + bytes
+// Children always come after their parent and every node but the root has one parent.
+const MAGIC: &[u8; 4] = b"APFB";
+const FORMAT: u16 = 1;
+
+// One stored component value, re-inserted by clone.
+trait StoredComponent: Send + Sync {
+    fn component_type(&self) -> TypeId;
+    fn type_name(&self) -> &'static str;
+    fn value(&self) -> &dyn Any;
+    fn insert_into(&self, ecs: &mut Ecs, entity: Entity);
+    fn clone_box(&self) -> Box<dyn StoredComponent>;
+}
+
+struct Stored<T>(T);
+
+impl<T: 'static + Send + Sync + Clone> StoredComponent for Stored<T> {
+    fn component_type(&self) -> TypeId { TypeId::of::<T>() }
+    fn type_name(&self) -> &'static str { std::any::type_name::<T>() }
+    fn value(&self) -> &dyn Any { &self.0 }
+    fn insert_into(&self, ecs: &mut Ecs, entity: Entity) { ecs.insert::<T>(entity, self.0.clone()); }
+    fn clone_box(&self) -> Box<dyn StoredComponent> { Box::new(Stored(self.0.clone())) }
+}
+
+type CaptureFn = fn(&Ecs, Entity) -> Option<Box<dyn StoredComponent>>;
+
+fn capture<T: 'static + Send + Sync + Clone>(ecs: &Ecs, entity: Entity) -> Option<Box<dyn StoredComponent>> {
+    ecs.get::<T>(entity).map(|c| Box::new(Stored(c.clone())) as Box<dyn StoredComponent>)
+}
+
+/// Component types opted in to cloning via `Ecs::register_clone`.
+#[derive(Default)]
+pub(crate) struct CloneTypes {
+    map: HashMap<TypeId, CaptureFn>,
+}
+
+impl Ecs {
+    /// Opt `T` in to `clone_entity` / `clone_subtree` / `Prefab::from_entity`.
+    /// Unregistered components are not copied.
+    pub fn register_clone<T: 'static + Send + Sync + Clone>(&mut self) {
+        self.clone_types.map.insert(TypeId::of::<T>(), capture::<T>);
+    }
+
+    fn capture_components(&self, entity: Entity) -> Vec<Box<dyn StoredComponent>> {
+        let children = TypeId::of::<Children>();
+        self.clone_types
+            .map
+            .iter()
+            .filter(|(id, _)| **id != children)
+            .filter_map(|(_, f)| f(self, entity))
+            .collect()
+    }
+
+    /// Spawn a copy of `entity` with its registered components. `Children` is not copied
+    /// (use `clone_subtree` for that). None if `entity` is dead.
+    pub fn clone_entity(&mut self, entity: Entity) -> Option<Entity> {
+        if !self.is_alive(entity) { return None; }
+        let comps = self.capture_components(entity);
+        let e = self.spawn_empty();
+        for c in &comps { c.insert_into(self, e); }
+        Some(e)
+    }
+
+    /// Clone `entity` and its `Children` subtree; the copy gets its own `Children` list.
+    pub fn clone_subtree(&mut self, entity: Entity) -> Option<Entity> {
+        let prefab = Prefab::from_entity(self, entity)?;
+        Some(self.instantiate(&prefab).root())
+    }
+
+    /// Spawn the prefab tree; returns the spawned entities.
+    pub fn instantiate(&mut self, prefab: &Prefab) -> PrefabInstance {
+        self.instantiate_with(prefab, &PrefabOverrides::new())
+    }
+
+    /// Spawn the prefab tree, then apply `overrides` on top of the template values.
+    pub fn instantiate_with(&mut self, prefab: &Prefab, overrides: &PrefabOverrides) -> PrefabInstance {
+        let entities: Vec<Entity> = prefab.nodes.iter().map(|_| self.spawn_empty()).collect();
+        for (node, e) in prefab.nodes.iter().zip(entities.iter()) {
+            for c in &node.components { c.insert_into(self, *e); }
+            if !node.children.is_empty() {
+                self.insert(*e, Children(node.children.iter().map(|i| entities[*i]).collect()));
+            }
+        }
+        for (node, c) in &overrides.values {
+            if let Some(e) = entities.get(node.0) { c.insert_into(self, *e); }
+        }
+        PrefabInstance { entities }
+    }
+}
+
+/// Node in a `Prefab` tree. `Prefab::ROOT` is always present.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct PrefabNode(usize);
+
+struct Node {
+    components: Vec<Box<dyn StoredComponent>>,
+    children: Vec<usize>,
+}
+
+/// Reusable entity subtree template. Build it by hand, capture an existing subtree with
+/// `from_entity` or load a `.prefab` file (`PrefabLoader`), then spawn copies with `Ecs::instantiate`.
+/// ```ignore
+/// let mut cell = Prefab::new();
+/// cell.insert(Prefab::ROOT, MarginComponent::all(Size::Px(8.0)));
+/// let ph = cell.add_child(Prefab::ROOT).unwrap();
+/// cell.insert(ph, PlaceholderWidget::default());
+///
+/// let red = app.instantiate_with(&cell, &PrefabOverrides::new().set(ph, PlaceholderWidget { color: RED }));
+/// ```
+pub struct Prefab {
+    nodes: Vec<Node>,
+}
+
+impl Default for Prefab {
+    fn default() -> Self { Self::new() }
+}
+
+impl Clone for Prefab {
+    fn clone(&self) -> Self {
+        let nodes = self
+            .nodes
+            .iter()
+            .map(|n| Node { components: n.components.iter().map(|c| c.clone_box()).collect(), children: n.children.clone() })
+            .collect();
+        Self { nodes }
+    }
+}
+
+impl std::fmt::Debug for Prefab {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.nodes.iter().map(|n| (n.components.iter().map(|c| c.type_name()).collect::<Vec<_>>(), &n.children)))
+            .finish()
+    }
+}
+
+impl Prefab {
+    pub const ROOT: PrefabNode = PrefabNode(0);
+
+    /// Template with an empty root node.
+    pub fn new() -> Self { Self { nodes: vec![Node { components: Vec::new(), children: Vec::new() }] } }
+
+    /// Capture `entity` and its `Children` subtree (registered clone types only).
+    /// Dead children and repeated entities are skipped. None if `entity` is dead.
+    pub fn from_entity(ecs: &Ecs, entity: Entity) -> Option<Self> {
+        if !ecs.is_alive(entity) { return None; }
+        let mut prefab = Self { nodes: Vec::new() };
+        let mut stack = vec![(entity, None::<usize>)];
+        // an entity listed twice (or a `Children` cycle) is captured once, at its first visit
+        let mut visited = HashSet::new();
+        while let Some((e, parent)) = stack.pop() {
+            if !visited.insert(e) { continue; }
+            let idx = prefab.nodes.len();
+            prefab.nodes.push(Node { components: ecs.capture_components(e), children: Vec::new() });
+            if let Some(p) = parent { prefab.nodes[p].children.push(idx); }
+            if let Some(children) = ecs.get::<Children>(e) {
+                // reversed so children are visited (and numbered) in order
+                for c in children.0.iter().rev().filter(|c| ecs.is_alive(**c)) { stack.push((*c, Some(idx))); }
+            }
+        }
+        Some(prefab)
+    }
+
+    /// Add or replace component `T` on `node`. No-op if `node` is not in this prefab.
+    pub fn insert<T: 'static + Send + Sync + Clone>(&mut self, node: PrefabNode, component: T) -> &mut Self {
+        if let Some(n) = self.nodes.get_mut(node.0) {
+            n.components.retain(|c| c.component_type() != TypeId::of::<T>());
+            n.components.push(Box::new(Stored(component)));
+        }
+        self
+    }
+
+    pub fn remove<T: 'static>(&mut self, node: PrefabNode) -> &mut Self {
+        if let Some(n) = self.nodes.get_mut(node.0) { n.components.retain(|c| c.component_type() != TypeId::of::<T>()); }
+        self
+    }
+
+    /// Append an empty child under `parent`. None if `parent` is not in this prefab.
+    pub fn add_child(&mut self, parent: PrefabNode) -> Option<PrefabNode> {
+        let idx = self.nodes.len();
+        self.nodes.get_mut(parent.0)?.children.push(idx);
+        self.nodes.push(Node { components: Vec::new(), children: Vec::new() });
+        Some(PrefabNode(idx))
+    }
+
+    /// `index`-th child of `node`.
+    pub fn child(&self, node: PrefabNode, index: usize) -> Option<PrefabNode> {
+        self.nodes.get(node.0)?.children.get(index).map(|i| PrefabNode(*i))
+    }
+
+    pub fn children(&self, node: PrefabNode) -> impl Iterator<Item = PrefabNode> + '_ {
+        self.nodes.get(node.0).into_iter().flat_map(|n| n.children.iter().map(|i| PrefabNode(*i)))
+    }
+
+    pub fn has<T: 'static>(&self, node: PrefabNode) -> bool {
+        self.nodes.get(node.0).is_some_and(|n| n.components.iter().any(|c| c.component_type() == TypeId::of::<T>()))
+    }
+
+    /// Number of nodes (root included).
+    pub fn len(&self) -> usize { self.nodes.len() }
+
+    pub fn is_empty(&self) -> bool { self.nodes.is_empty() }
+}
+
+/// Per-instance component values applied after the template ones.
+#[derive(Default)]
+pub struct PrefabOverrides {
+    values: Vec<(PrefabNode, Box<dyn StoredComponent>)>,
+}
+
+impl PrefabOverrides {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn set<T: 'static + Send + Sync + Clone>(mut self, node: PrefabNode, component: T) -> Self {
+        self.values.push((node, Box::new(Stored(component))));
+        self
+    }
+}
+
+/// Entities spawned by one `instantiate` call.
+#[derive(Clone, Debug)]
+pub struct PrefabInstance {
+    entities: Vec<Entity>,
+}
+
+impl PrefabInstance {
+    pub fn root(&self) -> Entity { self.entities[0] }
+
+    /// Spawned entity for `node`.
+    pub fn entity(&self, node: PrefabNode) -> Option<Entity> { self.entities.get(node.0).copied() }
+
+    /// All spawned entities in node order.
+    pub fn entities(&self) -> &[Entity] { &self.entities }
+}
+
+type EncodeFn = fn(&dyn Any) -> Option<Vec<u8>>;
+type DecodeFn = fn(&[u8]) -> Result<Box<dyn StoredComponent>, BoxError>;
+
+fn encode_component<T: SaveData>(value: &dyn Any) -> Option<Vec<u8>> { value.downcast_ref::<T>().map(T::save) }
+
+fn decode_component<T: SaveData + Clone>(data: &[u8]) -> Result<Box<dyn StoredComponent>, BoxError> {
+    Ok(Box::new(Stored(T::load(data)?)))
+}
+
+/// Component types a `.prefab` file can hold, each under a stable key (as in `SaveStore`)
+/// and encoded with its `SaveData` impl.
+/// ```ignore
+/// let format = PrefabFormat::new().component::<Health>("health").component::<Name>("name");
+/// vfs.write("/prefabs/enemy.prefab", &format.encode(&enemy)?)?;
+/// assets.add_loader(PrefabLoader(format));
+/// ```
+#[derive(Clone, Default)]
+pub struct PrefabFormat {
+    keys: HashMap<TypeId, (String, EncodeFn)>,
+    decoders: HashMap<String, DecodeFn>,
+}
+
+impl PrefabFormat {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn component<T: SaveData + Clone>(mut self, key: &str) -> Self {
+        self.keys.insert(TypeId::of::<T>(), (key.to_string(), encode_component::<T>));
+        self.decoders.insert(key.to_string(), decode_component::<T>);
+        self
+    }
+
+    /// Serialize `prefab`. Fails if it holds a component type without a key.
+    pub fn encode(&self, prefab: &Prefab) -> Result<Vec<u8>, BoxError> {
+        let mut out = Vec::new();
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&FORMAT.to_le_bytes());
+        out.extend_from_slice(&(prefab.nodes.len() as u32).to_le_bytes());
+        for node in &prefab.nodes {
+            out.extend_from_slice(&(node.children.len() as u32).to_le_bytes());
+            for c in &node.children { out.extend_from_slice(&(*c as u32).to_le_bytes()); }
+            let mut comps = Vec::with_capacity(node.components.len());
+            for c in &node.components {
+                let (key, encode) = self.keys.get(&c.component_type()).ok_or_else(|| format!("no prefab key for {}", c.type_name()))?;
+                comps.push((key.as_str(), encode(c.value()).ok_or("prefab component type mismatch")?));
+            }
+            // sorted so the output doesn't depend on capture order
+            comps.sort();
+            out.extend_from_slice(&(comps.len() as u32).to_le_bytes());
+            for (key, data) in comps {
+                out.extend_from_slice(&(key.len() as u16).to_le_bytes());
+                out.extend_from_slice(key.as_bytes());
+                out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+                out.extend_from_slice(&data);
+            }
+        }
+        Ok(out)
+    }
+
+    /// Parse a prefab written by `encode`. Fails on malformed data or an unknown component key.
+    pub fn decode(&self, bytes: &[u8]) -> Result<Prefab, BoxError> {
+        let mut r = Reader { buf: bytes, pos: 0 };
+        if r.take(4)? != MAGIC { return Err("not a prefab file".into()); }
+        let format = r.u16()?;
+        if format != FORMAT { return Err(format!("unsupported prefab format {}", format).into()); }
+        let count = r.u32()? as usize;
+        if count == 0 { return Err("prefab has no root node".into()); }
+        let mut parented = vec![false; count];
+        let mut nodes = Vec::new();
+        for i in 0..count {
+            let mut children = Vec::new();
+            for _ in 0..r.u32()? {
+                let c = r.u32()? as usize;
+                if c <= i || c >= count || std::mem::replace(&mut parented[c], true) {
+                    return Err(format!("invalid child {} of prefab node {}", c, i).into());
+                }
+                children.push(c);
+            }
+            let mut components = Vec::new();
+            for _ in 0..r.u32()? {
+                let len = r.u16()? as usize;
+                let key = std::str::from_utf8(r.take(len)?)?;
+                let len = r.u32()? as usize;
+                let data = r.take(len)?;
+                let decode = self.decoders.get(key).ok_or_else(|| format!("unknown prefab component \"{}\"", key))?;
+                components.push(decode(data).map_err(|e| format!("prefab component \"{}\": {}", key, e))?);
+            }
+            nodes.push(Node { components, children });
+        }
+        if r.pos != bytes.len() { return Err("trailing data after prefab".into()); }
+        if let Some(i) = parented.iter().skip(1).position(|p| !p) { return Err(format!("prefab node {} has no parent", i + 1).into()); }
+        Ok(Prefab { nodes })
+    }
+}
+
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], BoxError> {
+        let end = self.pos.checked_add(n).filter(|e| *e <= self.buf.len()).ok_or("truncated prefab data")?;
+        let out = &self.buf[self.pos..end];
+        self.pos = end;
+        Ok(out)
+    }
+    fn u16(&mut self) -> Result<u16, BoxError> { Ok(u16::from_le_bytes(self.take(2)?.try_into()?)) }
+    fn u32(&mut self) -> Result<u32, BoxError> { Ok(u32::from_le_bytes(self.take(4)?.try_into()?)) }
+}
+
+/// Loads `.prefab` files as `Prefab` with the given format. Not registered by default,
+/// since the component keys belong to the game.
+pub struct PrefabLoader(pub PrefabFormat);
+
+impl AssetLoader for PrefabLoader {
+    type Asset = Prefab;
+    fn extensions(&self) -> &[&str] { &["prefab"] }
+    fn load(&self, ctx: &mut LoadContext) -> Result<Prefab, BoxError> { self.0.decode(ctx.bytes()) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset::AssetServer;
+    use crate::fs::{MemBackend, Vfs};
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Hp(u32);
+    #[derive(Clone, Debug, PartialEq)]
+    struct Name(String);
+    // never registered for cloning
+    #[derive(Clone, Debug, PartialEq)]
+    struct Cache(u32);
+
+    impl SaveData for Hp {
+        fn save(&self) -> Vec<u8> { self.0.save() }
+        fn load(data: &[u8]) -> Result<Self, BoxError> { u32::load(data).map(Hp) }
+    }
+    impl SaveData for Name {
+        fn save(&self) -> Vec<u8> { self.0.save() }
+        fn load(data: &[u8]) -> Result<Self, BoxError> { String::load(data).map(Name) }
+    }
+
+    fn ecs() -> Ecs {
+        let mut ecs = Ecs::new();
+        ecs.register_clone::<Hp>();
+        ecs.register_clone::<Name>();
+        ecs
+    }
+
+    // root(Hp 10) -> [a(Name "a"), b(Name "b") -> [c(Hp 3)]]
+    fn tree(ecs: &mut Ecs) -> Entity {
+        let c = ecs.spawn_one(Hp(3));
+        let b = ecs.spawn_one(Name("b".into()));
+        ecs.insert(b, Children(vec![c]));
+        let a = ecs.spawn_one(Name("a".into()));
+        let root = ecs.spawn_one(Hp(10));
+        ecs.insert(root, Children(vec![a, b]));
+        ecs.insert(root, Cache(1));
+        root
+    }
+
+    // (Hp, Name, children) of `e`'s subtree in depth-first order
+    fn shape(ecs: &Ecs, e: Entity) -> Vec<(Option<Hp>, Option<Name>, usize)> {
+        let mut out = Vec::new();
+        let mut stack = vec![e];
+        while let Some(e) = stack.pop() {
+            let children = ecs.get::<Children>(e).map(|c| c.0.clone()).unwrap_or_default();
+            out.push((ecs.get::<Hp>(e).cloned(), ecs.get::<Name>(e).cloned(), children.len()));
+            stack.extend(children.into_iter().rev());
+        }
+        out
+    }
+
+    #[test]
+    fn clone_entity_copies_registered_components_only() {
+        let mut ecs = ecs();
+        let root = tree(&mut ecs);
+        let copy = ecs.clone_entity(root).unwrap();
+        assert_ne!(copy, root);
+        assert_eq!(ecs.get::<Hp>(copy), Some(&Hp(10)));
+        assert!(ecs.get::<Cache>(copy).is_none());
+        assert!(ecs.get::<Children>(copy).is_none());
+        // the copy is independent
+        ecs.get_mut::<Hp>(copy).unwrap().0 = 1;
+        assert_eq!(ecs.get::<Hp>(root), Some(&Hp(10)));
+        ecs.despawn(root);
+        assert!(ecs.clone_entity(root).is_none());
+    }
+
+    #[test]
+    fn clone_subtree_copies_the_tree() {
+        let mut ecs = ecs();
+        let root = tree(&mut ecs);
+        let copy = ecs.clone_subtree(root).unwrap();
+        assert_eq!(shape(&ecs, copy), shape(&ecs, root));
+        let (orig, new) = (&ecs.get::<Children>(root).unwrap().0, &ecs.get::<Children>(copy).unwrap().0);
+        assert!(orig.iter().all(|e| !new.contains(e)));
+    }
+
+    #[test]
+    fn from_entity_survives_children_cycles() {
+        let mut ecs = ecs();
+        let root = tree(&mut ecs);
+        let a = ecs.get::<Children>(root).unwrap().0[0];
+        // a -> root closes a cycle; root is also listed twice
+        ecs.insert(a, Children(vec![root, root]));
+        let prefab = Prefab::from_entity(&ecs, root).unwrap();
+        assert_eq!(prefab.len(), 4);
+        let copy = ecs.instantiate(&prefab).root();
+        assert_eq!(shape(&ecs, copy).len(), 4);
+    }
+
+    #[test]
+    fn instantiate_applies_overrides_per_instance() {
+        let mut prefab = Prefab::new();
+        prefab.insert(Prefab::ROOT, Hp(1));
+        let child = prefab.add_child(Prefab::ROOT).unwrap();
+        prefab.insert(child, Name("template".into())).insert(child, Hp(2));
+        prefab.remove::<Hp>(child);
+
+        let mut ecs = ecs();
+        let plain = ecs.instantiate(&prefab);
+        let custom = ecs.instantiate_with(&prefab, &PrefabOverrides::new().set(child, Name("custom".into())).set(Prefab::ROOT, Hp(9)));
+        assert_eq!(plain.entities().len(), 2);
+        assert_eq!(ecs.get::<Children>(plain.root()).unwrap().0, [plain.entity(child).unwrap()]);
+        assert_eq!(ecs.get::<Hp>(plain.root()), Some(&Hp(1)));
+        assert_eq!(ecs.get::<Name>(plain.entity(child).unwrap()), Some(&Name("template".into())));
+        assert!(ecs.get::<Hp>(plain.entity(child).unwrap()).is_none());
+        assert_eq!(ecs.get::<Hp>(custom.root()), Some(&Hp(9)));
+        assert_eq!(ecs.get::<Name>(custom.entity(child).unwrap()), Some(&Name("custom".into())));
+    }
+
+    #[test]
+    fn nodes_from_another_prefab_are_ignored() {
+        let mut big = Prefab::new();
+        let far = (0..3).fold(Prefab::ROOT, |n, _| big.add_child(n).unwrap());
+        let mut small = Prefab::new();
+        small.insert(far, Hp(1)).remove::<Hp>(far);
+        assert!(small.add_child(far).is_none());
+        assert_eq!(small.children(far).count(), 0);
+        assert!(!small.has::<Hp>(far));
+        assert!(small.child(far, 0).is_none());
+        assert_eq!(small.len(), 1);
+
+        let mut ecs = ecs();
+        let inst = ecs.instantiate_with(&small, &PrefabOverrides::new().set(far, Hp(1)));
+        assert_eq!(inst.entities().len(), 1);
+        assert!(inst.entity(far).is_none());
+    }
+
+    fn format() -> PrefabFormat { PrefabFormat::new().component::<Hp>("hp").component::<Name>("name") }
+
+    #[test]
+    fn format_round_trips() {
+        let mut ecs = ecs();
+        let root = tree(&mut ecs);
+        ecs.remove::<Cache>(root);
+        let prefab = Prefab::from_entity(&ecs, root).unwrap();
+        let bytes = format().encode(&prefab).unwrap();
+        assert_eq!(format().encode(&prefab.clone()).unwrap(), bytes);
+        let loaded = format().decode(&bytes).unwrap();
+        assert_eq!(loaded.len(), 4);
+        let copy = ecs.instantiate(&loaded).root();
+        assert_eq!(shape(&ecs, copy), shape(&ecs, root));
+    }
+
+    #[test]
+    fn format_rejects_bad_input() {
+        let mut prefab = Prefab::new();
+        prefab.insert(Prefab::ROOT, Name("x".into()));
+        let child = prefab.add_child(Prefab::ROOT).unwrap();
+        prefab.insert(child, Hp(1));
+        let bytes = format().encode(&prefab).unwrap();
+        for len in 0..bytes.len() { assert!(format().decode(&bytes[..len]).is_err(), "len {}", len); }
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(format().decode(&trailing).is_err());
+
+        let err = PrefabFormat::new().component::<Hp>("hp").decode(&bytes).unwrap_err();
+        assert_eq!(err.to_string(), "unknown prefab component \"name\"");
+        let err = PrefabFormat::new().component::<Name>("name").encode(&prefab).unwrap_err();
+        assert!(err.to_string().starts_with("no prefab key for"), "{}", err);
+        // Hp stored as 4 bytes; a 2-byte value fails to decode
+        let short = PrefabFormat::new().component::<Name>("name").component::<u16>("hp");
+        let mut odd = Prefab::new();
+        odd.insert(Prefab::ROOT, 7u16);
+        assert!(format().decode(&short.encode(&odd).unwrap()).is_err());
+
+        // node 1 listed as the root's child twice
+        let mut twice = Vec::new();
+        twice.extend_from_slice(MAGIC);
+        twice.extend_from_slice(&FORMAT.to_le_bytes());
+        for v in [2u32, 2, 1, 1, 0, 0, 0] { twice.extend_from_slice(&v.to_le_bytes()); }
+        assert!(format().decode(&twice).unwrap_err().to_string().contains("invalid child"));
+        // node 1 without a parent
+        let mut orphan = Vec::new();
+        orphan.extend_from_slice(MAGIC);
+        orphan.extend_from_slice(&FORMAT.to_le_bytes());
+        for v in [2u32, 0, 0, 0, 0] { orphan.extend_from_slice(&v.to_le_bytes()); }
+        assert!(format().decode(&orphan).unwrap_err().to_string().contains("no parent"));
+    }
+
+    #[test]
+    fn loader_reads_prefab_files() {
+        let mut prefab = Prefab::new();
+        prefab.insert(Prefab::ROOT, Hp(5));
+        let mut vfs = Vfs::new();
+        vfs.mount("/", Box::new(MemBackend::new()));
+        vfs.write("/enemy.prefab", &format().encode(&prefab).unwrap()).unwrap();
+        vfs.write("/broken.prefab", b"APFB").unwrap();
+
+        let mut assets = AssetServer::with_workers(0);
+        assets.add_loader(PrefabLoader(format()));
+        let enemy = assets.load_blocking::<Prefab>(&vfs, "/enemy.prefab");
+        let broken = assets.load_blocking::<Prefab>(&vfs, "/broken.prefab");
+        assert!(assets.error(&broken).is_some());
+        let loaded = assets.get(&enemy).unwrap().clone();
+        let mut ecs = ecs();
+        let e = ecs.instantiate(&loaded).root();
+        assert_eq!(ecs.get::<Hp>(e), Some(&Hp(5)));
+    }
+}
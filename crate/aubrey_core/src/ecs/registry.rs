@@ -30,6 +30,10 @@ impl Registry {
         self.components_by_name.get(name.as_ref()).copied()
     }
 
+    pub fn component_name(&self, id: ComponentId) -> Option<&str> {
+        self.components_by_name.iter().find(|(_, v)| **v == id).map(|(k, _)| k.as_str())
+    }
+
     pub fn register_resource<S: Into<String>>(&mut self, name: S) -> ResourceId {
         let name = name.into();
         if let Some(id) = self.resources_by_name.get(&name) { return *id; }
@@ -42,5 +46,9 @@ impl Registry {
     pub fn get_resource<S: AsRef<str>>(&self, name: S) -> Option<ResourceId> {
         self.resources_by_name.get(name.as_ref()).copied()
     }
+
+    pub fn resource_name(&self, id: ResourceId) -> Option<&str> {
+        self.resources_by_name.iter().find(|(_, v)| **v == id).map(|(k, _)| k.as_str())
+    }
 }
 
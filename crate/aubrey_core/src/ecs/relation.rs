@@ -0,0 +1,207 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+
+use crate::ecs::ecs::Ecs;
+use crate::ecs::entity::Entity;
+
+/// Many-to-many links of kind `R` (a marker type such as `struct Targets;`).
+pub(crate) struct RelationStore<R: 'static + Send + Sync> {
+    // source -> targets / target -> sources, both in insertion order
+    forward: HashMap<Entity, Vec<Entity>>,
+    reverse: HashMap<Entity, Vec<Entity>>,
+    _m: std::marker::PhantomData<fn() -> R>,
+}
+
+impl<R: 'static + Send + Sync> Default for RelationStore<R> {
+    fn default() -> Self { Self { forward: HashMap::new(), reverse: HashMap::new(), _m: std::marker::PhantomData } }
+}
+
+fn unlink(map: &mut HashMap<Entity, Vec<Entity>>, key: Entity, value: Entity) {
+    if let Some(v) = map.get_mut(&key) {
+        v.retain(|e| *e != value);
+        if v.is_empty() { map.remove(&key); }
+    }
+}
+
+impl<R: 'static + Send + Sync> RelationStore<R> {
+    fn link(&mut self, source: Entity, target: Entity) -> bool {
+        let targets = self.forward.entry(source).or_default();
+        if targets.contains(&target) { return false; }
+        targets.push(target);
+        self.reverse.entry(target).or_default().push(source);
+        true
+    }
+
+    fn unlink(&mut self, source: Entity, target: Entity) -> bool {
+        let had = self.forward.get(&source).is_some_and(|v| v.contains(&target));
+        unlink(&mut self.forward, source, target);
+        unlink(&mut self.reverse, target, source);
+        had
+    }
+}
+
+// ----- erased store for despawn cleanup -----
+pub(crate) trait ErasedRelation: Send + Sync + 'static {
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+    fn remove_entity(&mut self, entity: Entity);
+}
+
+impl<R: 'static + Send + Sync> ErasedRelation for RelationStore<R> {
+    fn as_any(&self) -> &dyn Any { self }
+    fn as_any_mut(&mut self) -> &mut dyn Any { self }
+    fn remove_entity(&mut self, entity: Entity) {
+        if let Some(targets) = self.forward.remove(&entity) {
+            for t in targets { unlink(&mut self.reverse, t, entity); }
+        }
+        if let Some(sources) = self.reverse.remove(&entity) {
+            for s in sources { unlink(&mut self.forward, s, entity); }
+        }
+    }
+}
+
+// Relation APIs on Ecs
+impl Ecs {
+    fn relation_store<R: 'static + Send + Sync>(&self) -> Option<&RelationStore<R>> {
+        self.relations.get(&TypeId::of::<R>()).and_then(|s| s.as_any().downcast_ref::<RelationStore<R>>())
+    }
+
+    fn existing_relation_store_mut<R: 'static + Send + Sync>(&mut self) -> Option<&mut RelationStore<R>> {
+        self.relations.get_mut(&TypeId::of::<R>()).and_then(|s| s.as_any_mut().downcast_mut::<RelationStore<R>>())
+    }
+
+    fn relation_store_mut<R: 'static + Send + Sync>(&mut self) -> &mut RelationStore<R> {
+        self.relations
+            .entry(TypeId::of::<R>())
+            .or_insert_with(|| Box::new(RelationStore::<R>::default()))
+            .as_any_mut()
+            .downcast_mut::<RelationStore<R>>()
+            .expect("Relation store type mismatch")
+    }
+
+    /// Link `source -R-> target`. Returns false if either entity is dead or the link exists.
+    /// Links are removed automatically when either side is despawned.
+    pub fn relate<R: 'static + Send + Sync>(&mut self, source: Entity, target: Entity) -> bool {
+        if !self.is_alive(source) || !self.is_alive(target) { return false; }
+        self.relation_store_mut::<R>().link(source, target)
+    }
+
+    /// Remove `source -R-> target`. Returns false if there was no such link.
+    pub fn unrelate<R: 'static + Send + Sync>(&mut self, source: Entity, target: Entity) -> bool {
+        self.existing_relation_store_mut::<R>().is_some_and(|s| s.unlink(source, target))
+    }
+
+    pub fn is_related<R: 'static + Send + Sync>(&self, source: Entity, target: Entity) -> bool {
+        self.relation_store::<R>().and_then(|s| s.forward.get(&source)).is_some_and(|v| v.contains(&target))
+    }
+
+    /// Entities `source` points to via `R`, in link order.
+    pub fn targets_of<R: 'static + Send + Sync>(&self, source: Entity) -> &[Entity] {
+        self.relation_store::<R>().and_then(|s| s.forward.get(&source)).map(|v| v.as_slice()).unwrap_or(&[])
+    }
+
+    /// Entities related to `target` via `R` ("all X that Targets this"), in link order.
+    pub fn sources_of<R: 'static + Send + Sync>(&self, target: Entity) -> &[Entity] {
+        self.relation_store::<R>().and_then(|s| s.reverse.get(&target)).map(|v| v.as_slice()).unwrap_or(&[])
+    }
+
+    /// All `(source, target)` pairs of kind `R`, sorted by source then target id.
+    pub fn relation_pairs<R: 'static + Send + Sync>(&self) -> Vec<(Entity, Entity)> {
+        let mut out: Vec<(Entity, Entity)> = self
+            .relation_store::<R>()
+            .map(|s| s.forward.iter().flat_map(|(src, ts)| ts.iter().map(move |t| (*src, *t))).collect())
+            .unwrap_or_default();
+        out.sort_by_key(|(a, b)| (a.id(), b.id()));
+        out
+    }
+
+    /// Drop every `R` link touching `entity` (either side).
+    pub fn clear_relations<R: 'static + Send + Sync>(&mut self, entity: Entity) {
+        if let Some(s) = self.existing_relation_store_mut::<R>() { s.remove_entity(entity); }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ecs::Commands;
+
+    struct Targets;
+    struct Likes;
+
+    fn world() -> (Ecs, Entity, Entity, Entity) {
+        let mut ecs = Ecs::new();
+        let (a, b, c) = (ecs.spawn_empty(), ecs.spawn_empty(), ecs.spawn_empty());
+        (ecs, a, b, c)
+    }
+
+    #[test]
+    fn relate_links_both_directions_in_order() {
+        let (mut ecs, a, b, c) = world();
+        assert!(ecs.relate::<Targets>(a, c));
+        assert!(ecs.relate::<Targets>(a, b));
+        assert!(ecs.relate::<Targets>(b, c));
+        assert!(!ecs.relate::<Targets>(a, c));
+        assert_eq!(ecs.targets_of::<Targets>(a), [c, b]);
+        assert_eq!(ecs.sources_of::<Targets>(c), [a, b]);
+        assert!(ecs.is_related::<Targets>(a, b));
+        assert!(!ecs.is_related::<Targets>(b, a));
+        assert_eq!(ecs.relation_pairs::<Targets>(), [(a, b), (a, c), (b, c)]);
+        // kinds are independent
+        assert!(!ecs.is_related::<Likes>(a, b));
+        assert!(ecs.targets_of::<Likes>(a).is_empty());
+
+        let dead = ecs.spawn_empty();
+        ecs.despawn(dead);
+        assert!(!ecs.relate::<Targets>(a, dead));
+        assert!(!ecs.relate::<Targets>(dead, a));
+    }
+
+    #[test]
+    fn unrelate_removes_one_link() {
+        let (mut ecs, a, b, c) = world();
+        ecs.relate::<Targets>(a, b);
+        ecs.relate::<Targets>(a, c);
+        assert!(ecs.unrelate::<Targets>(a, b));
+        assert!(!ecs.unrelate::<Targets>(a, b));
+        assert_eq!(ecs.targets_of::<Targets>(a), [c]);
+        assert!(ecs.sources_of::<Targets>(b).is_empty());
+        // no store is created for a kind that was never linked
+        assert!(!ecs.unrelate::<Likes>(a, b));
+        ecs.clear_relations::<Likes>(a);
+        assert!(!ecs.relations.contains_key(&TypeId::of::<Likes>()));
+    }
+
+    #[test]
+    fn despawn_drops_links_on_either_side() {
+        let (mut ecs, a, b, c) = world();
+        ecs.relate::<Targets>(a, b);
+        ecs.relate::<Targets>(b, c);
+        ecs.relate::<Likes>(c, b);
+        ecs.despawn(b);
+        assert!(ecs.targets_of::<Targets>(a).is_empty());
+        assert!(ecs.sources_of::<Targets>(c).is_empty());
+        assert!(ecs.targets_of::<Likes>(c).is_empty());
+        assert!(ecs.relation_pairs::<Targets>().is_empty());
+
+        // deferred despawns too
+        ecs.relate::<Targets>(a, c);
+        ecs.relate::<Targets>(c, a);
+        let mut commands = Commands::default();
+        commands.despawn(a);
+        commands.apply(&mut ecs);
+        assert!(ecs.targets_of::<Targets>(c).is_empty());
+        assert!(ecs.sources_of::<Targets>(c).is_empty());
+    }
+
+    #[test]
+    fn clear_relations_drops_one_kind() {
+        let (mut ecs, a, b, c) = world();
+        ecs.relate::<Targets>(a, b);
+        ecs.relate::<Targets>(c, a);
+        ecs.relate::<Likes>(a, b);
+        ecs.clear_relations::<Targets>(a);
+        assert!(ecs.relation_pairs::<Targets>().is_empty());
+        assert_eq!(ecs.relation_pairs::<Likes>(), [(a, b)]);
+    }
+}
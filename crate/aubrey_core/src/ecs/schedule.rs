@@ -1,6 +1,8 @@
 use crate::ecs::system::System;
 use crate::ecs::ecs::Ecs;
+use crate::diagnostics::FrameDiagnostics;
 use std::collections::HashMap;
+use std::time::Instant;
 
 pub enum Stage {
     PreStartup,
@@ -81,16 +83,24 @@ impl Schedules {
     }
 
     pub fn run_frame(&mut self, ecs: &mut Ecs) {
+        let frame_start = Instant::now();
         self.ensure_startup(ecs);
         for st in [Stage::First, Stage::PreUpdate, Stage::Update, Stage::PostUpdate, Stage::Last] {
             self.run_stage(ecs, st);
         }
+        if let Some(diag) = ecs.get_resource_mut::<FrameDiagnostics>() {
+            diag.record_frame(frame_start, frame_start.elapsed());
+        }
     }
 
     pub fn run_stage(&mut self, ecs: &mut Ecs, stage: Stage) {
+        let stage_key = key(stage);
+        // FrameDiagnostics があるときだけ計測する
+        let profiling = ecs.resources.contains::<FrameDiagnostics>();
+        let stage_start = Instant::now();
         // Set up Commands per stage
         ecs.insert_resource(crate::ecs::ecs::Commands::default());
-        if let Some(list) = self.stages.get_mut(key(stage)) {
+        if let Some(list) = self.stages.get_mut(stage_key) {
             // まず order の小さい順に安定ソート
             // 登録順を安定性のため保持
             let n = list.len();
@@ -159,13 +169,26 @@ impl Schedules {
             // 実行
             for i in final_order {
                 let s = &mut list[i];
+                if !profiling {
+                    s.sys.run(ecs);
+                    continue;
+                }
+                let t0 = Instant::now();
                 s.sys.run(ecs);
+                let dt = t0.elapsed();
+                let name = s.label.unwrap_or_else(|| s.sys.name());
+                if let Some(diag) = ecs.get_resource_mut::<FrameDiagnostics>() {
+                    diag.record_system(stage_key, i, name, t0, dt);
+                }
             }
         }
         // Apply commands (take ownership) and drop resource
         if let Some(mut cmds) = ecs.remove_resource::<crate::ecs::ecs::Commands>() {
             cmds.apply(ecs);
         }
+        if profiling && let Some(diag) = ecs.get_resource_mut::<FrameDiagnostics>() {
+            diag.record_stage(stage_key, stage_start, stage_start.elapsed());
+        }
     }
 }
 
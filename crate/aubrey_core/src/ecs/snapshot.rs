@@ -0,0 +1,378 @@
+//! World snapshots for undo and rollback.
+//!
+//! Only types registered with `register_snapshot*` are captured, and they must be `Clone`:
+//! values are cloned in and out, never serialized. There is no reflection-based path, so a
+//! type without `Clone` can't take part (wrap it or give it a `Clone` impl); snapshots also
+//! live in memory only and can't be written to disk.
+
+use std::any::{Any, TypeId};
+use std::collections::{HashMap, HashSet};
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+use crate::ecs::ecs::Ecs;
+use crate::ecs::entity::Entity;
+
+type Boxed = Box<dyn Any + Send + Sync>;
+
+// Type-erased capture/restore/diff for one opted-in component or resource type.
+trait SnapshotType: Send + Sync {
+    fn type_name(&self) -> &'static str;
+    fn capture(&self, ecs: &Ecs) -> Option<Boxed>;
+    fn restore(&self, ecs: &mut Ecs, data: Option<&Boxed>);
+    // None when nothing changed
+    fn diff(&self, old: Option<&Boxed>, new: Option<&Boxed>) -> Option<Boxed>;
+    fn apply(&self, ecs: &mut Ecs, delta: &Boxed);
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+enum SlotKey {
+    Component(TypeId),
+    Resource(TypeId),
+}
+
+#[derive(Default)]
+pub(crate) struct SnapshotTypes {
+    map: HashMap<SlotKey, Arc<dyn SnapshotType>>,
+}
+
+// ----- components -----
+struct ComponentSlot<T> {
+    eq: Option<fn(&T, &T) -> bool>,
+    _m: PhantomData<fn() -> T>,
+}
+
+struct ComponentDelta<T> {
+    upsert: Vec<(Entity, T)>,
+    remove: Vec<Entity>,
+}
+
+impl<T: 'static + Send + Sync + Clone> SnapshotType for ComponentSlot<T> {
+    fn type_name(&self) -> &'static str { std::any::type_name::<T>() }
+
+    fn capture(&self, ecs: &Ecs) -> Option<Boxed> {
+        let map: HashMap<Entity, T> = ecs
+            .get_store::<T>()
+            .map(|s| s.map.iter().filter(|(e, _)| ecs.is_alive(**e)).map(|(e, c)| (*e, c.clone())).collect())
+            .unwrap_or_default();
+        Some(Box::new(map))
+    }
+
+    fn restore(&self, ecs: &mut Ecs, data: Option<&Boxed>) {
+        let Some(map) = data.and_then(|d| d.downcast_ref::<HashMap<Entity, T>>()) else { return };
+        ecs.ensure_store::<T>();
+        let store = ecs.get_store_mut::<T>().expect("Component store type mismatch");
+        store.map.clear();
+        store.map.extend(map.iter().map(|(e, c)| (*e, c.clone())));
+    }
+
+    fn diff(&self, old: Option<&Boxed>, new: Option<&Boxed>) -> Option<Boxed> {
+        let empty = HashMap::new();
+        let old = old.and_then(|d| d.downcast_ref::<HashMap<Entity, T>>()).unwrap_or(&empty);
+        let new = new.and_then(|d| d.downcast_ref::<HashMap<Entity, T>>()).unwrap_or(&empty);
+        let mut delta = ComponentDelta::<T> { upsert: Vec::new(), remove: Vec::new() };
+        for (e, c) in new {
+            let same = match (old.get(e), self.eq) {
+                (Some(prev), Some(eq)) => eq(prev, c),
+                _ => false,
+            };
+            if !same { delta.upsert.push((*e, c.clone())); }
+        }
+        for e in old.keys() {
+            if !new.contains_key(e) { delta.remove.push(*e); }
+        }
+        if delta.upsert.is_empty() && delta.remove.is_empty() { None } else { Some(Box::new(delta)) }
+    }
+
+    fn apply(&self, ecs: &mut Ecs, delta: &Boxed) {
+        let Some(delta) = delta.downcast_ref::<ComponentDelta<T>>() else { return };
+        ecs.ensure_store::<T>();
+        let store = ecs.get_store_mut::<T>().expect("Component store type mismatch");
+        for e in &delta.remove { store.map.remove(e); }
+        for (e, c) in &delta.upsert { store.map.insert(*e, c.clone()); }
+    }
+}
+
+// ----- resources -----
+struct ResourceSlot<T> {
+    eq: Option<fn(&T, &T) -> bool>,
+    _m: PhantomData<fn() -> T>,
+}
+
+struct ResourceDelta<T>(Option<T>);
+
+impl<T: 'static + Send + Sync + Clone> SnapshotType for ResourceSlot<T> {
+    fn type_name(&self) -> &'static str { std::any::type_name::<T>() }
+
+    fn capture(&self, ecs: &Ecs) -> Option<Boxed> {
+        ecs.get_resource::<T>().map(|r| Box::new(r.clone()) as Boxed)
+    }
+
+    fn restore(&self, ecs: &mut Ecs, data: Option<&Boxed>) {
+        match data.and_then(|d| d.downcast_ref::<T>()) {
+            Some(v) => ecs.insert_resource::<T>(v.clone()),
+            None => { ecs.remove_resource::<T>(); }
+        }
+    }
+
+    fn diff(&self, old: Option<&Boxed>, new: Option<&Boxed>) -> Option<Boxed> {
+        let old = old.and_then(|d| d.downcast_ref::<T>());
+        let new = new.and_then(|d| d.downcast_ref::<T>());
+        let same = match (old, new, self.eq) {
+            (None, None, _) => true,
+            (Some(a), Some(b), Some(eq)) => eq(a, b),
+            _ => false,
+        };
+        if same { None } else { Some(Box::new(ResourceDelta::<T>(new.cloned()))) }
+    }
+
+    fn apply(&self, ecs: &mut Ecs, delta: &Boxed) {
+        let Some(delta) = delta.downcast_ref::<ResourceDelta<T>>() else { return };
+        match &delta.0 {
+            Some(v) => ecs.insert_resource::<T>(v.clone()),
+            None => { ecs.remove_resource::<T>(); }
+        }
+    }
+}
+
+/// Captured state of all opted-in component/resource types plus the entity set.
+/// Components that were not registered are not captured; on restore they are kept
+/// for entities that stay alive and dropped for entities that did not exist in the snapshot.
+pub struct Snapshot {
+    next_id: u64,
+    alive: HashSet<Entity>,
+    entries: HashMap<SlotKey, (Arc<dyn SnapshotType>, Option<Boxed>)>,
+}
+
+impl Snapshot {
+    pub fn entity_count(&self) -> usize { self.alive.len() }
+
+    pub fn contains(&self, entity: Entity) -> bool { self.alive.contains(&entity) }
+
+    /// Type names captured in this snapshot, sorted.
+    pub fn type_names(&self) -> Vec<&'static str> {
+        let mut out: Vec<&'static str> = self.entries.values().map(|(k, _)| k.type_name()).collect();
+        out.sort_unstable();
+        out
+    }
+
+    /// Delta that turns a world in the state of `self` into the state of `newer`.
+    pub fn diff(&self, newer: &Snapshot) -> SnapshotDiff {
+        let mut spawned: Vec<Entity> = newer.alive.difference(&self.alive).copied().collect();
+        let mut despawned: Vec<Entity> = self.alive.difference(&newer.alive).copied().collect();
+        spawned.sort_by_key(|e| e.id());
+        despawned.sort_by_key(|e| e.id());
+
+        let mut deltas = Vec::new();
+        let keys: HashSet<SlotKey> = self.entries.keys().chain(newer.entries.keys()).copied().collect();
+        for key in keys {
+            let old = self.entries.get(&key);
+            let new = newer.entries.get(&key);
+            let kind = new.or(old).map(|(k, _)| k.clone()).expect("key from either snapshot");
+            if let Some(d) = kind.diff(old.and_then(|(_, d)| d.as_ref()), new.and_then(|(_, d)| d.as_ref())) {
+                deltas.push((kind, d));
+            }
+        }
+        SnapshotDiff { next_id: newer.next_id, spawned, despawned, deltas }
+    }
+}
+
+/// Incremental change between two snapshots; apply with `Ecs::apply_diff`.
+pub struct SnapshotDiff {
+    next_id: u64,
+    spawned: Vec<Entity>,
+    despawned: Vec<Entity>,
+    deltas: Vec<(Arc<dyn SnapshotType>, Boxed)>,
+}
+
+impl SnapshotDiff {
+    pub fn is_empty(&self) -> bool { self.spawned.is_empty() && self.despawned.is_empty() && self.deltas.is_empty() }
+
+    pub fn spawned(&self) -> &[Entity] { &self.spawned }
+
+    pub fn despawned(&self) -> &[Entity] { &self.despawned }
+
+    /// Type names with changes, sorted.
+    pub fn changed_types(&self) -> Vec<&'static str> {
+        let mut out: Vec<&'static str> = self.deltas.iter().map(|(k, _)| k.type_name()).collect();
+        out.sort_unstable();
+        out
+    }
+}
+
+// Snapshot APIs on Ecs
+impl Ecs {
+    /// Opt component `T` into snapshots. Every present value is treated as changed in diffs.
+    pub fn register_snapshot<T: 'static + Send + Sync + Clone>(&mut self) {
+        let slot = ComponentSlot::<T> { eq: None, _m: PhantomData };
+        self.snapshot_types.map.insert(SlotKey::Component(TypeId::of::<T>()), Arc::new(slot));
+    }
+
+    /// Like `register_snapshot`, but unchanged values are skipped in diffs.
+    pub fn register_snapshot_eq<T: 'static + Send + Sync + Clone + PartialEq>(&mut self) {
+        let slot = ComponentSlot::<T> { eq: Some(|a, b| a == b), _m: PhantomData };
+        self.snapshot_types.map.insert(SlotKey::Component(TypeId::of::<T>()), Arc::new(slot));
+    }
+
+    pub fn register_snapshot_resource<T: 'static + Send + Sync + Clone>(&mut self) {
+        let slot = ResourceSlot::<T> { eq: None, _m: PhantomData };
+        self.snapshot_types.map.insert(SlotKey::Resource(TypeId::of::<T>()), Arc::new(slot));
+    }
+
+    pub fn register_snapshot_resource_eq<T: 'static + Send + Sync + Clone + PartialEq>(&mut self) {
+        let slot = ResourceSlot::<T> { eq: Some(|a, b| a == b), _m: PhantomData };
+        self.snapshot_types.map.insert(SlotKey::Resource(TypeId::of::<T>()), Arc::new(slot));
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        let entries = self
+            .snapshot_types
+            .map
+            .iter()
+            .map(|(key, kind)| (*key, (kind.clone(), kind.capture(self))))
+            .collect();
+        Snapshot { next_id: self.next_id, alive: self.alive.clone(), entries }
+    }
+
+    /// Roll the world back (or forward) to `snapshot`.
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        let gone: Vec<Entity> = self.alive.difference(&snapshot.alive).copied().collect();
+        for e in gone { self.remove_from_all_stores(e); }
+        self.alive = snapshot.alive.clone();
+        self.next_id = snapshot.next_id;
+        for (kind, data) in snapshot.entries.values() {
+            kind.restore(self, data.as_ref());
+        }
+    }
+
+    pub fn apply_diff(&mut self, diff: &SnapshotDiff) {
+        for e in &diff.despawned {
+            self.alive.remove(e);
+            self.remove_from_all_stores(*e);
+        }
+        for e in &diff.spawned { self.alive.insert(*e); }
+        self.next_id = diff.next_id;
+        for (kind, delta) in &diff.deltas {
+            kind.apply(self, delta);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Pos(i32, i32);
+    #[derive(Clone, Debug, PartialEq)]
+    struct Hp(u32);
+    #[derive(Clone, Debug, PartialEq)]
+    struct Turn(u32);
+    // never registered
+    #[derive(Debug, PartialEq)]
+    struct Cache(u32);
+
+    fn world() -> (Ecs, Entity, Entity) {
+        let mut ecs = Ecs::new();
+        ecs.register_snapshot_eq::<Pos>();
+        ecs.register_snapshot::<Hp>();
+        ecs.register_snapshot_resource_eq::<Turn>();
+        let a = ecs.spawn_one(Pos(0, 0));
+        ecs.insert(a, Hp(10));
+        let b = ecs.spawn_one(Pos(5, 5));
+        ecs.insert_resource(Turn(1));
+        (ecs, a, b)
+    }
+
+    fn state(ecs: &Ecs) -> Vec<(Entity, Option<Pos>, Option<Hp>)> {
+        ecs.entities().into_iter().map(|e| (e, ecs.get::<Pos>(e).cloned(), ecs.get::<Hp>(e).cloned())).collect()
+    }
+
+    #[test]
+    fn restore_rolls_back_components_and_resources() {
+        let (mut ecs, a, b) = world();
+        let before = state(&ecs);
+        let snap = ecs.snapshot();
+        assert_eq!(snap.entity_count(), 2);
+        assert_eq!(snap.type_names().len(), 3);
+
+        ecs.get_mut::<Pos>(a).unwrap().0 = 7;
+        ecs.remove::<Hp>(a);
+        ecs.insert(b, Hp(3));
+        ecs.despawn(b);
+        let c = ecs.spawn_one(Pos(9, 9));
+        ecs.get_resource_mut::<Turn>().unwrap().0 = 5;
+
+        ecs.restore(&snap);
+        assert_eq!(state(&ecs), before);
+        assert!(!ecs.is_alive(c));
+        assert_eq!(ecs.get_resource::<Turn>(), Some(&Turn(1)));
+        // ids handed out after the snapshot are reused
+        assert_eq!(ecs.spawn_empty(), c);
+    }
+
+    #[test]
+    fn restore_removes_resources_added_later() {
+        let (mut ecs, ..) = world();
+        ecs.remove_resource::<Turn>();
+        let snap = ecs.snapshot();
+        ecs.insert_resource(Turn(2));
+        ecs.restore(&snap);
+        assert_eq!(ecs.get_resource::<Turn>(), None);
+    }
+
+    #[test]
+    fn apply_diff_turns_old_state_into_new() {
+        let (mut ecs, a, b) = world();
+        let old = ecs.snapshot();
+
+        ecs.get_mut::<Pos>(a).unwrap().1 = 4;
+        ecs.remove::<Hp>(a);
+        ecs.despawn(b);
+        let c = ecs.spawn_one(Pos(1, 2));
+        ecs.insert(c, Hp(8));
+        ecs.remove_resource::<Turn>();
+        let new = ecs.snapshot();
+        let expected = state(&ecs);
+
+        let diff = old.diff(&new);
+        assert_eq!(diff.spawned(), &[c]);
+        assert_eq!(diff.despawned(), &[b]);
+        assert_eq!(diff.changed_types().len(), 3);
+
+        ecs.restore(&old);
+        ecs.apply_diff(&diff);
+        assert_eq!(state(&ecs), expected);
+        assert_eq!(ecs.get_resource::<Turn>(), None);
+        assert_eq!(ecs.spawn_empty().id(), c.id() + 1);
+    }
+
+    #[test]
+    fn eq_types_skip_unchanged_values() {
+        let (mut ecs, a, _) = world();
+        let old = ecs.snapshot();
+        ecs.get_mut::<Hp>(a).unwrap().0 = 10; // same value, but Hp has no eq
+        let diff = old.diff(&ecs.snapshot());
+        assert_eq!(diff.changed_types(), vec![std::any::type_name::<Hp>()]);
+    }
+
+    #[test]
+    fn unregistered_types_are_untouched() {
+        let (mut ecs, a, b) = world();
+        ecs.insert(a, Cache(1));
+        let snap = ecs.snapshot();
+        assert!(!snap.type_names().contains(&std::any::type_name::<Cache>()));
+
+        ecs.get_mut::<Cache>(a).unwrap().0 = 2;
+        ecs.insert(b, Cache(3));
+        ecs.insert_resource(Cache(4));
+        let c = ecs.spawn_one(Cache(5));
+
+        ecs.restore(&snap);
+        assert_eq!(ecs.get::<Cache>(a), Some(&Cache(2)));
+        assert_eq!(ecs.get::<Cache>(b), Some(&Cache(3)));
+        assert_eq!(ecs.get_resource::<Cache>(), Some(&Cache(4)));
+        // entities that did not exist in the snapshot lose everything
+        assert_eq!(ecs.get::<Cache>(c), None);
+    }
+}
@@ -2,6 +2,8 @@ use crate::ecs::ecs::Ecs;
 
 pub trait System: Send {
     fn run(&mut self, ecs: &mut Ecs);
+    // Name shown in diagnostics when the system has no label
+    fn name(&self) -> &'static str { std::any::type_name::<Self>() }
 }
 
 impl<F> System for F
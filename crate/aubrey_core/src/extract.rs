@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+
+use crate::ecs::ecs::Ecs;
+use crate::ecs::entity::Entity;
+
+/// Resource in a sub-app world mapping parent-world entities to their mirrors.
+/// Created on demand by the `extract_*` helpers.
+#[derive(Default)]
+pub struct ExtractedEntities {
+    map: HashMap<Entity, Entity>,
+}
+
+impl ExtractedEntities {
+    /// Mirror of the parent entity `main`, if it was extracted.
+    pub fn get(&self, main: Entity) -> Option<Entity> { self.map.get(&main).copied() }
+
+    pub fn len(&self) -> usize { self.map.len() }
+
+    pub fn is_empty(&self) -> bool { self.map.is_empty() }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, Entity)> + '_ { self.map.iter().map(|(a, b)| (*a, *b)) }
+}
+
+/// Mirror of `main` in `sub`, spawning it on first use.
+pub fn mirror_entity(sub: &mut Ecs, main: Entity) -> Entity {
+    if let Some(e) = sub.get_resource::<ExtractedEntities>().and_then(|m| m.get(main))
+        && sub.is_alive(e)
+    {
+        return e;
+    }
+    let e = sub.spawn_empty();
+    if sub.get_resource::<ExtractedEntities>().is_none() { sub.insert_resource(ExtractedEntities::default()); }
+    if let Some(m) = sub.get_resource_mut::<ExtractedEntities>() { m.map.insert(main, e); }
+    e
+}
+
+/// Copy every `T` from `main` onto the mirrored entities in `sub`.
+/// Mirrors whose parent entity no longer has `T` lose it.
+pub fn extract_component<T: 'static + Send + Sync + Clone>(main: &Ecs, sub: &mut Ecs) {
+    let items: Vec<(Entity, T)> = main.query::<T>().iter().map(|(e, c)| (e, c.clone())).collect();
+    let stale: Vec<Entity> = sub
+        .get_resource::<ExtractedEntities>()
+        .map(|m| m.map.iter().filter(|(me, _)| !main.has::<T>(**me)).map(|(_, se)| *se).collect())
+        .unwrap_or_default();
+    for se in stale { sub.remove::<T>(se); }
+    for (e, c) in items {
+        let se = mirror_entity(sub, e);
+        sub.insert::<T>(se, c);
+    }
+}
+
+/// Copy resource `T` from `main` into `sub` (removed from `sub` when absent in `main`).
+pub fn extract_resource<T: 'static + Send + Sync + Clone>(main: &Ecs, sub: &mut Ecs) {
+    match main.get_resource::<T>() {
+        Some(v) => sub.insert_resource::<T>(v.clone()),
+        None => { sub.remove_resource::<T>(); }
+    }
+}
+
+/// Despawn mirrors whose parent entity was despawned.
+pub fn extract_despawns(main: &Ecs, sub: &mut Ecs) {
+    let Some(map) = sub.get_resource_mut::<ExtractedEntities>() else { return };
+    let mut dead = Vec::new();
+    map.map.retain(|me, se| {
+        let alive = main.is_alive(*me);
+        if !alive { dead.push(*se); }
+        alive
+    });
+    for e in dead { sub.despawn(e); }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Pos(i32);
+    #[derive(Clone, Debug, PartialEq)]
+    struct Hidden;
+    #[derive(Clone, Debug, PartialEq)]
+    struct Camera(u32);
+
+    #[test]
+    fn components_follow_the_parent() {
+        let (mut main, mut sub) = (Ecs::new(), Ecs::new());
+        let a = main.spawn_one(Pos(1));
+        let b = main.spawn_one(Pos(2));
+        main.insert(b, Hidden);
+        let unrelated = sub.spawn_one(Pos(99));
+        extract_component::<Pos>(&main, &mut sub);
+        extract_component::<Hidden>(&main, &mut sub);
+        let map = sub.get_resource::<ExtractedEntities>().unwrap();
+        assert_eq!(map.len(), 2);
+        let (sa, sb) = (map.get(a).unwrap(), map.get(b).unwrap());
+        assert_ne!(sa, unrelated);
+        assert_eq!((sub.get::<Pos>(sa), sub.get::<Pos>(sb)), (Some(&Pos(1)), Some(&Pos(2))));
+        assert!(sub.has::<Hidden>(sb) && !sub.has::<Hidden>(sa));
+
+        // values are refreshed on the same mirrors, and removals are mirrored
+        main.insert(a, Pos(10));
+        main.remove::<Hidden>(b);
+        extract_component::<Pos>(&main, &mut sub);
+        extract_component::<Hidden>(&main, &mut sub);
+        assert_eq!(sub.get_resource::<ExtractedEntities>().unwrap().get(a), Some(sa));
+        assert_eq!(sub.get::<Pos>(sa), Some(&Pos(10)));
+        assert!(!sub.has::<Hidden>(sb));
+        assert_eq!(sub.get::<Pos>(unrelated), Some(&Pos(99)));
+    }
+
+    #[test]
+    fn despawns_are_mirrored() {
+        let (mut main, mut sub) = (Ecs::new(), Ecs::new());
+        let a = main.spawn_one(Pos(1));
+        let b = main.spawn_one(Pos(2));
+        extract_component::<Pos>(&main, &mut sub);
+        let (sa, sb) = {
+            let map = sub.get_resource::<ExtractedEntities>().unwrap();
+            (map.get(a).unwrap(), map.get(b).unwrap())
+        };
+        main.despawn(a);
+        extract_despawns(&main, &mut sub);
+        assert!(!sub.is_alive(sa) && sub.is_alive(sb));
+        let map = sub.get_resource::<ExtractedEntities>().unwrap();
+        assert_eq!((map.get(a), map.len()), (None, 1));
+        // nothing to do without extracted entities
+        extract_despawns(&main, &mut Ecs::new());
+
+        // a mirror despawned on the sub side is spawned again
+        sub.despawn(sb);
+        extract_component::<Pos>(&main, &mut sub);
+        let sb2 = sub.get_resource::<ExtractedEntities>().unwrap().get(b).unwrap();
+        assert!(sb2 != sb && sub.is_alive(sb2));
+        assert_eq!(sub.get::<Pos>(sb2), Some(&Pos(2)));
+    }
+
+    #[test]
+    fn resources_are_copied_and_removed() {
+        let (mut main, mut sub) = (Ecs::new(), Ecs::new());
+        extract_resource::<Camera>(&main, &mut sub);
+        assert!(sub.get_resource::<Camera>().is_none());
+        main.insert_resource(Camera(1));
+        extract_resource::<Camera>(&main, &mut sub);
+        assert_eq!(sub.get_resource::<Camera>(), Some(&Camera(1)));
+        main.get_resource_mut::<Camera>().unwrap().0 = 2;
+        extract_resource::<Camera>(&main, &mut sub);
+        assert_eq!(sub.get_resource::<Camera>(), Some(&Camera(2)));
+        main.remove_resource::<Camera>();
+        extract_resource::<Camera>(&main, &mut sub);
+        assert!(sub.get_resource::<Camera>().is_none());
+    }
+}
@@ -0,0 +1,115 @@
+use std::collections::BTreeMap;
+use std::io::Cursor;
+
+use super::{norm, Backend, Bytes, Metadata, VfsError, VfsReader, VfsResult};
+
+/// Read-only backend over data compiled into the binary (`include_bytes!`).
+/// Files are registered by path; their directories exist implicitly.
+///
+/// ```ignore
+/// let mut embedded = EmbeddedBackend::new();
+/// embedded.insert("fonts/NotoSans-Regular.ttf", include_bytes!("../assets/NotoSans-Regular.ttf"));
+/// vfs.mount("/embedded", Box::new(embedded));
+/// vfs.add_source("embedded", "/embedded");
+/// ```
+#[derive(Default)]
+pub struct EmbeddedBackend {
+    files: BTreeMap<String, &'static [u8]>,
+}
+
+impl EmbeddedBackend {
+    pub fn new() -> Self { Self::default() }
+
+    /// Register `data` at `path` (relative to the mount), replacing an earlier registration.
+    pub fn insert(&mut self, path: &str, data: &'static [u8]) { self.files.insert(norm(path), data); }
+
+    pub fn with(mut self, path: &str, data: &'static [u8]) -> Self { self.insert(path, data); self }
+
+    /// Registered file paths, sorted.
+    pub fn paths(&self) -> impl Iterator<Item = &str> + '_ { self.files.keys().map(|s| s.as_str()) }
+
+    pub fn len(&self) -> usize { self.files.len() }
+
+    pub fn is_empty(&self) -> bool { self.files.is_empty() }
+
+    fn is_dir(&self, p: &str) -> bool {
+        let prefix = if p == "/" { "/".to_string() } else { format!("{}/", p) };
+        p == "/" || self.files.range(prefix.clone()..).next().is_some_and(|(k, _)| k.starts_with(&prefix))
+    }
+
+    fn file(&self, path: &str) -> VfsResult<&'static [u8]> {
+        let p = norm(path);
+        if let Some(data) = self.files.get(&p) { return Ok(data); }
+        if self.is_dir(&p) { return Err(VfsError::IsADirectory(p)); }
+        Err(VfsError::NotFound(p))
+    }
+}
+
+impl Backend for EmbeddedBackend {
+    fn read(&self, path: &str) -> VfsResult<Bytes> { self.file(path).map(|d| d.to_vec()) }
+    // streams straight from the static data
+    fn open(&self, path: &str) -> VfsResult<VfsReader> { Ok(Box::new(Cursor::new(self.file(path)?))) }
+    fn metadata(&self, path: &str) -> VfsResult<Metadata> {
+        let p = norm(path);
+        if let Some(data) = self.files.get(&p) { return Ok(Metadata { is_dir: false, size: data.len() as u64, modified: None }); }
+        if self.is_dir(&p) { return Ok(Metadata { is_dir: true, size: 0, modified: None }); }
+        Err(VfsError::NotFound(p))
+    }
+    fn list(&self, path: &str) -> VfsResult<Vec<String>> {
+        let p = norm(path);
+        if self.files.contains_key(&p) { return Err(VfsError::NotADirectory(p)); }
+        if !self.is_dir(&p) { return Err(VfsError::NotFound(p)); }
+        let prefix = if p == "/" { "/".to_string() } else { format!("{}/", p) };
+        let mut out: Vec<String> = self
+            .files
+            .range(prefix.clone()..)
+            .take_while(|(k, _)| k.starts_with(&prefix))
+            .map(|(k, _)| k[prefix.len()..].split('/').next().unwrap_or_default().to_string())
+            .collect();
+        // "a/x" and "a.txt" interleave in key order
+        out.sort();
+        out.dedup();
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    use crate::fs::Vfs;
+
+    #[test]
+    fn reads_and_lists_registered_files() {
+        let mut vfs = Vfs::new();
+        vfs.mount("/embedded", Box::new(EmbeddedBackend::new().with("fonts/a.ttf", b"font").with("fonts/b.ttf", b"b").with("fonts.txt", b"list")));
+        assert_eq!(vfs.read("/embedded/fonts/a.ttf").unwrap(), b"font");
+        let mut text = String::new();
+        vfs.open("/embedded/fonts.txt").unwrap().read_to_string(&mut text).unwrap();
+        assert_eq!(text, "list");
+        assert_eq!(vfs.list("/embedded").unwrap(), ["fonts", "fonts.txt"]);
+        assert_eq!(vfs.list("/embedded/fonts").unwrap(), ["a.ttf", "b.ttf"]);
+        assert!(vfs.is_dir("/embedded/fonts"));
+        assert_eq!(vfs.metadata("/embedded/fonts/a.ttf").unwrap().size, 4);
+        assert!(matches!(vfs.read("/embedded/fonts"), Err(VfsError::IsADirectory(p)) if p == "/embedded/fonts"));
+        assert!(matches!(vfs.list("/embedded/fonts.txt"), Err(VfsError::NotADirectory(p)) if p == "/embedded/fonts.txt"));
+        assert!(matches!(vfs.read("/embedded/font"), Err(VfsError::NotFound(p)) if p == "/embedded/font"));
+    }
+
+    #[test]
+    fn rejects_writes() {
+        let mut vfs = Vfs::new();
+        vfs.mount("/embedded", Box::new(EmbeddedBackend::new().with("a.txt", b"a")));
+        vfs.add_source("embedded", "/embedded");
+        let read_only = |r: VfsResult<()>| assert!(matches!(r, Err(VfsError::ReadOnly(p)) if p.starts_with("/embedded/")));
+        read_only(vfs.write("embedded://a.txt", b"b"));
+        read_only(vfs.write("/embedded/new.txt", b"b"));
+        read_only(vfs.create("/embedded/a.txt").map(drop));
+        read_only(vfs.mkdir("/embedded/dir"));
+        read_only(vfs.remove("/embedded/a.txt"));
+        read_only(vfs.rename("/embedded/a.txt", "/embedded/b.txt"));
+        assert!(vfs.watch("/embedded").is_err());
+        assert_eq!(vfs.read("/embedded/a.txt").unwrap(), b"a");
+    }
+}
@@ -0,0 +1,141 @@
+use std::fmt;
+use std::io;
+
+/// Error from a `Vfs` / `Backend` operation. Paths are VFS paths.
+#[derive(Debug)]
+pub enum VfsError {
+    NotFound(String),
+    AlreadyExists(String),
+    NotADirectory(String),
+    IsADirectory(String),
+    DirectoryNotEmpty(String),
+    /// Parent directory of the target does not exist.
+    MissingParent(String),
+    /// Backend or mount is read-only.
+    ReadOnly(String),
+    /// Path resolves outside the backend root (e.g. via a symlink).
+    OutsideRoot(String),
+    /// No backend is mounted for the path.
+    NoMount(String),
+    /// `scheme://` path whose scheme has no source (see `Vfs::add_source`).
+    UnknownSource(String),
+    /// Backend does not implement the operation.
+    Unsupported(&'static str),
+    /// Stored data failed validation (bad checksum, undecodable entry).
+    Corrupt(String),
+    Io { path: String, source: io::Error },
+}
+
+pub type VfsResult<T> = Result<T, VfsError>;
+
+impl VfsError {
+    /// Map an `io::Error` for `path`, keeping the common kinds as their own variants.
+    pub fn from_io(path: &str, err: io::Error) -> Self {
+        let path = path.to_string();
+        match err.kind() {
+            io::ErrorKind::NotFound => VfsError::NotFound(path),
+            io::ErrorKind::AlreadyExists => VfsError::AlreadyExists(path),
+            io::ErrorKind::NotADirectory => VfsError::NotADirectory(path),
+            io::ErrorKind::IsADirectory => VfsError::IsADirectory(path),
+            io::ErrorKind::DirectoryNotEmpty => VfsError::DirectoryNotEmpty(path),
+            io::ErrorKind::ReadOnlyFilesystem => VfsError::ReadOnly(path),
+            _ => VfsError::Io { path, source: err },
+        }
+    }
+
+    /// Path the error refers to, if any.
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            VfsError::NotFound(p)
+            | VfsError::AlreadyExists(p)
+            | VfsError::NotADirectory(p)
+            | VfsError::IsADirectory(p)
+            | VfsError::DirectoryNotEmpty(p)
+            | VfsError::MissingParent(p)
+            | VfsError::ReadOnly(p)
+            | VfsError::OutsideRoot(p)
+            | VfsError::NoMount(p)
+            | VfsError::UnknownSource(p)
+            | VfsError::Corrupt(p)
+            | VfsError::Io { path: p, .. } => Some(p),
+            VfsError::Unsupported(_) => None,
+        }
+    }
+
+    pub fn is_not_found(&self) -> bool { matches!(self, VfsError::NotFound(_)) }
+
+    // Rewrite the carried path (backend-relative -> mount-prefixed).
+    pub(crate) fn map_path(self, f: impl FnOnce(String) -> String) -> Self {
+        match self {
+            VfsError::NotFound(p) => VfsError::NotFound(f(p)),
+            VfsError::AlreadyExists(p) => VfsError::AlreadyExists(f(p)),
+            VfsError::NotADirectory(p) => VfsError::NotADirectory(f(p)),
+            VfsError::IsADirectory(p) => VfsError::IsADirectory(f(p)),
+            VfsError::DirectoryNotEmpty(p) => VfsError::DirectoryNotEmpty(f(p)),
+            VfsError::MissingParent(p) => VfsError::MissingParent(f(p)),
+            VfsError::ReadOnly(p) => VfsError::ReadOnly(f(p)),
+            VfsError::OutsideRoot(p) => VfsError::OutsideRoot(f(p)),
+            VfsError::NoMount(p) => VfsError::NoMount(f(p)),
+            VfsError::UnknownSource(p) => VfsError::UnknownSource(f(p)),
+            VfsError::Corrupt(p) => VfsError::Corrupt(f(p)),
+            VfsError::Io { path, source } => VfsError::Io { path: f(path), source },
+            e @ VfsError::Unsupported(_) => e,
+        }
+    }
+}
+
+impl fmt::Display for VfsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VfsError::NotFound(p) => write!(f, "not found: {}", p),
+            VfsError::AlreadyExists(p) => write!(f, "already exists: {}", p),
+            VfsError::NotADirectory(p) => write!(f, "not a directory: {}", p),
+            VfsError::IsADirectory(p) => write!(f, "is a directory: {}", p),
+            VfsError::DirectoryNotEmpty(p) => write!(f, "directory not empty: {}", p),
+            VfsError::MissingParent(p) => write!(f, "parent directory does not exist: {}", p),
+            VfsError::ReadOnly(p) => write!(f, "read-only: {}", p),
+            VfsError::OutsideRoot(p) => write!(f, "path escapes the backend root: {}", p),
+            VfsError::NoMount(p) => write!(f, "no backend mounted for: {}", p),
+            VfsError::UnknownSource(p) => write!(f, "unknown asset source: {}", p),
+            VfsError::Unsupported(op) => write!(f, "operation not supported by backend: {}", op),
+            VfsError::Corrupt(p) => write!(f, "corrupt data: {}", p),
+            VfsError::Io { path, source } => write!(f, "{}: {}", path, source),
+        }
+    }
+}
+
+impl std::error::Error for VfsError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            VfsError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn io_kinds_map_to_variants() {
+        let e = |kind| VfsError::from_io("/p", io::Error::from(kind));
+        assert!(e(io::ErrorKind::NotFound).is_not_found());
+        assert!(matches!(e(io::ErrorKind::IsADirectory), VfsError::IsADirectory(_)));
+        assert!(matches!(e(io::ErrorKind::NotADirectory), VfsError::NotADirectory(_)));
+        assert!(matches!(e(io::ErrorKind::AlreadyExists), VfsError::AlreadyExists(_)));
+        assert!(matches!(e(io::ErrorKind::ReadOnlyFilesystem), VfsError::ReadOnly(_)));
+        let other = e(io::ErrorKind::PermissionDenied);
+        assert!(matches!(other, VfsError::Io { ref path, .. } if path == "/p"));
+        assert!(std::error::Error::source(&other).is_some());
+    }
+
+    #[test]
+    fn paths_are_carried_and_rebased() {
+        let e = VfsError::IsADirectory("/tex".into()).map_path(|p| format!("/assets{}", p));
+        assert_eq!(e.path(), Some("/assets/tex"));
+        assert_eq!(e.to_string(), "is a directory: /assets/tex");
+        assert_eq!(VfsError::Unsupported("watch").path(), None);
+        assert!(!VfsError::Corrupt("/a".into()).is_not_found());
+    }
+}
@@ -0,0 +1,101 @@
+// Glob matching over normalized VFS paths.
+// `*` matches within one segment, `?` one character, `**` any number of segments.
+
+pub(crate) fn has_wildcard(s: &str) -> bool { s.contains(['*', '?']) }
+
+/// Longest leading run of segments without wildcards, as a directory path.
+pub(crate) fn static_prefix(pattern: &str) -> String {
+    let mut out = String::new();
+    let segs: Vec<&str> = pattern.split('/').filter(|s| !s.is_empty()).collect();
+    for (i, seg) in segs.iter().enumerate() {
+        // the last segment names the entries themselves, not a directory to start from
+        if has_wildcard(seg) || i + 1 == segs.len() { break; }
+        out.push('/');
+        out.push_str(seg);
+    }
+    if out.is_empty() { "/".to_string() } else { out }
+}
+
+pub(crate) fn matches(pattern: &str, path: &str) -> bool {
+    let pat: Vec<&str> = pattern.split('/').filter(|s| !s.is_empty()).collect();
+    let segs: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    match_segments(&pat, &segs)
+}
+
+fn match_segments(pat: &[&str], segs: &[&str]) -> bool {
+    match pat.split_first() {
+        None => segs.is_empty(),
+        Some((&"**", rest)) => (0..=segs.len()).any(|i| match_segments(rest, &segs[i..])),
+        Some((p, rest)) => match segs.split_first() {
+            Some((s, srest)) => match_segment(&p.chars().collect::<Vec<_>>(), &s.chars().collect::<Vec<_>>()) && match_segments(rest, srest),
+            None => false,
+        },
+    }
+}
+
+fn match_segment(p: &[char], s: &[char]) -> bool {
+    // iterative wildcard match with single backtrack point
+    let (mut pi, mut si) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while si < s.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == s[si]) {
+            pi += 1;
+            si += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, si));
+            pi += 1;
+        } else if let Some((sp, ss)) = star {
+            pi = sp + 1;
+            si = ss + 1;
+            star = Some((sp, ss + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn star_stays_in_one_segment() {
+        assert!(matches("/a/*.png", "/a/x.png"));
+        assert!(matches("/a/*.png", "/a/.png"));
+        assert!(!matches("/a/*.png", "/a/b/x.png"));
+        assert!(!matches("/a/*.png", "/a/x.pngx"));
+        assert!(matches("/a/*", "/a/anything"));
+        assert!(matches("/a/x*y*z", "/a/xaybz"));
+        assert!(!matches("/a/x*y*z", "/a/xayb"));
+    }
+
+    #[test]
+    fn question_mark_is_one_character() {
+        assert!(matches("/a/?.txt", "/a/1.txt"));
+        assert!(matches("/a/?.txt", "/a/é.txt"));
+        assert!(!matches("/a/?.txt", "/a/12.txt"));
+        assert!(!matches("/a/?.txt", "/a/.txt"));
+    }
+
+    #[test]
+    fn double_star_spans_segments() {
+        assert!(matches("/a/**/*.png", "/a/x.png"));
+        assert!(matches("/a/**/*.png", "/a/b/c/x.png"));
+        assert!(!matches("/a/**/*.png", "/b/x.png"));
+        assert!(matches("/**", "/"));
+        assert!(matches("/a/**", "/a"));
+        assert!(matches("/a/**", "/a/b/c"));
+        assert!(matches("/a/**/b/**/c", "/a/x/b/y/z/c"));
+        assert!(!matches("/a/b", "/a/b/c"));
+    }
+
+    #[test]
+    fn static_prefix_stops_at_wildcards() {
+        assert_eq!(static_prefix("/assets/**/*.png"), "/assets");
+        assert_eq!(static_prefix("/a/b/c.txt"), "/a/b");
+        assert_eq!(static_prefix("/a/*/c.txt"), "/a");
+        assert_eq!(static_prefix("/*.txt"), "/");
+        assert!(has_wildcard("a?") && !has_wildcard("/a/b"));
+    }
+}
@@ -0,0 +1,298 @@
+use std::collections::HashMap;
+use std::io::{self, Cursor, Write};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use super::{norm, parent_of, Backend, Bytes, CommitWrite, Metadata, VfsError, VfsEvent, VfsEventKind, VfsReader, VfsResult, VfsWriter};
+
+struct MemFile {
+    // shared so reads and open handles don't copy
+    data: Arc<[u8]>,
+    modified: SystemTime,
+}
+
+// Buffers writes and stores the file on commit or drop.
+struct MemWriter<'a> {
+    backend: &'a mut MemBackend,
+    path: String,
+    buf: Vec<u8>,
+}
+
+impl Write for MemWriter<'_> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> { self.buf.extend_from_slice(data); Ok(data.len()) }
+    fn flush(&mut self) -> io::Result<()> { Ok(()) }
+}
+
+impl CommitWrite for MemWriter<'_> {
+    // storing in memory can't fail
+    fn commit(self: Box<Self>) -> VfsResult<()> { Ok(()) }
+}
+
+impl Drop for MemWriter<'_> {
+    fn drop(&mut self) {
+        let data: Arc<[u8]> = Arc::from(std::mem::take(&mut self.buf));
+        self.backend.store(std::mem::take(&mut self.path), data);
+    }
+}
+
+// Simple in-memory backend with hierarchical directories.
+// When watched, every change is recorded as it happens.
+#[derive(Default)]
+pub struct MemBackend {
+    files: HashMap<String, MemFile>,
+    dirs: HashMap<String, ()>,
+    watching: bool,
+    changes: Vec<VfsEvent>,
+}
+
+impl MemBackend {
+    pub fn new() -> Self { let mut s = Self::default(); s.dirs.insert("/".into(), ()); s }
+
+    // parent must be an existing directory
+    fn check_parent(&self, p: &str) -> VfsResult<()> {
+        let parent = parent_of(p);
+        if self.dirs.contains_key(parent) { return Ok(()); }
+        if self.files.contains_key(parent) { return Err(VfsError::NotADirectory(parent.to_string())); }
+        Err(VfsError::MissingParent(p.to_string()))
+    }
+
+    fn record(&mut self, kind: VfsEventKind, path: &str) {
+        if self.watching { self.changes.push(VfsEvent::new(kind, path)); }
+    }
+
+    fn store(&mut self, p: String, data: Arc<[u8]>) {
+        let kind = if self.files.contains_key(&p) { VfsEventKind::Modified } else { VfsEventKind::Created };
+        self.record(kind, &p);
+        self.files.insert(p, MemFile { data, modified: SystemTime::now() });
+    }
+
+    fn has_children(&self, dir: &str) -> bool {
+        let prefix = if dir == "/" { "/".to_string() } else { format!("{}/", dir) };
+        self.files.keys().chain(self.dirs.keys()).any(|k| k.len() > prefix.len() && k.starts_with(&prefix))
+    }
+}
+
+impl Backend for MemBackend {
+    fn read(&self, path: &str) -> VfsResult<Bytes> { self.read_shared(path).map(|d| d.to_vec()) }
+    fn read_shared(&self, path: &str) -> VfsResult<Arc<[u8]>> {
+        let p = norm(path);
+        if let Some(f) = self.files.get(&p) { return Ok(f.data.clone()); }
+        if self.dirs.contains_key(&p) { return Err(VfsError::IsADirectory(p)); }
+        Err(VfsError::NotFound(p))
+    }
+    fn open(&self, path: &str) -> VfsResult<VfsReader> { Ok(Box::new(Cursor::new(self.read_shared(path)?))) }
+    fn write(&mut self, path: &str, data: &[u8]) -> VfsResult<()> {
+        let p = norm(path);
+        if self.dirs.contains_key(&p) { return Err(VfsError::IsADirectory(p)); }
+        // ensure parent dir exists
+        self.check_parent(&p)?;
+        self.store(p, Arc::from(data));
+        Ok(())
+    }
+    fn create(&mut self, path: &str) -> VfsResult<VfsWriter<'_>> {
+        let p = norm(path);
+        if self.dirs.contains_key(&p) { return Err(VfsError::IsADirectory(p)); }
+        self.check_parent(&p)?;
+        Ok(Box::new(MemWriter { backend: self, path: p, buf: Vec::new() }))
+    }
+    fn metadata(&self, path: &str) -> VfsResult<Metadata> {
+        let p = norm(path);
+        if let Some(f) = self.files.get(&p) {
+            return Ok(Metadata { is_dir: false, size: f.data.len() as u64, modified: Some(f.modified) });
+        }
+        if self.dirs.contains_key(&p) { return Ok(Metadata { is_dir: true, size: 0, modified: None }); }
+        Err(VfsError::NotFound(p))
+    }
+    fn exists(&self, path: &str) -> bool { self.files.contains_key(&norm(path)) || self.dirs.contains_key(&norm(path)) }
+    fn list(&self, path: &str) -> VfsResult<Vec<String>> {
+        let base = norm(path);
+        if !self.dirs.contains_key(&base) {
+            return Err(if self.files.contains_key(&base) { VfsError::NotADirectory(base) } else { VfsError::NotFound(base) });
+        }
+        let mut out = Vec::new();
+        let prefix = if base == "/" { "/".to_string() } else { format!("{}/", base) };
+        for k in self.files.keys().chain(self.dirs.keys()) {
+            if let Some(rest) = k.strip_prefix(prefix.as_str()) {
+                if rest.is_empty() { continue; }
+                if let Some(i) = rest.find('/') { out.push(rest[..i].to_string()); } else { out.push(rest.to_string()); }
+            }
+        }
+        out.sort(); out.dedup(); Ok(out)
+    }
+    fn mkdir(&mut self, path: &str) -> VfsResult<()> {
+        let p = norm(path);
+        if self.exists(&p) { return Err(VfsError::AlreadyExists(p)); }
+        self.check_parent(&p)?;
+        self.record(VfsEventKind::Created, &p);
+        self.dirs.insert(p, ());
+        Ok(())
+    }
+    fn remove(&mut self, path: &str) -> VfsResult<()> {
+        let p = norm(path);
+        if self.files.remove(&p).is_some() { self.record(VfsEventKind::Removed, &p); return Ok(()); }
+        if !self.dirs.contains_key(&p) { return Err(VfsError::NotFound(p)); }
+        if p == "/" { return Err(VfsError::Unsupported("remove /")); }
+        if self.has_children(&p) { return Err(VfsError::DirectoryNotEmpty(p)); }
+        self.dirs.remove(&p);
+        self.record(VfsEventKind::Removed, &p);
+        Ok(())
+    }
+    fn rename(&mut self, from: &str, to: &str) -> VfsResult<()> {
+        let (from, to) = (norm(from), norm(to));
+        if from == to { return if self.exists(&from) { Ok(()) } else { Err(VfsError::NotFound(from)) }; }
+        if let Some(f) = self.files.remove(&from) {
+            if self.dirs.contains_key(&to) { self.files.insert(from, f); return Err(VfsError::IsADirectory(to)); }
+            if let Err(e) = self.check_parent(&to) { self.files.insert(from, f); return Err(e); }
+            let kind = if self.files.contains_key(&to) { VfsEventKind::Modified } else { VfsEventKind::Created };
+            self.record(VfsEventKind::Removed, &from);
+            self.record(kind, &to);
+            self.files.insert(to, f);
+            return Ok(());
+        }
+        if !self.dirs.contains_key(&from) { return Err(VfsError::NotFound(from)); }
+        if from == "/" || to.starts_with(&format!("{}/", from)) { return Err(VfsError::Unsupported("rename a directory into itself")); }
+        if self.exists(&to) { return Err(VfsError::AlreadyExists(to)); }
+        self.check_parent(&to)?;
+        // move the directory and everything under it
+        let prefix = format!("{}/", from);
+        let moved = |k: &str| -> Option<String> {
+            if k == from { Some(to.clone()) } else { k.strip_prefix(prefix.as_str()).map(|rest| format!("{}/{}", to, rest)) }
+        };
+        let mut dirs: Vec<(String, String)> = self.dirs.keys().filter_map(|k| moved(k).map(|n| (k.clone(), n))).collect();
+        let mut files: Vec<(String, String)> = self.files.keys().filter_map(|k| moved(k).map(|n| (k.clone(), n))).collect();
+        dirs.sort();
+        files.sort();
+        for (old, new) in dirs.iter().chain(&files) {
+            self.record(VfsEventKind::Removed, old);
+            self.record(VfsEventKind::Created, new);
+        }
+        for (old, new) in dirs { self.dirs.remove(&old); self.dirs.insert(new, ()); }
+        for (old, new) in files {
+            if let Some(f) = self.files.remove(&old) { self.files.insert(new, f); }
+        }
+        Ok(())
+    }
+    fn watch(&mut self, enabled: bool) -> bool {
+        self.watching = enabled;
+        if !enabled { self.changes.clear(); }
+        true
+    }
+    fn poll_changes(&mut self, out: &mut Vec<VfsEvent>) { out.append(&mut self.changes); }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::Vfs;
+
+    fn vfs() -> Vfs {
+        let mut vfs = Vfs::new();
+        vfs.mount("/", Box::new(MemBackend::new()));
+        vfs.create_dir_all("/a/b").unwrap();
+        vfs.write("/a/one.txt", b"1").unwrap();
+        vfs.write("/a/b/two.txt", b"2").unwrap();
+        vfs
+    }
+
+    #[test]
+    fn not_found_vs_is_a_directory() {
+        let mut vfs = vfs();
+        assert!(matches!(vfs.read("/a/missing"), Err(VfsError::NotFound(p)) if p == "/a/missing"));
+        assert!(vfs.read("/a/missing").unwrap_err().is_not_found());
+        assert!(matches!(vfs.read("/a/b"), Err(VfsError::IsADirectory(p)) if p == "/a/b"));
+        assert!(matches!(vfs.open("/a/b"), Err(VfsError::IsADirectory(_))));
+        assert!(matches!(vfs.write("/a/b", b"x"), Err(VfsError::IsADirectory(_))));
+        assert!(matches!(vfs.list("/a/one.txt"), Err(VfsError::NotADirectory(_))));
+        assert!(matches!(vfs.list("/nope"), Err(VfsError::NotFound(_))));
+        assert!(matches!(vfs.write("/nope/f", b"x"), Err(VfsError::MissingParent(p)) if p == "/nope/f"));
+        assert!(matches!(vfs.write("/a/one.txt/f", b"x"), Err(VfsError::NotADirectory(p)) if p == "/a/one.txt"));
+        assert!(matches!(vfs.remove("/a"), Err(VfsError::DirectoryNotEmpty(_))));
+        assert!(matches!(vfs.mkdir("/a"), Err(VfsError::AlreadyExists(_))));
+    }
+
+    #[test]
+    fn rename_overwrites_files_but_not_directories() {
+        let mut vfs = vfs();
+        vfs.rename("/a/one.txt", "/a/b/two.txt").unwrap();
+        assert_eq!(vfs.read("/a/b/two.txt").unwrap(), b"1");
+        assert!(!vfs.exists("/a/one.txt"));
+
+        vfs.write("/a/one.txt", b"1").unwrap();
+        assert!(matches!(vfs.rename("/a/one.txt", "/a/b"), Err(VfsError::IsADirectory(_))));
+        vfs.mkdir("/c").unwrap();
+        assert!(matches!(vfs.rename("/a", "/c"), Err(VfsError::AlreadyExists(_))));
+        assert!(matches!(vfs.rename("/a/missing", "/x"), Err(VfsError::NotFound(_))));
+        // failed renames leave the source in place
+        assert_eq!(vfs.read("/a/one.txt").unwrap(), b"1");
+    }
+
+    #[test]
+    fn rename_moves_directory_trees() {
+        let mut vfs = vfs();
+        assert!(matches!(vfs.rename("/a", "/a/b/inner"), Err(VfsError::Unsupported(_))));
+        assert!(vfs.exists("/a/b/two.txt"));
+        vfs.rename("/a", "/z").unwrap();
+        assert!(!vfs.exists("/a"));
+        assert_eq!(vfs.list_recursive("/z").unwrap(), ["/z/b", "/z/b/two.txt", "/z/one.txt"]);
+        assert_eq!(vfs.read("/z/b/two.txt").unwrap(), b"2");
+    }
+
+    #[test]
+    fn create_dir_all_and_remove_all() {
+        let mut vfs = vfs();
+        vfs.create_dir_all("/x/y/z").unwrap();
+        assert!(vfs.is_dir("/x") && vfs.is_dir("/x/y") && vfs.is_dir("/x/y/z"));
+        vfs.create_dir_all("/x/y").unwrap();
+        vfs.create_dir_all("/").unwrap();
+        assert!(matches!(vfs.create_dir_all("/a/one.txt/sub"), Err(VfsError::NotADirectory(p)) if p == "/a/one.txt"));
+
+        vfs.remove_all("/a").unwrap();
+        assert!(!vfs.exists("/a") && !vfs.exists("/a/b/two.txt"));
+        vfs.write("/x/f", b"").unwrap();
+        vfs.remove_all("/x/f").unwrap();
+        assert!(!vfs.exists("/x/f"));
+        assert!(matches!(vfs.remove_all("/a"), Err(VfsError::NotFound(_))));
+        assert_eq!(vfs.list("/").unwrap(), ["x"]);
+    }
+
+    #[test]
+    fn list_recursive_is_sorted_depth_first() {
+        let mut vfs = vfs();
+        vfs.write("/a/b.txt", b"").unwrap();
+        vfs.write("/a/0.txt", b"").unwrap();
+        assert_eq!(
+            vfs.list_recursive("/a").unwrap(),
+            ["/a/0.txt", "/a/b", "/a/b.txt", "/a/b/two.txt", "/a/one.txt"]
+        );
+        assert_eq!(vfs.list_recursive("/").unwrap()[0], "/a");
+        assert!(vfs.list_recursive("/a/b/two.txt").is_err());
+    }
+
+    #[test]
+    fn glob_through_vfs() {
+        let mut vfs = vfs();
+        vfs.write("/a/b/three.png", b"").unwrap();
+        assert_eq!(vfs.glob("/a/*.txt").unwrap(), ["/a/one.txt"]);
+        assert_eq!(vfs.glob("/a/**/*.txt").unwrap(), ["/a/b/two.txt", "/a/one.txt"]);
+        assert_eq!(vfs.glob("/**/t??.*").unwrap(), ["/a/b/two.txt"]);
+        assert!(vfs.glob("/missing/**").unwrap().is_empty());
+    }
+
+    #[test]
+    fn create_stores_on_drop_and_events_are_recorded() {
+        let mut b = MemBackend::new();
+        assert!(b.watch(true));
+        {
+            let mut w = b.create("/f").unwrap();
+            w.write_all(b"ab").unwrap();
+            w.write_all(b"c").unwrap();
+        }
+        assert_eq!(b.read("/f").unwrap(), b"abc");
+        b.write("/f", b"d").unwrap();
+        b.remove("/f").unwrap();
+        let mut events = Vec::new();
+        b.poll_changes(&mut events);
+        let kinds: Vec<VfsEventKind> = events.iter().map(|e| e.kind).collect();
+        assert_eq!(kinds, [VfsEventKind::Created, VfsEventKind::Modified, VfsEventKind::Removed]);
+    }
+}
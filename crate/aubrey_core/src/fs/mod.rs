@@ -0,0 +1,458 @@
+use std::io::{Cursor, Read, Seek, Write};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+pub mod error;
+pub mod mem;
+pub mod std_fs;
+pub mod pack;
+pub mod overlay;
+pub mod watch;
+pub mod embedded;
+mod glob;
+
+pub use error::{VfsError, VfsResult};
+pub use mem::MemBackend;
+pub use std_fs::{user_data_dir, StdFsBackend};
+pub use pack::{PackBackend, PackWriter, Compression};
+pub use overlay::OverlayBackend;
+pub use watch::{VfsEvent, VfsEventKind};
+pub use embedded::EmbeddedBackend;
+
+pub type Bytes = Vec<u8>;
+
+/// Readable + seekable stream returned by `open`.
+pub trait ReadSeek: Read + Seek + Send {}
+impl<T: Read + Seek + Send> ReadSeek for T {}
+
+pub type VfsReader = Box<dyn ReadSeek>;
+/// Writable stream returned by `create`.
+pub trait CommitWrite: Write + Send {
+    /// Replace the file with what was written, reporting failure. Dropping the handle
+    /// commits too, but can only log errors.
+    fn commit(self: Box<Self>) -> VfsResult<()>;
+}
+
+/// Stream returned by `create`. The file is replaced on `commit` or when the handle is dropped.
+pub type VfsWriter<'a> = Box<dyn CommitWrite + 'a>;
+
+/// Entry information returned by `metadata`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Metadata {
+    pub is_dir: bool,
+    /// File size in bytes (0 for directories).
+    pub size: u64,
+    /// Last modification time, if the backend tracks it.
+    pub modified: Option<SystemTime>,
+}
+
+impl Metadata {
+    pub fn is_file(&self) -> bool { !self.is_dir }
+}
+
+/// Storage behind a mount point. Paths passed in are normalized and relative to the mount.
+/// Mutating operations default to `ReadOnly`.
+pub trait Backend: Send + Sync {
+    fn read(&self, path: &str) -> VfsResult<Bytes>;
+    /// Shared view of the file; backends that keep data in memory return it without copying.
+    fn read_shared(&self, path: &str) -> VfsResult<Arc<[u8]>> { self.read(path).map(Arc::from) }
+    /// Streaming read. Defaults to buffering the whole file.
+    fn open(&self, path: &str) -> VfsResult<VfsReader> { Ok(Box::new(Cursor::new(self.read(path)?))) }
+    fn write(&mut self, path: &str, data: &[u8]) -> VfsResult<()> { let _ = data; Err(VfsError::ReadOnly(path.to_string())) }
+    /// Streaming write (create or truncate); the parent must exist.
+    fn create(&mut self, path: &str) -> VfsResult<VfsWriter<'_>> { Err(VfsError::ReadOnly(path.to_string())) }
+    fn metadata(&self, path: &str) -> VfsResult<Metadata>;
+    fn exists(&self, path: &str) -> bool { self.metadata(path).is_ok() }
+    /// Names (not paths) of the entries directly under `path`, sorted.
+    fn list(&self, path: &str) -> VfsResult<Vec<String>>;
+    /// Create one directory; the parent must exist.
+    fn mkdir(&mut self, path: &str) -> VfsResult<()> { Err(VfsError::ReadOnly(path.to_string())) }
+    /// Remove a file or an empty directory.
+    fn remove(&mut self, path: &str) -> VfsResult<()> { Err(VfsError::ReadOnly(path.to_string())) }
+    /// Move a file or directory within this backend; an existing file at `to` is replaced.
+    fn rename(&mut self, from: &str, to: &str) -> VfsResult<()> { let _ = to; Err(VfsError::ReadOnly(from.to_string())) }
+    /// Start or stop recording changes. Returns false if the backend can't report changes.
+    fn watch(&mut self, enabled: bool) -> bool { let _ = enabled; false }
+    /// Append changes since the last call (backend-relative paths). Polling backends rescan here.
+    fn poll_changes(&mut self, out: &mut Vec<VfsEvent>) { let _ = out; }
+}
+
+// "scheme://rest" -> (Some("scheme"), "rest"). Schemes are ASCII letters, digits, `_` and `-`.
+pub(crate) fn split_scheme(path: &str) -> (Option<&str>, &str) {
+    match path.split_once("://") {
+        Some((s, rest)) if !s.is_empty() && s.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'_' || b == b'-') => (Some(s), rest),
+        _ => (None, path),
+    }
+}
+
+// Normalized path; a `scheme://` prefix is kept ("embedded://fonts/a.ttf").
+pub(crate) fn norm(path: &str) -> String {
+    if let (Some(scheme), rest) = split_scheme(path) { return format!("{}:/{}", scheme, norm(rest)); }
+    let mut out = String::from("/");
+    for part in path.split('/') {
+        if part.is_empty() || part == "." { continue; }
+        if part == ".." { continue; }
+        if !out.ends_with('/') { out.push('/'); }
+        out.push_str(part);
+    }
+    if out.len() > 1 && out.ends_with('/') { out.pop(); }
+    out
+}
+
+// Parent of a normalized path ("/" for top-level entries and for "/"; "scheme://" likewise).
+pub(crate) fn parent_of(p: &str) -> &str {
+    match p.rfind('/') {
+        Some(0) | None => "/",
+        Some(i) if p[..i].ends_with(":/") => &p[..=i],
+        Some(i) => &p[..i],
+    }
+}
+
+fn join(dir: &str, name: &str) -> String {
+    if dir == "/" { format!("/{}", name) } else { format!("{}/{}", dir, name) }
+}
+
+// Mount table that dispatches to backends by longest-prefix path match.
+// Paths may also be `scheme://path` URIs, resolved through the source table first.
+pub struct Vfs {
+    mounts: Vec<(String, Box<dyn Backend>)>,
+    sources: Vec<(String, String)>,
+}
+
+impl Vfs {
+    pub fn new() -> Self { Self { mounts: Vec::new(), sources: Vec::new() } }
+    /// Make `scheme://a/b` refer to `<root>/a/b`, e.g. `add_source("user", "/user")`.
+    /// Replaces an existing source with the same scheme.
+    pub fn add_source(&mut self, scheme: &str, root: &str) {
+        let root = norm(root);
+        match self.sources.iter_mut().find(|(s, _)| s == scheme) {
+            Some(entry) => entry.1 = root,
+            None => self.sources.push((scheme.to_string(), root)),
+        }
+    }
+    pub fn remove_source(&mut self, scheme: &str) { self.sources.retain(|(s, _)| s != scheme); }
+    pub fn source_root(&self, scheme: &str) -> Option<&str> {
+        self.sources.iter().find(|(s, _)| s == scheme).map(|(_, r)| r.as_str())
+    }
+    /// Normalized VFS path for `path`, expanding a `scheme://` prefix.
+    pub fn resolve(&self, path: &str) -> VfsResult<String> {
+        let p = norm(path);
+        let (Some(scheme), rest) = split_scheme(&p) else { return Ok(p) };
+        let root = self.source_root(scheme).ok_or_else(|| VfsError::UnknownSource(p.clone()))?;
+        Ok(if rest.is_empty() { root.to_string() } else { join(root, rest) })
+    }
+    /// `scheme://` forms of a plain VFS path, one per source whose root contains it.
+    pub fn uris_for(&self, path: &str) -> Vec<String> {
+        let p = norm(path);
+        self.sources
+            .iter()
+            .filter_map(|(scheme, root)| {
+                let rest = if root == "/" { p.strip_prefix('/') } else { p.strip_prefix(root.as_str()).and_then(|r| if r.is_empty() { Some(r) } else { r.strip_prefix('/') }) };
+                rest.map(|r| format!("{}://{}", scheme, r))
+            })
+            .collect()
+    }
+    pub fn mount(&mut self, at: &str, backend: Box<dyn Backend>) {
+        let p = norm(at);
+        self.mounts.push((p, backend));
+        self.mounts.sort_by(|a, b| b.0.len().cmp(&a.0.len()));
+    }
+    // index of the mount serving `p` (normalized) and the backend-relative path
+    fn route_index(&self, p: &str) -> Option<(usize, String)> {
+        for (i, (mp, _)) in self.mounts.iter().enumerate() {
+            let is_match = if mp == "/" { true } else { p == *mp || p.starts_with(&(mp.clone() + "/")) };
+            if is_match {
+                let sub = if mp == "/" {
+                    if p == "/" { "/".to_string() } else { format!("/{}", &p[1..]) }
+                } else if p == *mp { "/".to_string() } else { format!("/{}", &p[mp.len()+1..]) };
+                return Some((i, sub));
+            }
+        }
+        None
+    }
+    fn route_mut(&mut self, path: &str) -> VfsResult<(&str, &mut Box<dyn Backend>, String)> {
+        let p = self.resolve(path)?;
+        let (i, sub) = self.route_index(&p).ok_or(VfsError::NoMount(p))?;
+        let (mp, be) = &mut self.mounts[i];
+        Ok((mp.as_str(), be, sub))
+    }
+    fn route(&self, path: &str) -> VfsResult<(&str, &dyn Backend, String)> {
+        let p = self.resolve(path)?;
+        let (i, sub) = self.route_index(&p).ok_or(VfsError::NoMount(p))?;
+        let (mp, be) = &self.mounts[i];
+        Ok((mp.as_str(), be.as_ref(), sub))
+    }
+    // backend-relative path -> VFS path
+    fn full_path(mount: &str, p: String) -> String {
+        if mount == "/" { p } else if p == "/" { mount.to_string() } else { format!("{}{}", mount, p) }
+    }
+    fn rebase(mount: &str) -> impl Fn(VfsError) -> VfsError + '_ {
+        move |e| e.map_path(|p| Self::full_path(mount, p))
+    }
+
+    pub fn read(&self, path: &str) -> VfsResult<Bytes> {
+        let (mp, b, sub) = self.route(path)?;
+        b.read(&sub).map_err(Self::rebase(mp))
+    }
+    /// Shared, copy-free view where the backend supports it (e.g. `MemBackend`).
+    pub fn read_shared(&self, path: &str) -> VfsResult<Arc<[u8]>> {
+        let (mp, b, sub) = self.route(path)?;
+        b.read_shared(&sub).map_err(Self::rebase(mp))
+    }
+    /// Open for streaming reads (`Read + Seek`).
+    pub fn open(&self, path: &str) -> VfsResult<VfsReader> {
+        let (mp, b, sub) = self.route(path)?;
+        b.open(&sub).map_err(Self::rebase(mp))
+    }
+    /// Open for streaming writes. The new contents replace the file on `commit` (or when the handle is dropped).
+    pub fn create(&mut self, path: &str) -> VfsResult<VfsWriter<'_>> {
+        let (mp, b, sub) = self.route_mut(path)?;
+        let rebase = Self::rebase(mp);
+        b.create(&sub).map_err(rebase)
+    }
+    /// Write a file; the parent directory must exist.
+    pub fn write(&mut self, path: &str, data: &[u8]) -> VfsResult<()> {
+        let (mp, b, sub) = self.route_mut(path)?;
+        b.write(&sub, data).map_err(Self::rebase(mp))
+    }
+    pub fn exists(&self, path: &str) -> bool { self.route(path).map(|(_, b, sub)| b.exists(&sub)).unwrap_or(false) }
+    pub fn metadata(&self, path: &str) -> VfsResult<Metadata> {
+        let (mp, b, sub) = self.route(path)?;
+        b.metadata(&sub).map_err(Self::rebase(mp))
+    }
+    pub fn is_dir(&self, path: &str) -> bool { self.metadata(path).is_ok_and(|m| m.is_dir) }
+    pub fn is_file(&self, path: &str) -> bool { self.metadata(path).is_ok_and(|m| m.is_file()) }
+    /// Entry names directly under `path`, including mount points nested there.
+    pub fn list(&self, path: &str) -> VfsResult<Vec<String>> {
+        let p = self.resolve(path)?;
+        let mut nested: Vec<String> = self
+            .mounts
+            .iter()
+            .filter(|(mp, _)| *mp != p && mp != "/" && parent_of(mp) == p)
+            .map(|(mp, _)| mp[mp.rfind('/').map(|i| i + 1).unwrap_or(0)..].to_string())
+            .collect();
+        let listed = self.route(&p).and_then(|(mp, b, sub)| b.list(&sub).map_err(Self::rebase(mp)));
+        let mut out = match listed {
+            Ok(v) => v,
+            Err(e) if nested.is_empty() => return Err(e),
+            Err(_) => Vec::new(),
+        };
+        out.append(&mut nested);
+        out.sort(); out.dedup();
+        Ok(out)
+    }
+    /// Create one directory; the parent must exist.
+    pub fn mkdir(&mut self, path: &str) -> VfsResult<()> {
+        let (mp, b, sub) = self.route_mut(path)?;
+        b.mkdir(&sub).map_err(Self::rebase(mp))
+    }
+    /// Create `path` and any missing parents. Ok if it already is a directory.
+    pub fn create_dir_all(&mut self, path: &str) -> VfsResult<()> {
+        let p = self.resolve(path)?;
+        let mut cur = String::new();
+        for part in p.split('/').filter(|s| !s.is_empty()) {
+            cur.push('/');
+            cur.push_str(part);
+            match self.metadata(&cur) {
+                Ok(m) if m.is_dir => continue,
+                Ok(_) => return Err(VfsError::NotADirectory(cur)),
+                Err(e) if e.is_not_found() => self.mkdir(&cur)?,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+    /// Remove a file or an empty directory.
+    pub fn remove(&mut self, path: &str) -> VfsResult<()> {
+        let (mp, b, sub) = self.route_mut(path)?;
+        b.remove(&sub).map_err(Self::rebase(mp))
+    }
+    /// Remove a file, or a directory and everything under it.
+    pub fn remove_all(&mut self, path: &str) -> VfsResult<()> {
+        if self.metadata(path)?.is_dir {
+            // deepest first
+            for entry in self.list_recursive(path)?.into_iter().rev() { self.remove(&entry)?; }
+        }
+        self.remove(path)
+    }
+    /// Copy a file, or a directory recursively. Works across mounts.
+    pub fn copy(&mut self, from: &str, to: &str) -> VfsResult<()> {
+        let (from, to) = (self.resolve(from)?, self.resolve(to)?);
+        if !self.metadata(&from)?.is_dir { return self.write(&to, &self.read(&from)?); }
+        if to.starts_with(&format!("{}/", from)) { return Err(VfsError::Unsupported("copy a directory into itself")); }
+        self.create_dir_all(&to)?;
+        for entry in self.list_recursive(&from)? {
+            let dest = format!("{}{}", to.trim_end_matches('/'), &entry[from.trim_end_matches('/').len()..]);
+            if self.metadata(&entry)?.is_dir { self.create_dir_all(&dest)?; } else { self.write(&dest, &self.read(&entry)?)?; }
+        }
+        Ok(())
+    }
+    /// Move a file or directory. Within one mount this is the backend's rename;
+    /// across mounts it falls back to copy + remove.
+    pub fn rename(&mut self, from: &str, to: &str) -> VfsResult<()> {
+        let (from, to) = (self.resolve(from)?, self.resolve(to)?);
+        let (a, sub_from) = self.route_index(&from).ok_or_else(|| VfsError::NoMount(from.clone()))?;
+        let (b, sub_to) = self.route_index(&to).ok_or_else(|| VfsError::NoMount(to.clone()))?;
+        if a == b {
+            let (mp, be) = &mut self.mounts[a];
+            return be.rename(&sub_from, &sub_to).map_err(Self::rebase(mp));
+        }
+        self.copy(&from, &to)?;
+        self.remove_all(&from)
+    }
+    /// Full paths of every entry under `path` (files and directories), sorted; a directory comes before its contents.
+    /// Paths under a `scheme://` are returned resolved.
+    pub fn list_recursive(&self, path: &str) -> VfsResult<Vec<String>> {
+        let mut out = Vec::new();
+        let mut stack = vec![self.resolve(path)?];
+        while let Some(dir) = stack.pop() {
+            let names = self.list(&dir)?;
+            // reversed so the stack yields entries in order
+            for name in names.into_iter().rev() {
+                let child = join(&dir, &name);
+                if self.is_dir(&child) { stack.push(child.clone()); }
+                out.push(child);
+            }
+        }
+        out.sort();
+        Ok(out)
+    }
+    /// Report changes under the backend mounted exactly at `at` (see `poll_changes`).
+    pub fn watch(&mut self, at: &str) -> VfsResult<()> {
+        let p = self.resolve(at)?;
+        let (_, be) = self.mounts.iter_mut().find(|(mp, _)| *mp == p).ok_or_else(|| VfsError::NoMount(p.clone()))?;
+        if be.watch(true) { Ok(()) } else { Err(VfsError::Unsupported("backend does not report changes")) }
+    }
+    pub fn unwatch(&mut self, at: &str) -> VfsResult<()> {
+        let p = self.resolve(at)?;
+        let (_, be) = self.mounts.iter_mut().find(|(mp, _)| *mp == p).ok_or(VfsError::NoMount(p))?;
+        be.watch(false);
+        Ok(())
+    }
+    /// Changes on watched mounts since the last call, with full (plain, see `uris_for`) VFS paths.
+    /// Entries hidden by a nested mount are skipped.
+    pub fn poll_changes(&mut self) -> Vec<VfsEvent> {
+        let mut out = Vec::new();
+        for i in 0..self.mounts.len() {
+            let mut changes = Vec::new();
+            self.mounts[i].1.poll_changes(&mut changes);
+            for mut ev in changes {
+                ev.path = Self::full_path(&self.mounts[i].0, norm(&ev.path));
+                if self.route_index(&ev.path).is_some_and(|(j, _)| j == i) { out.push(ev); }
+            }
+        }
+        out
+    }
+    /// Paths matching `pattern` (`*`, `?`, `**`), e.g. `"/assets/**/*.png"`. Sorted.
+    pub fn glob(&self, pattern: &str) -> VfsResult<Vec<String>> {
+        let pattern = self.resolve(pattern)?;
+        let base = glob::static_prefix(&pattern);
+        let entries = match self.list_recursive(&base) {
+            Ok(v) => v,
+            Err(e) if e.is_not_found() => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        Ok(entries.into_iter().filter(|p| glob::matches(&pattern, p)).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, SeekFrom};
+
+    // Seeks around a handle opened on a file holding `expected`.
+    pub(super) fn assert_seeks(mut r: VfsReader, expected: &[u8]) {
+        let len = expected.len();
+        assert!(len >= 8);
+        let mut two = [0u8; 2];
+        r.read_exact(&mut two).unwrap();
+        assert_eq!(two, expected[..2]);
+        assert_eq!(r.seek(SeekFrom::Start(5)).unwrap(), 5);
+        r.read_exact(&mut two).unwrap();
+        assert_eq!(two, expected[5..7]);
+        assert_eq!(r.seek(SeekFrom::Current(-4)).unwrap(), 3);
+        assert_eq!(r.stream_position().unwrap(), 3);
+        // relative to the end of this file, not of whatever holds it
+        assert_eq!(r.seek(SeekFrom::End(-3)).unwrap(), len as u64 - 3);
+        let mut tail = Vec::new();
+        r.read_to_end(&mut tail).unwrap();
+        assert_eq!(tail, expected[len - 3..]);
+        assert_eq!(r.seek(SeekFrom::End(10)).unwrap(), len as u64 + 10);
+        assert_eq!(r.read(&mut two).unwrap(), 0);
+        assert!(r.seek(SeekFrom::Current(-(len as i64) - 11)).is_err());
+        r.rewind().unwrap();
+        let mut all = Vec::new();
+        r.read_to_end(&mut all).unwrap();
+        assert_eq!(all, expected);
+    }
+
+    #[test]
+    fn mem_handles_seek() {
+        let mut vfs = Vfs::new();
+        vfs.mount("/", Box::new(MemBackend::new()));
+        vfs.write("/a", b"0123456789").unwrap();
+        assert_seeks(vfs.open("/a").unwrap(), b"0123456789");
+    }
+
+    fn vfs() -> Vfs {
+        let mut vfs = Vfs::new();
+        vfs.mount("/", Box::new(MemBackend::new()));
+        vfs.mount("/embedded", Box::new(EmbeddedBackend::new().with("fonts/a.ttf", b"font").with("readme", b"hi")));
+        vfs.create_dir_all("/user/saves").unwrap();
+        vfs.add_source("embedded", "/embedded");
+        vfs.add_source("user", "/user/");
+        vfs.add_source("root", "/");
+        vfs
+    }
+
+    #[test]
+    fn schemes_route_to_their_roots() {
+        let mut vfs = vfs();
+        assert_eq!(vfs.resolve("embedded://fonts/a.ttf").unwrap(), "/embedded/fonts/a.ttf");
+        assert_eq!(vfs.resolve("user://").unwrap(), "/user");
+        assert_eq!(vfs.resolve("root://x//y/./z").unwrap(), "/x/y/z");
+        assert_eq!(vfs.resolve("/plain/../path").unwrap(), "/plain/path");
+        // `..` can't climb out of the source root
+        assert_eq!(vfs.resolve("user://../../etc/passwd").unwrap(), "/user/etc/passwd");
+
+        assert_eq!(vfs.read("embedded://fonts/a.ttf").unwrap(), b"font");
+        vfs.write("user://saves/1.sav", b"save").unwrap();
+        assert_eq!(vfs.read("/user/saves/1.sav").unwrap(), b"save");
+        assert_eq!(vfs.list("user://saves").unwrap(), ["1.sav"]);
+        assert_eq!(vfs.glob("user://**/*.sav").unwrap(), ["/user/saves/1.sav"]);
+        assert_eq!(vfs.list_recursive("embedded://").unwrap(), ["/embedded/fonts", "/embedded/fonts/a.ttf", "/embedded/readme"]);
+        vfs.rename("user://saves/1.sav", "user://saves/2.sav").unwrap();
+        assert!(vfs.exists("/user/saves/2.sav"));
+        // errors name the resolved path
+        assert!(matches!(vfs.read("user://nope"), Err(VfsError::NotFound(p)) if p == "/user/nope"));
+
+        vfs.add_source("user", "/elsewhere");
+        assert_eq!(vfs.resolve("user://a").unwrap(), "/elsewhere/a");
+        vfs.remove_source("user");
+        assert_eq!(vfs.source_root("user"), None);
+    }
+
+    #[test]
+    fn unknown_schemes_are_errors() {
+        let mut vfs = vfs();
+        assert!(matches!(vfs.resolve("nope://a"), Err(VfsError::UnknownSource(p)) if p == "nope://a"));
+        assert!(matches!(vfs.read("nope://a"), Err(VfsError::UnknownSource(_))));
+        assert!(matches!(vfs.write("nope://a", b""), Err(VfsError::UnknownSource(_))));
+        assert!(matches!(vfs.glob("nope://**"), Err(VfsError::UnknownSource(_))));
+        assert!(!vfs.exists("nope://a"));
+        // not a scheme: the part before `://` has other characters
+        assert_eq!(vfs.resolve("a b://c").unwrap(), "/a b:/c");
+    }
+
+    #[test]
+    fn uris_for_lists_every_matching_source() {
+        let vfs = vfs();
+        let mut uris = vfs.uris_for("/embedded/fonts/a.ttf");
+        uris.sort();
+        assert_eq!(uris, ["embedded://fonts/a.ttf", "root://embedded/fonts/a.ttf"]);
+        assert_eq!(vfs.uris_for("/user"), ["user://", "root://user"]);
+        // a shared prefix isn't enough
+        assert_eq!(vfs.uris_for("/username"), ["root://username"]);
+    }
+}
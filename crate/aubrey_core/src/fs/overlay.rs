@@ -0,0 +1,392 @@
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+use super::{join, norm, parent_of, Backend, Bytes, Metadata, VfsError, VfsEvent, VfsEventKind, VfsReader, VfsResult, VfsWriter};
+
+const WHITEOUT_PREFIX: &str = ".wh.";
+
+/// Several backends stacked at one mount point (union mount).
+/// Reads fall through from the top layer down, `list` merges all layers, and writes go to the
+/// writable upper layer. Deleting something that exists in a lower layer leaves a whiteout
+/// marker (`.wh.<name>`) in the upper layer, which hides the lower entry.
+/// ```ignore
+/// let overlay = OverlayBackend::new()
+///     .with_layer(Box::new(PackBackend::open("base.pak")?))   // bottom
+///     .with_layer(Box::new(StdFsBackend::new("mods/foo")?))   // overrides base
+///     .with_writable(Box::new(StdFsBackend::new("user")?));   // saves / deletions
+/// vfs.mount("/", Box::new(overlay));
+/// ```
+#[derive(Default)]
+pub struct OverlayBackend {
+    upper: Option<Box<dyn Backend>>,
+    // top first
+    lower: Vec<Box<dyn Backend>>,
+}
+
+fn whiteout_of(p: &str) -> String {
+    let name = &p[p.rfind('/').map(|i| i + 1).unwrap_or(0)..];
+    let parent = parent_of(p);
+    if parent == "/" { format!("/{}{}", WHITEOUT_PREFIX, name) } else { format!("{}/{}{}", parent, WHITEOUT_PREFIX, name) }
+}
+
+fn is_whiteout_name(name: &str) -> bool { name.starts_with(WHITEOUT_PREFIX) }
+
+impl OverlayBackend {
+    pub fn new() -> Self { Self::default() }
+
+    /// Stack a read-only layer on top of the existing lower layers.
+    pub fn with_layer(mut self, backend: Box<dyn Backend>) -> Self {
+        self.lower.insert(0, backend);
+        self
+    }
+
+    /// Set the writable layer. It always sits above every read-only layer.
+    pub fn with_writable(mut self, backend: Box<dyn Backend>) -> Self {
+        self.upper = Some(backend);
+        self
+    }
+
+    /// Number of layers, writable one included.
+    pub fn layer_count(&self) -> usize { self.lower.len() + self.upper.is_some() as usize }
+
+    fn upper_has(&self, p: &str) -> bool { self.upper.as_ref().is_some_and(|u| u.exists(p)) }
+
+    // true if `p` or one of its ancestors was deleted from the lower layers
+    fn whited(&self, p: &str) -> bool {
+        let Some(upper) = &self.upper else { return false };
+        let mut cur = p;
+        while cur != "/" {
+            if upper.exists(&whiteout_of(cur)) { return true; }
+            cur = parent_of(cur);
+        }
+        false
+    }
+
+    // layer serving `p`, top first
+    fn find(&self, p: &str) -> Option<&dyn Backend> {
+        if let Some(u) = &self.upper && u.exists(p) { return Some(u.as_ref()); }
+        if self.whited(p) { return None; }
+        self.lower.iter().find(|l| l.exists(p)).map(|l| l.as_ref())
+    }
+
+    fn merged_meta(&self, p: &str) -> VfsResult<Metadata> {
+        self.find(p).ok_or_else(|| VfsError::NotFound(p.to_string()))?.metadata(p)
+    }
+
+    fn upper_mut(&mut self, p: &str) -> VfsResult<&mut Box<dyn Backend>> {
+        self.upper.as_mut().ok_or_else(|| VfsError::ReadOnly(p.to_string()))
+    }
+
+    // Make the directory chain of `dir` exist in the upper layer (copy-up of directories).
+    fn ensure_upper_dir(&mut self, dir: &str) -> VfsResult<()> {
+        let mut cur = String::new();
+        for part in dir.split('/').filter(|s| !s.is_empty()) {
+            cur.push('/');
+            cur.push_str(part);
+            if self.upper_has(&cur) { continue; }
+            match self.merged_meta(&cur) {
+                Ok(m) if m.is_dir => self.upper_mut(&cur)?.mkdir(&cur)?,
+                Ok(_) => return Err(VfsError::NotADirectory(cur)),
+                Err(_) => return Err(VfsError::MissingParent(cur)),
+            }
+        }
+        Ok(())
+    }
+
+    // A re-created file no longer needs its whiteout. It stays while a lower layer has a
+    // directory there, since it also hides that directory's contents (see `mkdir`).
+    fn clear_whiteout(&mut self, p: &str) -> VfsResult<()> {
+        let wh = whiteout_of(p);
+        if !self.upper_has(&wh) || self.lower.iter().any(|l| l.metadata(p).is_ok_and(|m| m.is_dir)) { return Ok(()); }
+        self.upper_mut(p)?.remove(&wh)
+    }
+
+    fn check_name(p: &str) -> VfsResult<()> {
+        let name = &p[p.rfind('/').map(|i| i + 1).unwrap_or(0)..];
+        if is_whiteout_name(name) { Err(VfsError::Unsupported("names starting with .wh. are reserved by OverlayBackend")) } else { Ok(()) }
+    }
+}
+
+impl Backend for OverlayBackend {
+    fn read(&self, path: &str) -> VfsResult<Bytes> {
+        let p = norm(path);
+        self.find(&p).ok_or_else(|| VfsError::NotFound(p.clone()))?.read(&p)
+    }
+
+    fn read_shared(&self, path: &str) -> VfsResult<Arc<[u8]>> {
+        let p = norm(path);
+        self.find(&p).ok_or_else(|| VfsError::NotFound(p.clone()))?.read_shared(&p)
+    }
+
+    fn open(&self, path: &str) -> VfsResult<VfsReader> {
+        let p = norm(path);
+        self.find(&p).ok_or_else(|| VfsError::NotFound(p.clone()))?.open(&p)
+    }
+
+    fn write(&mut self, path: &str, data: &[u8]) -> VfsResult<()> {
+        let p = norm(path);
+        Self::check_name(&p)?;
+        self.upper_mut(&p)?;
+        if self.merged_meta(&p).is_ok_and(|m| m.is_dir) { return Err(VfsError::IsADirectory(p)); }
+        self.ensure_upper_dir(parent_of(&p))?;
+        self.upper_mut(&p)?.write(&p, data)?;
+        self.clear_whiteout(&p)
+    }
+
+    fn create(&mut self, path: &str) -> VfsResult<VfsWriter<'_>> {
+        let p = norm(path);
+        Self::check_name(&p)?;
+        self.upper_mut(&p)?;
+        if self.merged_meta(&p).is_ok_and(|m| m.is_dir) { return Err(VfsError::IsADirectory(p)); }
+        self.ensure_upper_dir(parent_of(&p))?;
+        self.clear_whiteout(&p)?;
+        self.upper_mut(&p)?.create(&p)
+    }
+
+    fn metadata(&self, path: &str) -> VfsResult<Metadata> { self.merged_meta(&norm(path)) }
+
+    fn exists(&self, path: &str) -> bool { self.find(&norm(path)).is_some() }
+
+    fn list(&self, path: &str) -> VfsResult<Vec<String>> {
+        let p = norm(path);
+        if !self.merged_meta(&p)?.is_dir { return Err(VfsError::NotADirectory(p)); }
+        let mut out = BTreeSet::new();
+        let mut hidden = BTreeSet::new();
+        if let Some(u) = &self.upper && let Ok(names) = u.list(&p) {
+            for n in names {
+                match n.strip_prefix(WHITEOUT_PREFIX) {
+                    Some(h) => { hidden.insert(h.to_string()); }
+                    None => { out.insert(n); }
+                }
+            }
+        }
+        if !self.whited(&p) {
+            for l in &self.lower {
+                let Ok(names) = l.list(&p) else { continue };
+                out.extend(names.into_iter().filter(|n| !hidden.contains(n)));
+            }
+        }
+        Ok(out.into_iter().collect())
+    }
+
+    fn mkdir(&mut self, path: &str) -> VfsResult<()> {
+        let p = norm(path);
+        Self::check_name(&p)?;
+        self.upper_mut(&p)?;
+        if self.exists(&p) { return Err(VfsError::AlreadyExists(p)); }
+        self.ensure_upper_dir(parent_of(&p))?;
+        // a whiteout for `p` stays, so a recreated directory doesn't show old lower contents
+        self.upper_mut(&p)?.mkdir(&p)
+    }
+
+    fn remove(&mut self, path: &str) -> VfsResult<()> {
+        let p = norm(path);
+        self.upper_mut(&p)?;
+        if p == "/" { return Err(VfsError::Unsupported("remove /")); }
+        let meta = self.merged_meta(&p)?;
+        if meta.is_dir && !self.list(&p)?.is_empty() { return Err(VfsError::DirectoryNotEmpty(p)); }
+        let in_lower = !self.whited(&p) && self.lower.iter().any(|l| l.exists(&p));
+        if self.upper_has(&p) {
+            let upper = self.upper_mut(&p)?;
+            if meta.is_dir {
+                // only whiteout markers can be left inside
+                for n in upper.list(&p)? { upper.remove(&format!("{}/{}", p.trim_end_matches('/'), n))?; }
+            }
+            upper.remove(&p)?;
+        }
+        if in_lower {
+            self.ensure_upper_dir(parent_of(&p))?;
+            self.upper_mut(&p)?.write(&whiteout_of(&p), &[])?;
+        }
+        Ok(())
+    }
+
+    fn rename(&mut self, from: &str, to: &str) -> VfsResult<()> {
+        let (from, to) = (norm(from), norm(to));
+        if from == to { return self.metadata(&from).map(|_| ()); }
+        Self::check_name(&to)?;
+        let meta = self.merged_meta(&from)?;
+        if !meta.is_dir {
+            // copy up, then delete (leaves a whiteout if the source came from a lower layer)
+            let data = self.read(&from)?;
+            self.write(&to, &data)?;
+            return self.remove(&from);
+        }
+        if from == "/" || to.starts_with(&format!("{}/", from)) { return Err(VfsError::Unsupported("rename a directory into itself")); }
+        self.mkdir(&to)?;
+        for n in self.list(&from)? {
+            self.rename(&format!("{}/{}", from, n), &format!("{}/{}", to, n))?;
+        }
+        self.remove(&from)
+    }
+
+    fn watch(&mut self, enabled: bool) -> bool {
+        let mut any = false;
+        if let Some(u) = &mut self.upper { any |= u.watch(enabled); }
+        for l in &mut self.lower { any |= l.watch(enabled); }
+        any
+    }
+
+    /// Layer changes as seen through the merged view: changes shadowed by a higher layer are
+    /// dropped, and whiteout markers are reported as the entry they hide.
+    fn poll_changes(&mut self, out: &mut Vec<VfsEvent>) {
+        // (level, event); level 0 is the upper layer, then the read-only layers top first
+        let mut raw = Vec::new();
+        let mut buf = Vec::new();
+        if let Some(u) = &mut self.upper { u.poll_changes(&mut buf); }
+        raw.extend(buf.drain(..).map(|e| (0, e)));
+        for (i, l) in self.lower.iter_mut().enumerate() {
+            l.poll_changes(&mut buf);
+            raw.extend(buf.drain(..).map(|e| (i + 1, e)));
+        }
+        let mut seen = BTreeSet::new();
+        for (level, ev) in raw {
+            let p = norm(&ev.path);
+            let name = &p[p.rfind('/').map(|i| i + 1).unwrap_or(0)..];
+            let (kind, path) = if let Some(hidden) = name.strip_prefix(WHITEOUT_PREFIX) {
+                if level > 0 { continue; }
+                let target = join(parent_of(&p), hidden);
+                if self.upper_has(&target) { continue; }
+                (if self.exists(&target) { VfsEventKind::Created } else { VfsEventKind::Removed }, target)
+            } else {
+                let shadowed = level > 0 && (self.upper_has(&p) || self.whited(&p) || self.lower[..level - 1].iter().any(|l| l.exists(&p)));
+                if shadowed { continue; }
+                // a layer below still provides it, or provided it before
+                let below = !self.whited(&p) && self.lower[level..].iter().any(|l| l.exists(&p));
+                let kind = match ev.kind {
+                    VfsEventKind::Removed if self.exists(&p) => VfsEventKind::Modified,
+                    VfsEventKind::Created if below => VfsEventKind::Modified,
+                    k => k,
+                };
+                (kind, p)
+            };
+            if seen.insert((kind, path.clone())) { out.push(VfsEvent::new(kind, path)); }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::*;
+    use crate::fs::MemBackend;
+
+    fn layer(files: &[(&str, &[u8])]) -> Box<dyn Backend> {
+        let mut b = MemBackend::new();
+        for (path, data) in files {
+            let mut dir = String::new();
+            for part in parent_of(path).split('/').filter(|s| !s.is_empty()) {
+                dir = join(if dir.is_empty() { "/" } else { &dir }, part);
+                if !b.exists(&dir) { b.mkdir(&dir).unwrap(); }
+            }
+            b.write(path, data).unwrap();
+        }
+        Box::new(b)
+    }
+
+    fn overlay() -> OverlayBackend {
+        OverlayBackend::new()
+            .with_layer(layer(&[("/cfg.txt", b"base"), ("/maps/a.map", b"a"), ("/maps/deep/b.map", b"b")]))
+            .with_layer(layer(&[("/cfg.txt", b"mod")]))
+            .with_writable(layer(&[]))
+    }
+
+    fn upper(o: &OverlayBackend) -> &dyn Backend { o.upper.as_deref().unwrap() }
+
+    #[test]
+    fn reads_fall_through_top_first() {
+        let o = overlay();
+        assert_eq!(o.layer_count(), 3);
+        assert_eq!(o.read("/cfg.txt").unwrap(), b"mod");
+        assert_eq!(o.read("/maps/deep/b.map").unwrap(), b"b");
+        assert_eq!(o.list("/").unwrap(), ["cfg.txt", "maps"]);
+    }
+
+    #[test]
+    fn deleting_a_lower_file_leaves_a_whiteout() {
+        let mut o = overlay();
+        o.remove("/maps/a.map").unwrap();
+        assert!(matches!(o.read("/maps/a.map"), Err(VfsError::NotFound(_))));
+        assert!(!o.exists("/maps/a.map"));
+        assert!(o.metadata("/maps/a.map").is_err());
+        assert_eq!(o.list("/maps").unwrap(), ["deep"]);
+        assert!(upper(&o).exists("/maps/.wh.a.map"));
+        assert!(matches!(o.remove("/maps/a.map"), Err(VfsError::NotFound(_))));
+
+        // a file shadowed in two layers is hidden in both
+        o.remove("/cfg.txt").unwrap();
+        assert!(!o.exists("/cfg.txt"));
+        assert_eq!(o.list("/").unwrap(), ["maps"]);
+    }
+
+    #[test]
+    fn recreating_a_deleted_file_clears_the_whiteout() {
+        let mut o = overlay();
+        o.remove("/maps/a.map").unwrap();
+        o.write("/maps/a.map", b"new").unwrap();
+        assert_eq!(o.read("/maps/a.map").unwrap(), b"new");
+        assert!(!upper(&o).exists("/maps/.wh.a.map"));
+        assert_eq!(o.list("/maps").unwrap(), ["a.map", "deep"]);
+
+        // deleting again falls back to a whiteout, not the lower file
+        o.remove("/maps/a.map").unwrap();
+        assert!(!o.exists("/maps/a.map"));
+        o.create("/maps/a.map").unwrap().write_all(b"streamed").unwrap();
+        assert_eq!(o.read("/maps/a.map").unwrap(), b"streamed");
+        assert!(!upper(&o).exists("/maps/.wh.a.map"));
+    }
+
+    #[test]
+    fn recreated_directories_stay_empty() {
+        let mut o = overlay();
+        assert!(matches!(o.remove("/maps/deep"), Err(VfsError::DirectoryNotEmpty(_))));
+        o.remove("/maps/deep/b.map").unwrap();
+        o.remove("/maps/deep").unwrap();
+        assert!(!o.exists("/maps/deep/b.map"));
+        o.mkdir("/maps/deep").unwrap();
+        assert!(o.list("/maps/deep").unwrap().is_empty());
+
+        // a file replacing a lower directory keeps its contents hidden
+        let mut o = overlay();
+        o.remove("/maps/deep/b.map").unwrap();
+        o.remove("/maps/deep").unwrap();
+        o.write("/maps/deep", b"file").unwrap();
+        assert!(upper(&o).exists("/maps/.wh.deep"));
+        assert!(!o.exists("/maps/deep/b.map"));
+    }
+
+    #[test]
+    fn writing_under_a_lower_directory_copies_it_up() {
+        let mut o = overlay();
+        assert!(!upper(&o).exists("/maps"));
+        o.write("/maps/deep/c.map", b"c").unwrap();
+        assert!(upper(&o).metadata("/maps").unwrap().is_dir);
+        assert!(upper(&o).metadata("/maps/deep").unwrap().is_dir);
+        assert_eq!(upper(&o).list("/maps").unwrap(), ["deep"]);
+        assert_eq!(o.list("/maps/deep").unwrap(), ["b.map", "c.map"]);
+        assert_eq!(o.lower[1].list("/maps/deep").unwrap(), ["b.map"]);
+
+        // editing a lower file copies it up and leaves the lower one alone
+        o.write("/cfg.txt", b"user").unwrap();
+        assert_eq!(o.read("/cfg.txt").unwrap(), b"user");
+        assert_eq!(o.lower[0].read("/cfg.txt").unwrap(), b"mod");
+        assert!(matches!(o.write("/nope/x", b""), Err(VfsError::MissingParent(_))));
+    }
+
+    #[test]
+    fn rename_of_a_lower_file_moves_it() {
+        let mut o = overlay();
+        o.rename("/maps/a.map", "/a.map").unwrap();
+        assert_eq!(o.read("/a.map").unwrap(), b"a");
+        assert!(!o.exists("/maps/a.map"));
+    }
+
+    #[test]
+    fn read_only_without_writable_layer() {
+        let mut o = OverlayBackend::new().with_layer(layer(&[("/f", b"x")]));
+        assert!(matches!(o.write("/f", b"y"), Err(VfsError::ReadOnly(_))));
+        assert!(matches!(o.remove("/f"), Err(VfsError::ReadOnly(_))));
+        assert!(matches!(overlay().write("/.wh.f", b""), Err(VfsError::Unsupported(_))));
+    }
+}
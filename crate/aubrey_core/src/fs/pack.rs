@@ -0,0 +1,425 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Cursor;
+use std::path::Path;
+use std::sync::Arc;
+
+use super::{norm, Backend, Bytes, Metadata, VfsError, VfsReader, VfsResult};
+
+// Layout (little endian):
+//   header: magic "APAK", version u16, flags u16 (0), entry count u32, index offset u64
+//   entry data blobs
+//   index: per entry
+//     path len u16, path (utf-8, normalized), method u8, offset u64, stored len u64, size u64, crc32 u32
+const MAGIC: &[u8; 4] = b"APAK";
+const VERSION: u16 = 1;
+const HEADER_LEN: usize = 20;
+
+/// Per-entry compression in a pack file.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Compression {
+    Store,
+    Deflate,
+}
+
+impl Compression {
+    fn id(self) -> u8 { match self { Compression::Store => 0, Compression::Deflate => 1 } }
+
+    fn from_id(id: u8) -> Option<Self> {
+        match id { 0 => Some(Compression::Store), 1 => Some(Compression::Deflate), _ => None }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Entry {
+    method: Compression,
+    offset: u64,
+    stored: u64,
+    size: u64,
+    crc: u32,
+}
+
+pub(crate) fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for b in data {
+        crc ^= *b as u32;
+        for _ in 0..8 { crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 }; }
+    }
+    !crc
+}
+
+/// Builds a pack file in memory.
+/// ```ignore
+/// let mut w = PackWriter::new();
+/// w.add_dir("assets")?;
+/// std::fs::write("game.pak", w.finish())?;
+/// ```
+#[derive(Default)]
+pub struct PackWriter {
+    // normalized path -> (method, stored bytes, size, crc)
+    entries: BTreeMap<String, (Compression, Vec<u8>, u64, u32)>,
+}
+
+impl PackWriter {
+    pub fn new() -> Self { Self::default() }
+
+    /// Add with deflate, falling back to store when compression doesn't help.
+    pub fn add(&mut self, path: &str, data: &[u8]) { self.add_with(path, data, Compression::Deflate); }
+
+    /// Add (or replace) an entry with the given compression.
+    pub fn add_with(&mut self, path: &str, data: &[u8], method: Compression) {
+        let crc = crc32(data);
+        let (method, stored) = match method {
+            Compression::Deflate => {
+                let packed = miniz_oxide::deflate::compress_to_vec(data, 6);
+                if packed.len() < data.len() { (Compression::Deflate, packed) } else { (Compression::Store, data.to_vec()) }
+            }
+            Compression::Store => (Compression::Store, data.to_vec()),
+        };
+        self.entries.insert(norm(path), (method, stored, data.len() as u64, crc));
+    }
+
+    /// Add every file under host directory `dir` (recursively) at the pack root.
+    /// Returns the number of files added.
+    pub fn add_dir(&mut self, dir: impl AsRef<Path>) -> std::io::Result<usize> {
+        self.add_dir_with(dir, Compression::Deflate)
+    }
+
+    pub fn add_dir_with(&mut self, dir: impl AsRef<Path>, method: Compression) -> std::io::Result<usize> {
+        let mut count = 0;
+        let mut stack = vec![(dir.as_ref().to_path_buf(), String::new())];
+        while let Some((host, vpath)) = stack.pop() {
+            for entry in std::fs::read_dir(&host)? {
+                let entry = entry?;
+                let Ok(name) = entry.file_name().into_string() else { continue };
+                let child = format!("{}/{}", vpath, name);
+                let ty = entry.file_type()?;
+                if ty.is_dir() {
+                    stack.push((entry.path(), child));
+                } else if ty.is_file() {
+                    self.add_with(&child, &std::fs::read(entry.path())?, method);
+                    count += 1;
+                }
+            }
+        }
+        Ok(count)
+    }
+
+    pub fn len(&self) -> usize { self.entries.len() }
+
+    pub fn is_empty(&self) -> bool { self.entries.is_empty() }
+
+    /// Serialize the pack. Entries are stored in path order, so output is deterministic.
+    pub fn finish(self) -> Vec<u8> {
+        let mut out = vec![0u8; HEADER_LEN];
+        let mut index = Vec::new();
+        for (path, (method, stored, size, crc)) in &self.entries {
+            let offset = out.len() as u64;
+            out.extend_from_slice(stored);
+            index.extend_from_slice(&(path.len() as u16).to_le_bytes());
+            index.extend_from_slice(path.as_bytes());
+            index.push(method.id());
+            index.extend_from_slice(&offset.to_le_bytes());
+            index.extend_from_slice(&(stored.len() as u64).to_le_bytes());
+            index.extend_from_slice(&size.to_le_bytes());
+            index.extend_from_slice(&crc.to_le_bytes());
+        }
+        let index_offset = out.len() as u64;
+        out.extend_from_slice(&index);
+        out[0..4].copy_from_slice(MAGIC);
+        out[4..6].copy_from_slice(&VERSION.to_le_bytes());
+        out[8..12].copy_from_slice(&(self.entries.len() as u32).to_le_bytes());
+        out[12..20].copy_from_slice(&index_offset.to_le_bytes());
+        out
+    }
+}
+
+/// Read-only `Backend` over a pack file built by `PackWriter` (or the `aubrey_pack` tool).
+/// Entries are decompressed and checksummed on each `read` (`VfsError::Corrupt` on mismatch).
+pub struct PackBackend {
+    data: Arc<[u8]>,
+    entries: BTreeMap<String, Entry>,
+    dirs: BTreeSet<String>,
+}
+
+struct IndexReader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> IndexReader<'a> {
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        let s = self.buf.get(self.pos..self.pos.checked_add(n)?)?;
+        self.pos += n;
+        Some(s)
+    }
+    fn u8(&mut self) -> Option<u8> { Some(self.take(1)?[0]) }
+    fn u16(&mut self) -> Option<u16> { Some(u16::from_le_bytes(self.take(2)?.try_into().ok()?)) }
+    fn u32(&mut self) -> Option<u32> { Some(u32::from_le_bytes(self.take(4)?.try_into().ok()?)) }
+    fn u64(&mut self) -> Option<u64> { Some(u64::from_le_bytes(self.take(8)?.try_into().ok()?)) }
+}
+
+// Window into the shared pack image (stored entries are streamed without copying).
+struct EntrySlice {
+    data: Arc<[u8]>,
+    start: usize,
+    end: usize,
+}
+
+impl AsRef<[u8]> for EntrySlice {
+    fn as_ref(&self) -> &[u8] { &self.data[self.start..self.end] }
+}
+
+impl PackBackend {
+    fn entry(&self, p: &str) -> VfsResult<&Entry> {
+        self.entries.get(p).ok_or_else(|| if self.dirs.contains(p) { VfsError::IsADirectory(p.to_string()) } else { VfsError::NotFound(p.to_string()) })
+    }
+
+    /// Parse a pack image. `VfsError::Corrupt("/")` if the header or index is malformed or truncated.
+    pub fn from_bytes(data: Vec<u8>) -> VfsResult<Self> {
+        Self::parse(data).ok_or_else(|| VfsError::Corrupt("/".to_string()))
+    }
+
+    fn parse(data: Vec<u8>) -> Option<Self> {
+        let mut c = IndexReader { buf: &data, pos: 0 };
+        if c.take(4)? != MAGIC { return None; }
+        if c.u16()? != VERSION { return None; }
+        let _flags = c.u16()?;
+        let count = c.u32()? as usize;
+        let index_offset = c.u64()? as usize;
+        c.pos = index_offset;
+        let mut entries = BTreeMap::new();
+        let mut dirs = BTreeSet::new();
+        dirs.insert("/".to_string());
+        for _ in 0..count {
+            let len = c.u16()? as usize;
+            let path = std::str::from_utf8(c.take(len)?).ok()?.to_string();
+            let method = Compression::from_id(c.u8()?)?;
+            let e = Entry { method, offset: c.u64()?, stored: c.u64()?, size: c.u64()?, crc: c.u32()? };
+            if e.offset.checked_add(e.stored)? > index_offset as u64 { return None; }
+            let mut p = path.as_str();
+            while let Some(i) = p.rfind('/') {
+                p = &p[..i];
+                if p.is_empty() { break; }
+                dirs.insert(p.to_string());
+            }
+            entries.insert(path, e);
+        }
+        Some(Self { data: Arc::from(data), entries, dirs })
+    }
+
+    /// Load a pack file from the host filesystem. Errors carry the host path;
+    /// `VfsError::Corrupt` if the file is not a valid pack.
+    pub fn open(path: impl AsRef<Path>) -> VfsResult<Self> {
+        let host = path.as_ref().display().to_string();
+        let data = std::fs::read(path).map_err(|e| VfsError::from_io(&host, e))?;
+        Self::parse(data).ok_or(VfsError::Corrupt(host))
+    }
+
+    /// Number of files in the pack.
+    pub fn len(&self) -> usize { self.entries.len() }
+
+    pub fn is_empty(&self) -> bool { self.entries.is_empty() }
+
+    /// All file paths, sorted.
+    pub fn paths(&self) -> impl Iterator<Item = &str> + '_ { self.entries.keys().map(|s| s.as_str()) }
+
+    /// Compression and (stored, uncompressed) sizes of an entry.
+    pub fn entry_info(&self, path: &str) -> Option<(Compression, u64, u64)> {
+        self.entries.get(&norm(path)).map(|e| (e.method, e.stored, e.size))
+    }
+}
+
+impl Backend for PackBackend {
+    fn read(&self, path: &str) -> VfsResult<Bytes> {
+        let p = norm(path);
+        let e = self.entry(&p)?;
+        let raw = &self.data[e.offset as usize..(e.offset + e.stored) as usize];
+        let bytes = match e.method {
+            Compression::Store => raw.to_vec(),
+            Compression::Deflate => match miniz_oxide::inflate::decompress_to_vec_with_limit(raw, e.size as usize) {
+                Ok(v) => v,
+                Err(err) => {
+                    log::warn!("pack entry {}: inflate failed: {:?}", p, err.status);
+                    return Err(VfsError::Corrupt(p));
+                }
+            },
+        };
+        if bytes.len() as u64 != e.size || crc32(&bytes) != e.crc {
+            log::warn!("pack entry {}: checksum mismatch", p);
+            return Err(VfsError::Corrupt(p));
+        }
+        Ok(bytes)
+    }
+
+    /// Stored entries stream straight from the pack image (not checksummed);
+    /// deflated ones are decompressed up front.
+    fn open(&self, path: &str) -> VfsResult<VfsReader> {
+        let p = norm(path);
+        let e = self.entry(&p)?;
+        if e.method == Compression::Store {
+            let (start, end) = (e.offset as usize, (e.offset + e.stored) as usize);
+            return Ok(Box::new(Cursor::new(EntrySlice { data: self.data.clone(), start, end })));
+        }
+        Ok(Box::new(Cursor::new(self.read(&p)?)))
+    }
+
+    fn metadata(&self, path: &str) -> VfsResult<Metadata> {
+        let p = norm(path);
+        if let Some(e) = self.entries.get(&p) { return Ok(Metadata { is_dir: false, size: e.size, modified: None }); }
+        if self.dirs.contains(&p) { return Ok(Metadata { is_dir: true, size: 0, modified: None }); }
+        Err(VfsError::NotFound(p))
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        let p = norm(path);
+        self.entries.contains_key(&p) || self.dirs.contains(&p)
+    }
+
+    fn list(&self, path: &str) -> VfsResult<Vec<String>> {
+        let base = norm(path);
+        if !self.dirs.contains(&base) {
+            return Err(if self.entries.contains_key(&base) { VfsError::NotADirectory(base) } else { VfsError::NotFound(base) });
+        }
+        let prefix = if base == "/" { "/".to_string() } else { format!("{}/", base) };
+        let mut out: Vec<String> = self
+            .entries
+            .keys()
+            .chain(self.dirs.iter())
+            .filter_map(|k| k.strip_prefix(prefix.as_str()))
+            .filter(|rest| !rest.is_empty())
+            .map(|rest| rest.split('/').next().unwrap_or(rest).to_string())
+            .collect();
+        out.sort();
+        out.dedup();
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+    use crate::fs::{MemBackend, Vfs};
+
+    const TEXT: &[u8] = b"hello hello hello hello hello hello hello hello";
+
+    fn pack() -> Vec<u8> {
+        let mut w = PackWriter::new();
+        w.add("a/b/deep.txt", TEXT);
+        w.add_with("a/raw.bin", &[1, 2, 3, 4], Compression::Store);
+        w.add("top.txt", b"x");
+        assert_eq!(w.len(), 3);
+        w.finish()
+    }
+
+    fn mounted(data: Vec<u8>) -> Vfs {
+        let mut vfs = Vfs::new();
+        vfs.mount("/data", Box::new(PackBackend::from_bytes(data).unwrap()));
+        vfs
+    }
+
+    #[test]
+    fn round_trip_through_vfs() {
+        let backend = PackBackend::from_bytes(pack()).unwrap();
+        assert_eq!(backend.paths().collect::<Vec<_>>(), ["/a/b/deep.txt", "/a/raw.bin", "/top.txt"]);
+        assert_eq!(backend.entry_info("a/b/deep.txt").map(|i| i.0), Some(Compression::Deflate));
+        assert_eq!(backend.entry_info("/a/raw.bin"), Some((Compression::Store, 4, 4)));
+        // deflate that doesn't shrink falls back to store
+        assert_eq!(backend.entry_info("/top.txt").map(|i| i.0), Some(Compression::Store));
+
+        let vfs = mounted(pack());
+        assert_eq!(vfs.read("/data/a/b/deep.txt").unwrap(), TEXT);
+        assert_eq!(&vfs.read_shared("/data/a/raw.bin").unwrap()[..], &[1, 2, 3, 4]);
+        assert_eq!(vfs.read("/data/top.txt").unwrap(), b"x");
+        for path in ["/data/a/b/deep.txt", "/data/a/raw.bin"] {
+            let mut out = Vec::new();
+            vfs.open(path).unwrap().read_to_end(&mut out).unwrap();
+            assert_eq!(out, vfs.read(path).unwrap());
+        }
+
+        assert_eq!(vfs.list("/data").unwrap(), ["a", "top.txt"]);
+        assert_eq!(vfs.list("/data/a").unwrap(), ["b", "raw.bin"]);
+        assert_eq!(vfs.list("/data/a/b").unwrap(), ["deep.txt"]);
+        assert_eq!(vfs.list_recursive("/data").unwrap(), ["/data/a", "/data/a/b", "/data/a/b/deep.txt", "/data/a/raw.bin", "/data/top.txt"]);
+        assert!(vfs.exists("/data/a/b") && vfs.exists("/data/top.txt") && !vfs.exists("/data/nope"));
+
+        let m = vfs.metadata("/data/a/b/deep.txt").unwrap();
+        assert!(m.is_file() && m.size == TEXT.len() as u64);
+        assert!(vfs.metadata("/data/a").unwrap().is_dir);
+        assert!(matches!(vfs.metadata("/data/nope"), Err(VfsError::NotFound(p)) if p == "/data/nope"));
+        assert!(matches!(vfs.read("/data/a"), Err(VfsError::IsADirectory(p)) if p == "/data/a"));
+        assert!(matches!(vfs.list("/data/top.txt"), Err(VfsError::NotADirectory(_))));
+    }
+
+    #[test]
+    fn open_handles_seek_within_the_entry() {
+        let vfs = mounted(pack());
+        // stored entries are windows into the pack, deflated ones are buffered
+        crate::fs::tests::assert_seeks(vfs.open("/data/a/b/deep.txt").unwrap(), TEXT);
+        let mut w = PackWriter::new();
+        w.add("first", b"ignored");
+        w.add_with("stored.bin", b"0123456789", Compression::Store);
+        w.add("z", b"ignored too");
+        let vfs = mounted(w.finish());
+        crate::fs::tests::assert_seeks(vfs.open("/data/stored.bin").unwrap(), b"0123456789");
+        assert!(matches!(vfs.open("/data/nope"), Err(VfsError::NotFound(p)) if p == "/data/nope"));
+        assert!(matches!(vfs.open("/data"), Err(VfsError::IsADirectory(_))));
+    }
+
+    #[test]
+    fn output_is_deterministic() {
+        let mut w = PackWriter::new();
+        w.add("top.txt", b"x");
+        w.add_with("a/raw.bin", &[1, 2, 3, 4], Compression::Store);
+        w.add("a/b/deep.txt", TEXT);
+        assert_eq!(w.finish(), pack());
+    }
+
+    #[test]
+    fn truncated_archive_is_corrupt() {
+        let data = pack();
+        for len in 0..data.len() {
+            assert!(matches!(PackBackend::from_bytes(data[..len].to_vec()), Err(VfsError::Corrupt(_))), "len {}", len);
+        }
+        let mut bad_magic = data.clone();
+        bad_magic[0] = b'X';
+        assert!(matches!(PackBackend::from_bytes(bad_magic), Err(VfsError::Corrupt(_))));
+        assert!(matches!(PackBackend::open("/nonexistent/dir/game.pak"), Err(VfsError::NotFound(p)) if p == "/nonexistent/dir/game.pak"));
+    }
+
+    #[test]
+    fn flipped_payload_byte_is_corrupt() {
+        let backend = PackBackend::from_bytes(pack()).unwrap();
+        for path in ["/a/b/deep.txt", "/a/raw.bin"] {
+            let offset = backend.entries[path].offset as usize;
+            let mut data = pack();
+            data[offset] ^= 0x40;
+            let vfs = mounted(data);
+            let full = format!("/data{}", path);
+            assert!(matches!(vfs.read(&full), Err(VfsError::Corrupt(p)) if p == full));
+            assert!(matches!(vfs.read_shared(&full), Err(VfsError::Corrupt(_))));
+            // the other entries are unaffected
+            assert_eq!(vfs.read("/data/top.txt").unwrap(), b"x");
+        }
+    }
+
+    #[test]
+    fn writes_are_read_only() {
+        let mut vfs = mounted(pack());
+        vfs.mount("/mem", Box::new(MemBackend::new()));
+        vfs.write("/mem/f", b"1").unwrap();
+        let ro = |r: VfsResult<()>| matches!(r, Err(VfsError::ReadOnly(p)) if p.starts_with("/data"));
+        assert!(ro(vfs.write("/data/top.txt", b"y")));
+        assert!(ro(vfs.write("/data/new.txt", b"y")));
+        assert!(ro(vfs.create("/data/new.txt").map(|_| ())));
+        assert!(ro(vfs.mkdir("/data/dir")));
+        assert!(ro(vfs.create_dir_all("/data/x/y")));
+        assert!(ro(vfs.remove("/data/top.txt")));
+        assert!(ro(vfs.remove_all("/data/a")));
+        assert!(ro(vfs.rename("/data/top.txt", "/data/moved.txt")));
+        assert!(ro(vfs.copy("/mem/f", "/data/f")));
+        assert_eq!(vfs.read("/data/top.txt").unwrap(), b"x");
+        assert_eq!(vfs.list_recursive("/data").unwrap().len(), 5);
+    }
+}
@@ -0,0 +1,434 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use super::watch::{diff_scans, EntryStamp};
+use super::{norm, Backend, Bytes, CommitWrite, Metadata, VfsError, VfsEvent, VfsReader, VfsResult, VfsWriter};
+
+// Streams into a temp file next to the target and renames it into place on commit or drop.
+struct AtomicWriter {
+    file: Option<io::BufWriter<fs::File>>,
+    tmp: PathBuf,
+    target: PathBuf,
+    // VFS path, for errors
+    path: String,
+    failed: bool,
+}
+
+impl AtomicWriter {
+    // Flush, sync and rename into place once; the temp file is removed on failure.
+    fn finish(&mut self) -> io::Result<()> {
+        let Some(f) = self.file.take() else { return Ok(()) };
+        let res = if self.failed {
+            Err(io::Error::other("write failed"))
+        } else {
+            f.into_inner().map_err(|e| e.into_error()).and_then(|f| f.sync_all()).and_then(|_| fs::rename(&self.tmp, &self.target))
+        };
+        if res.is_err() { let _ = fs::remove_file(&self.tmp); }
+        res
+    }
+}
+
+impl Write for AtomicWriter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let res = self.file.as_mut().map(|f| f.write(data)).unwrap_or(Ok(0));
+        if res.is_err() { self.failed = true; }
+        res
+    }
+    fn flush(&mut self) -> io::Result<()> { self.file.as_mut().map(|f| f.flush()).unwrap_or(Ok(())) }
+}
+
+impl CommitWrite for AtomicWriter {
+    fn commit(mut self: Box<Self>) -> VfsResult<()> { self.finish().map_err(|e| VfsError::from_io(&self.path, e)) }
+}
+
+impl Drop for AtomicWriter {
+    fn drop(&mut self) {
+        if let Err(e) = self.finish() { log::warn!("vfs write {}: {}", self.target.display(), e); }
+    }
+}
+
+/// Maps a host directory into the VFS. Paths can't leave the root: `..` is dropped by
+/// normalization and symlinks pointing outside the root are refused.
+/// ```ignore
+/// vfs.mount("/project", Box::new(StdFsBackend::new("./my_game")?));
+/// vfs.mount("/assets", Box::new(StdFsBackend::new("./assets")?.read_only()));
+/// ```
+/// Change notifications (`Vfs::watch`) work by rescanning the tree every `poll_interval`.
+pub struct StdFsBackend {
+    root: PathBuf,
+    read_only: bool,
+    poll_interval: Duration,
+    // entry names skipped at any depth when scanning for changes
+    watch_ignore: Vec<String>,
+    // last scan and when it was taken; Some while watched
+    watch: Option<(BTreeMap<String, EntryStamp>, Instant)>,
+}
+
+static TMP_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Per-user writable directory for `app` (not created): `%APPDATA%\<app>` on Windows,
+/// `~/Library/Application Support/<app>` on macOS, `$XDG_DATA_HOME/<app>` or `~/.local/share/<app>` elsewhere.
+/// Meant to back the `user://` source.
+pub fn user_data_dir(app: &str) -> Option<PathBuf> {
+    let env = |k: &str| std::env::var_os(k).filter(|v| !v.is_empty()).map(PathBuf::from);
+    let base = if cfg!(windows) {
+        env("APPDATA")?
+    } else if cfg!(target_os = "macos") {
+        env("HOME")?.join("Library/Application Support")
+    } else {
+        env("XDG_DATA_HOME").or_else(|| env("HOME").map(|h| h.join(".local/share")))?
+    };
+    Some(base.join(app))
+}
+
+impl StdFsBackend {
+    /// `root` must be an existing directory.
+    pub fn new(root: impl AsRef<Path>) -> io::Result<Self> {
+        let root = fs::canonicalize(root)?;
+        if !root.is_dir() { return Err(io::Error::new(io::ErrorKind::NotADirectory, "vfs root is not a directory")); }
+        Ok(Self { root, read_only: false, poll_interval: Duration::from_millis(500), watch_ignore: Vec::new(), watch: None })
+    }
+
+    /// Refuse writes, mkdir, remove and rename.
+    pub fn read_only(mut self) -> Self { self.read_only = true; self }
+
+    pub fn is_read_only(&self) -> bool { self.read_only }
+
+    pub fn root(&self) -> &Path { &self.root }
+
+    /// Minimum time between rescans while watched (default 500 ms).
+    pub fn poll_interval(mut self, interval: Duration) -> Self { self.poll_interval = interval; self }
+
+    /// Don't scan entries with this name (e.g. `"target"`, `".git"`) when watching.
+    pub fn watch_ignore(mut self, name: &str) -> Self { self.watch_ignore.push(name.to_string()); self }
+
+    // Every entry under the root by VFS path. Our own temp files and escaping symlinks are skipped.
+    fn scan(&self) -> BTreeMap<String, EntryStamp> {
+        let mut out = BTreeMap::new();
+        let mut stack = vec![(self.root.clone(), String::new())];
+        while let Some((dir, vdir)) = stack.pop() {
+            let Ok(entries) = fs::read_dir(&dir) else { continue };
+            for entry in entries.filter_map(|e| e.ok()) {
+                let Ok(name) = entry.file_name().into_string() else { continue };
+                if self.watch_ignore.contains(&name) || (name.starts_with('.') && name.ends_with(".tmp")) { continue; }
+                let path = entry.path();
+                if entry.file_type().is_ok_and(|t| t.is_symlink()) && !fs::canonicalize(&path).is_ok_and(|p| p.starts_with(&self.root)) { continue; }
+                let Ok(m) = fs::metadata(&path) else { continue };
+                let vpath = format!("{}/{}", vdir, name);
+                out.insert(vpath.clone(), EntryStamp { is_dir: m.is_dir(), size: if m.is_dir() { 0 } else { m.len() }, modified: m.modified().ok() });
+                // symlinked directories aren't followed, so cycles can't occur
+                if m.is_dir() && !entry.file_type().is_ok_and(|t| t.is_symlink()) { stack.push((path, vpath)); }
+            }
+        }
+        out
+    }
+
+    fn check_writable(&self, path: &str) -> VfsResult<()> {
+        if self.read_only { Err(VfsError::ReadOnly(norm(path))) } else { Ok(()) }
+    }
+
+    // VFS path -> host path under root (no existence check)
+    fn host_path(&self, path: &str) -> PathBuf {
+        let mut out = self.root.clone();
+        for part in norm(path).split('/').filter(|p| !p.is_empty()) { out.push(part); }
+        out
+    }
+
+    // Host path for an existing entry; refuses entries resolving outside the root.
+    fn resolve(&self, path: &str) -> VfsResult<PathBuf> {
+        let real = fs::canonicalize(self.host_path(path)).map_err(|e| VfsError::from_io(&norm(path), e))?;
+        if real.starts_with(&self.root) { Ok(real) } else { Err(VfsError::OutsideRoot(norm(path))) }
+    }
+
+    // Host path for an entry that may not exist yet; its parent must exist inside the root.
+    fn resolve_new(&self, path: &str) -> VfsResult<PathBuf> {
+        let p = self.host_path(path);
+        let (Some(name), Some(parent)) = (p.file_name(), p.parent()) else { return Err(VfsError::Unsupported("write to /")) };
+        let parent = fs::canonicalize(parent).map_err(|_| VfsError::MissingParent(norm(path)))?;
+        if !parent.starts_with(&self.root) { return Err(VfsError::OutsideRoot(norm(path))); }
+        if !parent.is_dir() { return Err(VfsError::NotADirectory(super::parent_of(&norm(path)).to_string())); }
+        let target = parent.join(name);
+        // an existing symlink at the target must not point outside either
+        if fs::symlink_metadata(&target).is_ok() {
+            match self.resolve(path) {
+                Err(VfsError::NotFound(_)) | Ok(_) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(target)
+    }
+
+    fn tmp_path(target: &Path) -> PathBuf {
+        let name = target.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        let n = TMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+        target.with_file_name(format!(".{}.{}-{}.tmp", name, std::process::id(), n))
+    }
+
+    fn write_atomic(target: &Path, data: &[u8]) -> io::Result<()> {
+        let tmp = Self::tmp_path(target);
+        let res = (|| {
+            let mut f = fs::File::create(&tmp)?;
+            f.write_all(data)?;
+            f.sync_all()?;
+            fs::rename(&tmp, target)
+        })();
+        if res.is_err() { let _ = fs::remove_file(&tmp); }
+        res
+    }
+}
+
+impl Backend for StdFsBackend {
+    fn read(&self, path: &str) -> VfsResult<Bytes> {
+        let p = self.resolve(path)?;
+        if p.is_dir() { return Err(VfsError::IsADirectory(norm(path))); }
+        fs::read(&p).map_err(|e| VfsError::from_io(&norm(path), e))
+    }
+
+    fn open(&self, path: &str) -> VfsResult<VfsReader> {
+        let p = self.resolve(path)?;
+        if p.is_dir() { return Err(VfsError::IsADirectory(norm(path))); }
+        let f = fs::File::open(&p).map_err(|e| VfsError::from_io(&norm(path), e))?;
+        Ok(Box::new(io::BufReader::new(f)))
+    }
+
+    fn write(&mut self, path: &str, data: &[u8]) -> VfsResult<()> {
+        self.check_writable(path)?;
+        let target = self.resolve_new(path)?;
+        if target.is_dir() { return Err(VfsError::IsADirectory(norm(path))); }
+        Self::write_atomic(&target, data).map_err(|e| VfsError::from_io(&norm(path), e))
+    }
+
+    fn create(&mut self, path: &str) -> VfsResult<VfsWriter<'_>> {
+        self.check_writable(path)?;
+        let target = self.resolve_new(path)?;
+        if target.is_dir() { return Err(VfsError::IsADirectory(norm(path))); }
+        let tmp = Self::tmp_path(&target);
+        let file = fs::File::create(&tmp).map_err(|e| VfsError::from_io(&norm(path), e))?;
+        Ok(Box::new(AtomicWriter { file: Some(io::BufWriter::new(file)), tmp, target, path: norm(path), failed: false }))
+    }
+
+    fn metadata(&self, path: &str) -> VfsResult<Metadata> {
+        let p = self.resolve(path)?;
+        let m = fs::metadata(&p).map_err(|e| VfsError::from_io(&norm(path), e))?;
+        Ok(Metadata { is_dir: m.is_dir(), size: if m.is_dir() { 0 } else { m.len() }, modified: m.modified().ok() })
+    }
+
+    fn exists(&self, path: &str) -> bool { self.resolve(path).is_ok() }
+
+    fn list(&self, path: &str) -> VfsResult<Vec<String>> {
+        let dir = self.resolve(path)?;
+        if !dir.is_dir() { return Err(VfsError::NotADirectory(norm(path))); }
+        let entries = fs::read_dir(&dir).map_err(|e| VfsError::from_io(&norm(path), e))?;
+        let mut out: Vec<String> = entries
+            .filter_map(|e| e.ok())
+            // hide symlinks that lead outside the root
+            .filter(|e| fs::canonicalize(e.path()).is_ok_and(|p| p.starts_with(&self.root)))
+            .filter_map(|e| e.file_name().into_string().ok())
+            .collect();
+        out.sort();
+        Ok(out)
+    }
+
+    fn mkdir(&mut self, path: &str) -> VfsResult<()> {
+        self.check_writable(path)?;
+        let target = self.resolve_new(path)?;
+        fs::create_dir(&target).map_err(|e| VfsError::from_io(&norm(path), e))
+    }
+
+    fn remove(&mut self, path: &str) -> VfsResult<()> {
+        self.check_writable(path)?;
+        // remove the link itself, not what it points to
+        let target = self.resolve_new(path)?;
+        let meta = fs::symlink_metadata(&target).map_err(|e| VfsError::from_io(&norm(path), e))?;
+        let res = if meta.is_dir() { fs::remove_dir(&target) } else { fs::remove_file(&target) };
+        res.map_err(|e| VfsError::from_io(&norm(path), e))
+    }
+
+    fn rename(&mut self, from: &str, to: &str) -> VfsResult<()> {
+        self.check_writable(from)?;
+        let src = self.resolve_new(from)?;
+        if fs::symlink_metadata(&src).is_err() { return Err(VfsError::NotFound(norm(from))); }
+        let dst = self.resolve_new(to)?;
+        if dst.is_dir() { return Err(VfsError::AlreadyExists(norm(to))); }
+        fs::rename(&src, &dst).map_err(|e| VfsError::from_io(&norm(from), e))
+    }
+
+    fn watch(&mut self, enabled: bool) -> bool {
+        self.watch = if enabled { Some((self.scan(), Instant::now())) } else { None };
+        true
+    }
+
+    fn poll_changes(&mut self, out: &mut Vec<VfsEvent>) {
+        let Some((_, last)) = &self.watch else { return };
+        if last.elapsed() < self.poll_interval { return; }
+        let scan = self.scan();
+        if let Some((prev, last)) = &mut self.watch {
+            diff_scans(prev, &scan, out);
+            *prev = scan;
+            *last = Instant::now();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::Vfs;
+
+    // Fresh `<tmp>/<name>/root` with a sibling `outside/secret.txt`; returns (base, root).
+    fn sandbox(name: &str) -> (PathBuf, PathBuf) {
+        let base = std::env::temp_dir().join(format!("aubrey_std_fs_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&base);
+        fs::create_dir_all(base.join("root/dir")).unwrap();
+        fs::create_dir_all(base.join("outside")).unwrap();
+        fs::write(base.join("outside/secret.txt"), "secret").unwrap();
+        fs::write(base.join("root/a.txt"), "old").unwrap();
+        (base.clone(), base.join("root"))
+    }
+
+    fn temp_files(dir: &Path) -> Vec<String> {
+        fs::read_dir(dir).unwrap().filter_map(|e| e.ok()?.file_name().into_string().ok()).filter(|n| n.ends_with(".tmp")).collect()
+    }
+
+    #[test]
+    fn dot_dot_stays_inside_the_root() {
+        let (base, root) = sandbox("dotdot");
+        let mut b = StdFsBackend::new(&root).unwrap();
+        assert!(matches!(b.read("/../outside/secret.txt"), Err(VfsError::NotFound(_))));
+        assert_eq!(b.read("/../a.txt").unwrap(), b"old");
+        // dropped rather than resolved, so this is /dir/a.txt
+        assert!(matches!(b.read("/dir/../a.txt"), Err(VfsError::NotFound(p)) if p == "/dir/a.txt"));
+        b.write("/../escape.txt", b"x").unwrap();
+        assert!(root.join("escape.txt").exists());
+        assert!(!base.join("escape.txt").exists());
+        b.mkdir("/../../made").unwrap();
+        assert!(root.join("made").is_dir());
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_out_of_the_root_are_refused() {
+        use std::os::unix::fs::symlink;
+        let (base, root) = sandbox("symlink");
+        symlink(base.join("outside/secret.txt"), root.join("file_link")).unwrap();
+        symlink(base.join("outside"), root.join("dir_link")).unwrap();
+        symlink(root.join("a.txt"), root.join("inner_link")).unwrap();
+        let mut b = StdFsBackend::new(&root).unwrap();
+
+        let outside = |r: VfsResult<()>| matches!(r, Err(VfsError::OutsideRoot(_)));
+        assert!(outside(b.read("/file_link").map(|_| ())));
+        assert!(outside(b.open("/file_link").map(|_| ())));
+        assert!(outside(b.read("/dir_link/secret.txt").map(|_| ())));
+        assert!(outside(b.list("/dir_link").map(|_| ())));
+        assert!(outside(b.metadata("/file_link").map(|_| ())));
+        assert!(!b.exists("/file_link"));
+        assert!(outside(b.write("/file_link", b"pwned")));
+        assert!(outside(b.create("/file_link").map(|_| ())));
+        assert!(outside(b.write("/dir_link/new.txt", b"pwned")));
+        assert!(outside(b.create("/dir_link/new.txt").map(|_| ())));
+        assert!(outside(b.mkdir("/dir_link/sub")));
+        assert_eq!(fs::read(base.join("outside/secret.txt")).unwrap(), b"secret");
+        assert!(!base.join("outside/new.txt").exists());
+        // escaping links are hidden from listings; links within the root work
+        assert_eq!(b.list("/").unwrap(), ["a.txt", "dir", "inner_link"]);
+        assert_eq!(b.read("/inner_link").unwrap(), b"old");
+        assert!(outside(b.remove("/file_link")));
+        assert!(outside(b.rename("/file_link", "/moved")));
+        assert!(base.join("outside/secret.txt").exists());
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn read_only_refuses_every_write() {
+        let (base, root) = sandbox("read_only");
+        let mut b = StdFsBackend::new(&root).unwrap().read_only();
+        let ro = |r: VfsResult<()>| matches!(r, Err(VfsError::ReadOnly(_)));
+        assert!(ro(b.write("/a.txt", b"new")));
+        assert!(ro(b.write("/new.txt", b"new")));
+        assert!(ro(b.create("/a.txt").map(|_| ())));
+        assert!(ro(b.mkdir("/sub")));
+        assert!(ro(b.remove("/a.txt")));
+        assert!(ro(b.rename("/a.txt", "/b.txt")));
+        assert_eq!(b.read("/a.txt").unwrap(), b"old");
+        assert_eq!(b.list("/").unwrap(), ["a.txt", "dir"]);
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn streamed_writes_replace_the_file_on_commit() {
+        let (base, root) = sandbox("commit");
+        let mut vfs = Vfs::new();
+        vfs.mount("/", Box::new(StdFsBackend::new(&root).unwrap()));
+        let mut w = vfs.create("/a.txt").unwrap();
+        w.write_all(b"new ").unwrap();
+        w.write_all(b"contents").unwrap();
+        assert_eq!(fs::read(root.join("a.txt")).unwrap(), b"old");
+        w.commit().unwrap();
+        assert_eq!(vfs.read("/a.txt").unwrap(), b"new contents");
+        assert!(temp_files(&root).is_empty());
+
+        // dropping commits too
+        vfs.create("/dir/b.txt").unwrap().write_all(b"b").unwrap();
+        assert_eq!(vfs.read("/dir/b.txt").unwrap(), b"b");
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn streams_host_files() {
+        let (base, root) = sandbox("stream");
+        let mut vfs = Vfs::new();
+        vfs.mount("/", Box::new(StdFsBackend::new(&root).unwrap()));
+        // bigger than the read buffer, so seeks leave it
+        let big: Vec<u8> = (0..20_000u32).map(|i| (i % 251) as u8).collect();
+        let mut w = vfs.create("/dir/big.bin").unwrap();
+        for chunk in big.chunks(3000) { w.write_all(chunk).unwrap(); }
+        w.commit().unwrap();
+        crate::fs::tests::assert_seeks(vfs.open("/dir/big.bin").unwrap(), &big);
+        assert_eq!(&vfs.read_shared("/dir/big.bin").unwrap()[..], &big[..]);
+
+        assert!(matches!(vfs.open("/dir"), Err(VfsError::IsADirectory(p)) if p == "/dir"));
+        assert!(matches!(vfs.open("/nope"), Err(VfsError::NotFound(p)) if p == "/nope"));
+        assert!(matches!(vfs.read_shared("/dir"), Err(VfsError::IsADirectory(_))));
+        assert!(matches!(vfs.create("/missing/c.txt").map(drop), Err(VfsError::MissingParent(p)) if p == "/missing/c.txt"));
+        assert!(matches!(vfs.create("/dir").map(drop), Err(VfsError::IsADirectory(_))));
+        let _ = fs::remove_dir_all(&base);
+    }
+
+    #[test]
+    fn failed_writes_keep_the_old_file() {
+        let (base, root) = sandbox("failed");
+        let mut b = StdFsBackend::new(&root).unwrap();
+
+        // the temp file vanishes before the rename
+        let mut w = b.create("/a.txt").unwrap();
+        w.write_all(b"new").unwrap();
+        for name in temp_files(&root) { fs::remove_file(root.join(name)).unwrap(); }
+        assert!(matches!(w.commit(), Err(VfsError::NotFound(p)) if p == "/a.txt"));
+        assert_eq!(fs::read(root.join("a.txt")).unwrap(), b"old");
+
+        // a write error poisons the writer
+        let target = root.join("a.txt");
+        let tmp = StdFsBackend::tmp_path(&target);
+        let file = io::BufWriter::new(fs::File::create(&tmp).unwrap());
+        let mut w = Box::new(AtomicWriter { file: Some(file), tmp, target, path: "/a.txt".into(), failed: false });
+        w.write_all(b"partial").unwrap();
+        w.failed = true;
+        assert!(matches!(w.commit(), Err(VfsError::Io { .. })));
+        assert_eq!(fs::read(root.join("a.txt")).unwrap(), b"old");
+        assert!(temp_files(&root).is_empty());
+
+        // a target that became a directory can't be replaced either
+        let mut w = b.create("/dir/c.txt").unwrap();
+        w.write_all(b"c").unwrap();
+        fs::create_dir(root.join("dir/c.txt/")).unwrap();
+        fs::write(root.join("dir/c.txt/keep"), "").unwrap();
+        assert!(w.commit().is_err());
+        assert!(temp_files(&root.join("dir")).is_empty());
+        let _ = fs::remove_dir_all(&base);
+    }
+}
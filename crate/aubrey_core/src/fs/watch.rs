@@ -0,0 +1,160 @@
+use std::collections::BTreeMap;
+use std::time::SystemTime;
+
+use crate::app::App;
+use crate::ecs::{Ecs, Events, Stage};
+
+use super::Vfs;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum VfsEventKind {
+    Created,
+    Modified,
+    Removed,
+}
+
+/// A change under a watched mount. `path` is a full VFS path.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VfsEvent {
+    pub kind: VfsEventKind,
+    pub path: String,
+}
+
+impl VfsEvent {
+    pub fn new(kind: VfsEventKind, path: impl Into<String>) -> Self { Self { kind, path: path.into() } }
+}
+
+// What a polling backend remembers about an entry between scans.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub(crate) struct EntryStamp {
+    pub is_dir: bool,
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+}
+
+/// Difference between two scans as events, in path order.
+pub(crate) fn diff_scans(old: &BTreeMap<String, EntryStamp>, new: &BTreeMap<String, EntryStamp>, out: &mut Vec<VfsEvent>) {
+    for (path, stamp) in new {
+        match old.get(path) {
+            None => out.push(VfsEvent::new(VfsEventKind::Created, path.clone())),
+            // a directory's mtime changes with its contents, which are reported on their own
+            Some(prev) if prev.is_dir && stamp.is_dir => {}
+            Some(prev) if prev.is_dir != stamp.is_dir => {
+                out.push(VfsEvent::new(VfsEventKind::Removed, path.clone()));
+                out.push(VfsEvent::new(VfsEventKind::Created, path.clone()));
+            }
+            Some(prev) if prev != stamp => out.push(VfsEvent::new(VfsEventKind::Modified, path.clone())),
+            Some(_) => {}
+        }
+    }
+    for path in old.keys() {
+        if !new.contains_key(path) { out.push(VfsEvent::new(VfsEventKind::Removed, path.clone())); }
+    }
+}
+
+/// Add `Events<VfsEvent>` and a `Stage::First` system that moves `Vfs::poll_changes` into it,
+/// so changes are readable from `PreUpdate` on in the same frame.
+/// Only mounts enabled with `Vfs::watch` report anything.
+pub fn register(app: &mut App) {
+    app.add_event::<VfsEvent>();
+    // right after the event buffers are swapped
+    app.add_systems_ordered(Stage::First, i32::MIN + 1, poll_vfs);
+}
+
+fn poll_vfs(ecs: &mut Ecs) {
+    let Some(vfs) = ecs.get_resource_mut::<Vfs>() else { return };
+    let changes = vfs.poll_changes();
+    if changes.is_empty() { return; }
+    if let Some(events) = ecs.get_resource_mut::<Events<VfsEvent>>() { events.extend(changes); }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use crate::ecs::EventCursor;
+    use crate::fs::{MemBackend, StdFsBackend};
+
+    fn file(size: u64, secs: u64) -> EntryStamp { EntryStamp { is_dir: false, size, modified: Some(UNIX_EPOCH + Duration::from_secs(secs)) } }
+
+    fn dir(secs: u64) -> EntryStamp { EntryStamp { is_dir: true, size: 0, modified: Some(UNIX_EPOCH + Duration::from_secs(secs)) } }
+
+    fn diff(old: &[(&str, EntryStamp)], new: &[(&str, EntryStamp)]) -> Vec<(VfsEventKind, String)> {
+        let scan = |entries: &[(&str, EntryStamp)]| entries.iter().map(|(p, s)| (p.to_string(), *s)).collect::<BTreeMap<_, _>>();
+        let mut out = Vec::new();
+        diff_scans(&scan(old), &scan(new), &mut out);
+        out.into_iter().map(|e| (e.kind, e.path)).collect()
+    }
+
+    #[test]
+    fn diff_reports_created_modified_and_removed() {
+        use VfsEventKind::*;
+        let old = [("/a", file(1, 1)), ("/b", file(1, 1)), ("/c", file(1, 1)), ("/d", dir(1))];
+        let new = [("/a", file(1, 1)), ("/b", file(2, 1)), ("/c", file(1, 2)), ("/d", dir(5)), ("/d/e", file(0, 5))];
+        assert_eq!(diff(&old, &new), [(Modified, "/b".into()), (Modified, "/c".into()), (Created, "/d/e".into())]);
+        assert_eq!(diff(&new, &old), [(Modified, "/b".into()), (Modified, "/c".into()), (Removed, "/d/e".into())]);
+        assert!(diff(&old, &old).is_empty());
+        assert_eq!(diff(&[], &[("/x", dir(1))]), [(Created, "/x".into())]);
+    }
+
+    #[test]
+    fn diff_reports_file_dir_swaps_as_remove_and_create() {
+        use VfsEventKind::*;
+        let swapped = [(Removed, "/a".to_string()), (Created, "/a".to_string())];
+        assert_eq!(diff(&[("/a", file(3, 1))], &[("/a", dir(1))]), swapped);
+        assert_eq!(diff(&[("/a", dir(1)), ("/a/b", file(1, 1))], &[("/a", file(1, 1))]), [swapped[0].clone(), swapped[1].clone(), (Removed, "/a/b".into())]);
+    }
+
+    #[test]
+    fn register_delivers_changes_in_the_same_frame() {
+        let mut vfs = Vfs::new();
+        vfs.mount("/", Box::new(MemBackend::new()));
+        vfs.mount("/quiet", Box::new(MemBackend::new()));
+        vfs.watch("/").unwrap();
+        let mut app = App::new();
+        app.insert_resource(vfs);
+        register(&mut app);
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let out = seen.clone();
+        let mut cursor = EventCursor::<VfsEvent>::default();
+        app.add_systems(Stage::PreUpdate, move |ecs: &mut Ecs| {
+            let events = ecs.get_resource::<Events<VfsEvent>>().unwrap();
+            out.lock().unwrap().extend(cursor.read(events).cloned());
+        });
+
+        let vfs = app.resource_mut::<Vfs>().unwrap();
+        vfs.write("/a.txt", b"1").unwrap();
+        vfs.write("/quiet/b.txt", b"1").unwrap();
+        app.update();
+        assert_eq!(*seen.lock().unwrap(), [VfsEvent::new(VfsEventKind::Created, "/a.txt")]);
+        seen.lock().unwrap().clear();
+        app.update();
+        assert!(seen.lock().unwrap().is_empty());
+        app.resource_mut::<Vfs>().unwrap().remove("/a.txt").unwrap();
+        app.update();
+        assert_eq!(*seen.lock().unwrap(), [VfsEvent::new(VfsEventKind::Removed, "/a.txt")]);
+    }
+
+    #[test]
+    fn std_fs_rescans_the_host_tree() {
+        let root = std::env::temp_dir().join(format!("aubrey_watch_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("target")).unwrap();
+        let mut vfs = Vfs::new();
+        vfs.mount("/project", Box::new(StdFsBackend::new(&root).unwrap().poll_interval(Duration::ZERO).watch_ignore("target")));
+        vfs.watch("/project").unwrap();
+        assert!(vfs.poll_changes().is_empty());
+
+        std::fs::write(root.join("a.txt"), b"1").unwrap();
+        std::fs::write(root.join("target/out"), b"1").unwrap();
+        std::fs::write(root.join(".a.txt.123.tmp"), b"1").unwrap();
+        assert_eq!(vfs.poll_changes(), [VfsEvent::new(VfsEventKind::Created, "/project/a.txt")]);
+        std::fs::write(root.join("a.txt"), b"22").unwrap();
+        assert_eq!(vfs.poll_changes(), [VfsEvent::new(VfsEventKind::Modified, "/project/a.txt")]);
+        std::fs::remove_file(root.join("a.txt")).unwrap();
+        assert_eq!(vfs.poll_changes(), [VfsEvent::new(VfsEventKind::Removed, "/project/a.txt")]);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,29 @@
+pub mod png;
+
+/// 8-bit RGBA pixels, row-major, no padding between rows.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RgbaImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl RgbaImage {
+    /// Transparent black image.
+    pub fn new(width: u32, height: u32) -> Self {
+        Self { width, height, pixels: vec![0; width as usize * height as usize * 4] }
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let i = (y as usize * self.width as usize + x as usize) * 4;
+        [self.pixels[i], self.pixels[i + 1], self.pixels[i + 2], self.pixels[i + 3]]
+    }
+
+    /// Multiply color channels by alpha (straight -> premultiplied), rounding to nearest.
+    pub fn premultiply(&mut self) {
+        for px in self.pixels.chunks_exact_mut(4) {
+            let a = px[3] as u16;
+            for c in &mut px[..3] { *c = ((*c as u16 * a + 127) / 255) as u8; }
+        }
+    }
+}
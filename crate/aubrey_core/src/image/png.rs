@@ -0,0 +1,450 @@
+use std::fmt;
+
+use crate::fs::pack::crc32;
+
+use super::RgbaImage;
+
+const SIGNATURE: &[u8; 8] = b"\x89PNG\r\n\x1a\n";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum PngError {
+    NotPng,
+    /// Data ends inside a chunk or the image data is short.
+    Truncated,
+    /// A chunk failed its CRC check.
+    Checksum,
+    /// Malformed header or chunk.
+    Invalid(&'static str),
+    Unsupported(&'static str),
+}
+
+impl fmt::Display for PngError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PngError::NotPng => write!(f, "not a PNG file"),
+            PngError::Truncated => write!(f, "truncated PNG data"),
+            PngError::Checksum => write!(f, "PNG chunk checksum mismatch"),
+            PngError::Invalid(what) => write!(f, "invalid PNG: {}", what),
+            PngError::Unsupported(what) => write!(f, "unsupported PNG: {}", what),
+        }
+    }
+}
+
+impl std::error::Error for PngError {}
+
+struct Header {
+    width: u32,
+    height: u32,
+    depth: u8,
+    color: u8,
+    interlaced: bool,
+}
+
+impl Header {
+    fn channels(&self) -> usize {
+        match self.color { 0 | 3 => 1, 4 => 2, 2 => 3, _ => 4 }
+    }
+    fn bits_per_pixel(&self) -> usize { self.channels() * self.depth as usize }
+    fn row_bytes(&self, width: usize) -> usize { (width * self.bits_per_pixel()).div_ceil(8) }
+}
+
+// tRNS contents by color type
+enum Transparency {
+    None,
+    Gray(u16),
+    Rgb(u16, u16, u16),
+    Palette(Vec<u8>),
+}
+
+/// Decode a PNG into straight (non-premultiplied) RGBA8.
+/// Supports every standard color type and bit depth, palettes with `tRNS`, and Adam7 interlacing.
+/// 16-bit samples are reduced to their high byte.
+pub fn decode(data: &[u8]) -> Result<RgbaImage, PngError> {
+    if data.len() < 8 || &data[..8] != SIGNATURE { return Err(PngError::NotPng); }
+    let mut pos = 8;
+    let mut header = None;
+    let mut palette: Vec<[u8; 3]> = Vec::new();
+    let mut trns = Transparency::None;
+    let mut idat = Vec::new();
+    loop {
+        let len = u32::from_be_bytes(data.get(pos..pos + 4).ok_or(PngError::Truncated)?.try_into().unwrap()) as usize;
+        let end = pos.checked_add(12 + len).ok_or(PngError::Truncated)?;
+        let chunk = data.get(pos + 4..end).ok_or(PngError::Truncated)?;
+        let (kind, body) = chunk[..len + 4].split_at(4);
+        let crc = u32::from_be_bytes(chunk[len + 4..].try_into().unwrap());
+        if crc32(&chunk[..len + 4]) != crc { return Err(PngError::Checksum); }
+        pos = end;
+        match kind {
+            b"IHDR" => {
+                if body.len() != 13 { return Err(PngError::Invalid("IHDR length")); }
+                let h = Header {
+                    width: u32::from_be_bytes(body[0..4].try_into().unwrap()),
+                    height: u32::from_be_bytes(body[4..8].try_into().unwrap()),
+                    depth: body[8],
+                    color: body[9],
+                    interlaced: body[12] == 1,
+                };
+                let depth_ok = match h.color {
+                    0 => matches!(h.depth, 1 | 2 | 4 | 8 | 16),
+                    3 => matches!(h.depth, 1 | 2 | 4 | 8),
+                    2 | 4 | 6 => matches!(h.depth, 8 | 16),
+                    _ => return Err(PngError::Invalid("color type")),
+                };
+                if !depth_ok { return Err(PngError::Invalid("bit depth for color type")); }
+                if body[10] != 0 || body[11] != 0 { return Err(PngError::Unsupported("compression or filter method")); }
+                if body[12] > 1 { return Err(PngError::Invalid("interlace method")); }
+                if h.width == 0 || h.height == 0 { return Err(PngError::Invalid("zero size")); }
+                header = Some(h);
+            }
+            b"PLTE" => palette = body.chunks_exact(3).map(|c| [c[0], c[1], c[2]]).collect(),
+            b"tRNS" => {
+                let h = header.as_ref().ok_or(PngError::Invalid("tRNS before IHDR"))?;
+                let be16 = |i: usize| body.get(i..i + 2).map(|b| u16::from_be_bytes([b[0], b[1]])).ok_or(PngError::Invalid("tRNS length"));
+                trns = match h.color {
+                    0 => Transparency::Gray(be16(0)?),
+                    2 => Transparency::Rgb(be16(0)?, be16(2)?, be16(4)?),
+                    3 => Transparency::Palette(body.to_vec()),
+                    _ => Transparency::None,
+                };
+            }
+            b"IDAT" => idat.extend_from_slice(body),
+            b"IEND" => break,
+            _ if kind[0] & 0x20 == 0 => return Err(PngError::Unsupported("critical chunk")),
+            _ => {}
+        }
+    }
+    let h = header.ok_or(PngError::Invalid("missing IHDR"))?;
+    if h.color == 3 && palette.is_empty() { return Err(PngError::Invalid("missing PLTE")); }
+    let (w, ht) = (h.width as usize, h.height as usize);
+
+    // (x0, y0, dx, dy) per pass
+    let passes: &[(usize, usize, usize, usize)] = if h.interlaced {
+        &[(0, 0, 8, 8), (4, 0, 8, 8), (0, 4, 4, 8), (2, 0, 4, 4), (0, 2, 2, 4), (1, 0, 2, 2), (0, 1, 1, 2)]
+    } else {
+        &[(0, 0, 1, 1)]
+    };
+    let pass_size = |&(x0, y0, dx, dy): &(usize, usize, usize, usize)| ((w.saturating_sub(x0)).div_ceil(dx), (ht.saturating_sub(y0)).div_ceil(dy));
+    let expected: usize = passes.iter().map(pass_size).filter(|(pw, ph)| *pw > 0 && *ph > 0).map(|(pw, ph)| (h.row_bytes(pw) + 1) * ph).sum();
+    let raw = miniz_oxide::inflate::decompress_to_vec_zlib_with_limit(&idat, expected).map_err(|_| PngError::Invalid("image data does not inflate"))?;
+    if raw.len() < expected { return Err(PngError::Truncated); }
+
+    let mut img = RgbaImage::new(h.width, h.height);
+    let bpp = h.bits_per_pixel().div_ceil(8);
+    let mut off = 0;
+    for pass in passes {
+        let (pw, ph) = pass_size(pass);
+        if pw == 0 || ph == 0 { continue; }
+        let stride = h.row_bytes(pw);
+        let mut prev = vec![0u8; stride];
+        for py in 0..ph {
+            let filter = raw[off];
+            let mut row = raw[off + 1..off + 1 + stride].to_vec();
+            off += stride + 1;
+            unfilter(filter, &mut row, &prev, bpp)?;
+            for px in 0..pw {
+                let (x, y) = (pass.0 + px * pass.2, pass.1 + py * pass.3);
+                let rgba = pixel(&h, &row, px, &palette, &trns);
+                let i = (y * w + x) * 4;
+                img.pixels[i..i + 4].copy_from_slice(&rgba);
+            }
+            prev = row;
+        }
+    }
+    Ok(img)
+}
+
+/// Encode as an 8-bit RGBA PNG (straight alpha). Rows use the adaptive filter heuristic
+/// from the PNG spec (smallest sum of absolute differences).
+pub fn encode(img: &RgbaImage) -> Vec<u8> {
+    let stride = img.width as usize * 4;
+    let mut raw = Vec::with_capacity((stride + 1) * img.height as usize);
+    let mut prev = vec![0u8; stride];
+    let mut row_out = vec![0u8; stride];
+    let mut best = vec![0u8; stride];
+    for row in img.pixels.chunks_exact(stride.max(1)).take(img.height as usize) {
+        let mut best_filter = 0;
+        let mut best_cost = u64::MAX;
+        for filter in 0..5u8 {
+            for i in 0..stride {
+                let a = if i >= 4 { row[i - 4] } else { 0 };
+                let c = if i >= 4 { prev[i - 4] } else { 0 };
+                row_out[i] = row[i].wrapping_sub(predict(filter, a, prev[i], c));
+            }
+            let cost: u64 = row_out.iter().map(|&v| (v as i8).unsigned_abs() as u64).sum();
+            if cost < best_cost {
+                best_cost = cost;
+                best_filter = filter;
+                best.copy_from_slice(&row_out);
+            }
+        }
+        raw.push(best_filter);
+        raw.extend_from_slice(&best);
+        prev.copy_from_slice(row);
+    }
+
+    let mut out = SIGNATURE.to_vec();
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&img.width.to_be_bytes());
+    ihdr.extend_from_slice(&img.height.to_be_bytes());
+    // 8-bit RGBA, deflate, adaptive filtering, not interlaced
+    ihdr.extend_from_slice(&[8, 6, 0, 0, 0]);
+    write_chunk(&mut out, b"IHDR", &ihdr);
+    write_chunk(&mut out, b"IDAT", &miniz_oxide::deflate::compress_to_vec_zlib(&raw, 6));
+    write_chunk(&mut out, b"IEND", &[]);
+    out
+}
+
+fn write_chunk(out: &mut Vec<u8>, kind: &[u8; 4], body: &[u8]) {
+    out.extend_from_slice(&(body.len() as u32).to_be_bytes());
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(body);
+    let crc = crc32(&out[start..]);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+// Filter predictor from the left (a), above (b) and upper-left (c) bytes.
+fn predict(filter: u8, a: u8, b: u8, c: u8) -> u8 {
+    match filter {
+        1 => a,
+        2 => b,
+        3 => ((a as u16 + b as u16) / 2) as u8,
+        4 => {
+            let p = a as i16 + b as i16 - c as i16;
+            let (pa, pb, pc) = ((p - a as i16).abs(), (p - b as i16).abs(), (p - c as i16).abs());
+            if pa <= pb && pa <= pc { a } else if pb <= pc { b } else { c }
+        }
+        _ => 0,
+    }
+}
+
+fn unfilter(filter: u8, row: &mut [u8], prev: &[u8], bpp: usize) -> Result<(), PngError> {
+    if filter > 4 { return Err(PngError::Invalid("filter type")); }
+    for i in 0..row.len() {
+        let a = if i >= bpp { row[i - bpp] } else { 0 };
+        let c = if i >= bpp { prev[i - bpp] } else { 0 };
+        row[i] = row[i].wrapping_add(predict(filter, a, prev[i], c));
+    }
+    Ok(())
+}
+
+// Sample `n` of pixel `x` at the image's bit depth.
+fn sample(h: &Header, row: &[u8], x: usize, n: usize) -> u16 {
+    let idx = x * h.channels() + n;
+    match h.depth {
+        16 => u16::from_be_bytes([row[idx * 2], row[idx * 2 + 1]]),
+        8 => row[idx] as u16,
+        d => {
+            let bit = idx * d as usize;
+            let shift = 8 - d as usize - bit % 8;
+            ((row[bit / 8] >> shift) & ((1u8 << d) - 1)) as u16
+        }
+    }
+}
+
+fn pixel(h: &Header, row: &[u8], x: usize, palette: &[[u8; 3]], trns: &Transparency) -> [u8; 4] {
+    let s = |n| sample(h, row, x, n);
+    // to 8 bits
+    let c8 = |v: u16| match h.depth { 16 => (v >> 8) as u8, 8 => v as u8, d => (v * 255 / ((1 << d) - 1)) as u8 };
+    match h.color {
+        0 => {
+            let g = s(0);
+            let a = if matches!(trns, Transparency::Gray(t) if *t == g) { 0 } else { 255 };
+            let g = c8(g);
+            [g, g, g, a]
+        }
+        2 => {
+            let (r, g, b) = (s(0), s(1), s(2));
+            let a = if matches!(trns, Transparency::Rgb(tr, tg, tb) if (*tr, *tg, *tb) == (r, g, b)) { 0 } else { 255 };
+            [c8(r), c8(g), c8(b), a]
+        }
+        3 => {
+            let i = s(0) as usize;
+            let [r, g, b] = palette.get(i).copied().unwrap_or([0, 0, 0]);
+            let a = match trns { Transparency::Palette(t) => t.get(i).copied().unwrap_or(255), _ => 255 };
+            [r, g, b, a]
+        }
+        4 => {
+            let g = c8(s(0));
+            [g, g, g, c8(s(1))]
+        }
+        _ => [c8(s(0)), c8(s(1)), c8(s(2)), c8(s(3))],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Samples packed at `depth`, high bits first.
+    fn pack(samples: &[u16], depth: u8) -> Vec<u8> {
+        match depth {
+            16 => samples.iter().flat_map(|s| s.to_be_bytes()).collect(),
+            8 => samples.iter().map(|&s| s as u8).collect(),
+            d => {
+                let mut out = vec![0u8; (samples.len() * d as usize).div_ceil(8)];
+                for (i, &s) in samples.iter().enumerate() {
+                    let bit = i * d as usize;
+                    out[bit / 8] |= (s as u8) << (8 - d as usize - bit % 8);
+                }
+                out
+            }
+        }
+    }
+
+    // PNG from unfiltered rows of samples.
+    fn png(width: u32, height: u32, depth: u8, color: u8, interlaced: bool, rows: &[Vec<u16>], extra: &[(&[u8; 4], Vec<u8>)]) -> Vec<u8> {
+        let raw: Vec<u8> = rows.iter().flat_map(|r| std::iter::once(0).chain(pack(r, depth))).collect();
+        let mut out = SIGNATURE.to_vec();
+        let mut ihdr = width.to_be_bytes().to_vec();
+        ihdr.extend_from_slice(&height.to_be_bytes());
+        ihdr.extend_from_slice(&[depth, color, 0, 0, interlaced as u8]);
+        write_chunk(&mut out, b"IHDR", &ihdr);
+        for (kind, body) in extra { write_chunk(&mut out, kind, body); }
+        write_chunk(&mut out, b"IDAT", &miniz_oxide::deflate::compress_to_vec_zlib(&raw, 6));
+        write_chunk(&mut out, b"IEND", &[]);
+        out
+    }
+
+    fn pixels(img: &RgbaImage) -> Vec<[u8; 4]> {
+        img.pixels.chunks_exact(4).map(|p| [p[0], p[1], p[2], p[3]]).collect()
+    }
+
+    fn test_image(width: u32, height: u32) -> RgbaImage {
+        let mut img = RgbaImage::new(width, height);
+        for (i, px) in img.pixels.chunks_exact_mut(4).enumerate() {
+            let (x, y) = (i as u32 % width, i as u32 / width);
+            px.copy_from_slice(&[(x * 37) as u8, (y * 53) as u8, (x * y * 11 + 7) as u8, if (x + y) % 3 == 0 { 255 } else { (x * 90) as u8 }]);
+        }
+        img
+    }
+
+    #[test]
+    fn round_trips_through_encode() {
+        for (w, h) in [(1, 1), (7, 5), (40, 3), (2, 33)] {
+            let img = test_image(w, h);
+            assert_eq!(decode(&encode(&img)).unwrap(), img, "{}x{}", w, h);
+        }
+        // flat and gradient rows pick different filters
+        let mut img = RgbaImage::new(16, 16);
+        for (i, px) in img.pixels.chunks_exact_mut(4).enumerate() { px.copy_from_slice(&[i as u8, 100, (i / 16) as u8, 255]); }
+        assert_eq!(decode(&encode(&img)).unwrap(), img);
+    }
+
+    #[test]
+    fn decodes_grayscale() {
+        let img = decode(&png(3, 1, 1, 0, false, &[vec![1, 0, 1]], &[])).unwrap();
+        assert_eq!(pixels(&img), [[255, 255, 255, 255], [0, 0, 0, 255], [255, 255, 255, 255]]);
+        let img = decode(&png(2, 1, 2, 0, false, &[vec![1, 3]], &[])).unwrap();
+        assert_eq!(pixels(&img), [[85, 85, 85, 255], [255, 255, 255, 255]]);
+        // tRNS marks one gray level transparent
+        let img = decode(&png(2, 1, 4, 0, false, &[vec![0, 15]], &[(b"tRNS", vec![0, 0])])).unwrap();
+        assert_eq!(pixels(&img), [[0, 0, 0, 0], [255, 255, 255, 255]]);
+        let img = decode(&png(2, 1, 8, 0, false, &[vec![7, 200]], &[])).unwrap();
+        assert_eq!(pixels(&img), [[7, 7, 7, 255], [200, 200, 200, 255]]);
+        let img = decode(&png(1, 2, 16, 0, false, &[vec![0x1234], vec![0xff00]], &[])).unwrap();
+        assert_eq!(pixels(&img), [[0x12, 0x12, 0x12, 255], [0xff, 0xff, 0xff, 255]]);
+    }
+
+    #[test]
+    fn decodes_rgb() {
+        let img = decode(&png(2, 1, 8, 2, false, &[vec![1, 2, 3, 4, 5, 6]], &[(b"tRNS", vec![0, 1, 0, 2, 0, 3])])).unwrap();
+        assert_eq!(pixels(&img), [[1, 2, 3, 0], [4, 5, 6, 255]]);
+        let img = decode(&png(1, 1, 16, 2, false, &[vec![0xabcd, 0x1234, 0xff00]], &[])).unwrap();
+        assert_eq!(pixels(&img), [[0xab, 0x12, 0xff, 255]]);
+    }
+
+    #[test]
+    fn decodes_palette() {
+        let plte = vec![10, 20, 30, 40, 50, 60, 70, 80, 90];
+        let img = decode(&png(3, 1, 2, 3, false, &[vec![0, 1, 2]], &[(b"PLTE", plte.clone()), (b"tRNS", vec![0x80])])).unwrap();
+        assert_eq!(pixels(&img), [[10, 20, 30, 0x80], [40, 50, 60, 255], [70, 80, 90, 255]]);
+        for depth in [1, 4, 8] {
+            let img = decode(&png(2, 1, depth, 3, false, &[vec![1, 0]], &[(b"PLTE", plte.clone())])).unwrap();
+            assert_eq!(pixels(&img), [[40, 50, 60, 255], [10, 20, 30, 255]], "depth {}", depth);
+        }
+        // out-of-range indices are black rather than a panic
+        let img = decode(&png(1, 1, 8, 3, false, &[vec![9]], &[(b"PLTE", plte)])).unwrap();
+        assert_eq!(pixels(&img), [[0, 0, 0, 255]]);
+        assert_eq!(decode(&png(1, 1, 8, 3, false, &[vec![0]], &[])), Err(PngError::Invalid("missing PLTE")));
+    }
+
+    #[test]
+    fn decodes_gray_alpha_and_rgba() {
+        let img = decode(&png(1, 1, 8, 4, false, &[vec![7, 9]], &[])).unwrap();
+        assert_eq!(pixels(&img), [[7, 7, 7, 9]]);
+        let img = decode(&png(1, 1, 16, 4, false, &[vec![0x1000, 0x20ff]], &[])).unwrap();
+        assert_eq!(pixels(&img), [[0x10, 0x10, 0x10, 0x20]]);
+        let img = decode(&png(2, 1, 8, 6, false, &[vec![1, 2, 3, 4, 5, 6, 7, 8]], &[])).unwrap();
+        assert_eq!(pixels(&img), [[1, 2, 3, 4], [5, 6, 7, 8]]);
+        let img = decode(&png(1, 1, 16, 6, false, &[vec![0x0100, 0x0200, 0x0300, 0x0400]], &[])).unwrap();
+        assert_eq!(pixels(&img), [[1, 2, 3, 4]]);
+    }
+
+    // Rows of the seven Adam7 passes of a `w` x `h` image.
+    fn adam7(w: u32, h: u32, sample: impl Fn(u32, u32) -> Vec<u16>) -> Vec<Vec<u16>> {
+        let passes = [(0, 0, 8, 8), (4, 0, 8, 8), (0, 4, 4, 8), (2, 0, 4, 4), (0, 2, 2, 4), (1, 0, 2, 2), (0, 1, 1, 2)];
+        let mut rows = Vec::new();
+        for (x0, y0, dx, dy) in passes {
+            if x0 >= w { continue; }
+            for y in (y0..h).step_by(dy) {
+                rows.push((x0..w).step_by(dx).flat_map(|x| sample(x, y)).collect());
+            }
+        }
+        rows
+    }
+
+    #[test]
+    fn decodes_adam7() {
+        let img = test_image(10, 9);
+        let rows = adam7(10, 9, |x, y| img.pixel(x, y).iter().map(|&c| c as u16).collect());
+        assert_eq!(decode(&png(10, 9, 8, 6, true, &rows, &[])).unwrap(), img);
+        // sub-byte rows are packed per pass; smaller than one 8x8 block
+        let bit = |x: u32, y: u32| ((x ^ y) & 1) as u16;
+        let img = decode(&png(5, 3, 1, 0, true, &adam7(5, 3, |x, y| vec![bit(x, y)]), &[])).unwrap();
+        for y in 0..3 {
+            for x in 0..5 { assert_eq!(img.pixel(x, y)[0], bit(x, y) as u8 * 255, "({}, {})", x, y); }
+        }
+    }
+
+    #[test]
+    fn truncated_input_is_an_error() {
+        let data = encode(&test_image(6, 4));
+        for len in 0..data.len() {
+            let expected = if len < 8 { PngError::NotPng } else { PngError::Truncated };
+            assert_eq!(decode(&data[..len]), Err(expected), "length {}", len);
+        }
+        // valid chunks around too little image data
+        let short = png(2, 3, 8, 0, false, &[vec![1, 2], vec![3, 4]], &[]);
+        assert_eq!(decode(&short), Err(PngError::Truncated));
+    }
+
+    #[test]
+    fn bad_crc_is_an_error() {
+        let data = encode(&test_image(3, 3));
+        // IHDR body, IDAT body, IEND crc
+        for i in [16, 41, data.len() - 1] {
+            let mut bad = data.clone();
+            bad[i] ^= 0x40;
+            assert_eq!(decode(&bad), Err(PngError::Checksum), "byte {}", i);
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_headers() {
+        assert_eq!(decode(b"GIF89a\0\0\0\0"), Err(PngError::NotPng));
+        assert_eq!(decode(&png(1, 1, 3, 0, false, &[vec![0]], &[])), Err(PngError::Invalid("bit depth for color type")));
+        assert_eq!(decode(&png(1, 1, 8, 5, false, &[vec![0]], &[])), Err(PngError::Invalid("color type")));
+        assert_eq!(decode(&png(0, 1, 8, 0, false, &[], &[])), Err(PngError::Invalid("zero size")));
+        let mut data = png(1, 1, 8, 0, false, &[vec![0]], &[]);
+        data.truncate(8);
+        write_chunk(&mut data, b"IEND", &[]);
+        assert_eq!(decode(&data), Err(PngError::Invalid("missing IHDR")));
+        let raw = [5u8, 0];
+        let mut data = SIGNATURE.to_vec();
+        write_chunk(&mut data, b"IHDR", &[0, 0, 0, 1, 0, 0, 0, 1, 8, 0, 0, 0, 0]);
+        write_chunk(&mut data, b"IDAT", &miniz_oxide::deflate::compress_to_vec_zlib(&raw, 6));
+        write_chunk(&mut data, b"IEND", &[]);
+        assert_eq!(decode(&data), Err(PngError::Invalid("filter type")));
+    }
+}
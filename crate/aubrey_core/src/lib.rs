@@ -2,3 +2,10 @@ pub mod app;
 pub mod ecs;
 pub mod resources;
 pub mod fs;
+pub mod asset;
+pub mod image;
+pub mod diagnostics;
+pub mod logging;
+pub mod rng;
+pub mod save;
+pub mod extract;
@@ -0,0 +1,273 @@
+use std::collections::VecDeque;
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::SystemTime;
+
+pub use log::{Level, LevelFilter};
+
+use crate::app::App;
+
+#[derive(Clone, Debug)]
+pub struct LogRecord {
+    /// Monotonic sequence number; use with `LogBuffer::since` to read incrementally.
+    pub seq: u64,
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+    pub time: SystemTime,
+}
+
+/// Per-target level filter. Targets match by module path prefix (`a::b` matches `a::b::c`),
+/// the longest matching prefix wins.
+#[derive(Clone, Debug)]
+pub struct LogFilter {
+    default: LevelFilter,
+    targets: Vec<(String, LevelFilter)>,
+}
+
+impl Default for LogFilter {
+    fn default() -> Self { Self { default: LevelFilter::Info, targets: Vec::new() } }
+}
+
+impl LogFilter {
+    pub fn new(default: LevelFilter) -> Self { Self { default, targets: Vec::new() } }
+
+    /// Parse `RUST_LOG`-like spec: `"info,aubrey_gui=debug,aubrey_render::text=off"`.
+    /// Entries with an unknown level or an empty target are ignored.
+    pub fn parse(spec: &str) -> Self {
+        let mut f = Self::default();
+        for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            match part.split_once('=') {
+                Some((target, level)) => {
+                    if let (false, Ok(l)) = (target.trim().is_empty(), level.trim().parse::<LevelFilter>()) { f.set(target.trim(), l); }
+                }
+                None => {
+                    if let Ok(l) = part.parse::<LevelFilter>() { f.default = l; }
+                }
+            }
+        }
+        f
+    }
+
+    pub fn default_level(&self) -> LevelFilter { self.default }
+
+    pub fn set_default(&mut self, level: LevelFilter) { self.default = level; }
+
+    pub fn set(&mut self, target: &str, level: LevelFilter) {
+        if let Some(t) = self.targets.iter_mut().find(|(t, _)| t == target) {
+            t.1 = level;
+        } else {
+            self.targets.push((target.to_string(), level));
+        }
+    }
+
+    pub fn unset(&mut self, target: &str) { self.targets.retain(|(t, _)| t != target); }
+
+    pub fn level_for(&self, target: &str) -> LevelFilter {
+        let mut best: Option<(usize, LevelFilter)> = None;
+        for (t, l) in &self.targets {
+            let hit = target == t || (target.starts_with(t.as_str()) && target[t.len()..].starts_with("::"));
+            if hit && best.is_none_or(|(len, _)| t.len() > len) { best = Some((t.len(), *l)); }
+        }
+        best.map(|(_, l)| l).unwrap_or(self.default)
+    }
+
+    pub fn enabled(&self, level: Level, target: &str) -> bool { level <= self.level_for(target) }
+}
+
+struct Ring {
+    entries: VecDeque<LogRecord>,
+    capacity: usize,
+    next_seq: u64,
+}
+
+struct Shared {
+    ring: Mutex<Ring>,
+    filter: RwLock<LogFilter>,
+    echo: AtomicBool,
+}
+
+/// Handle to the in-engine log ring buffer. Cheap to clone; all clones share one buffer.
+/// Inserted as a resource by `register` so the editor can display it.
+#[derive(Clone)]
+pub struct LogBuffer {
+    shared: Arc<Shared>,
+}
+
+impl LogBuffer {
+    pub fn new(capacity: usize) -> Self {
+        let ring = Ring { entries: VecDeque::with_capacity(capacity), capacity: capacity.max(1), next_seq: 0 };
+        Self {
+            shared: Arc::new(Shared {
+                ring: Mutex::new(ring),
+                filter: RwLock::new(LogFilter::default()),
+                echo: AtomicBool::new(false),
+            }),
+        }
+    }
+
+    pub fn push(&self, level: Level, target: &str, message: String) {
+        let mut ring = self.shared.ring.lock().unwrap_or_else(|e| e.into_inner());
+        let seq = ring.next_seq;
+        ring.next_seq += 1;
+        if ring.entries.len() == ring.capacity { ring.entries.pop_front(); }
+        ring.entries.push_back(LogRecord { seq, level, target: target.to_string(), message, time: SystemTime::now() });
+    }
+
+    /// All buffered records, oldest first.
+    pub fn records(&self) -> Vec<LogRecord> {
+        self.shared.ring.lock().unwrap_or_else(|e| e.into_inner()).entries.iter().cloned().collect()
+    }
+
+    /// Records with `seq >= seq`.
+    pub fn since(&self, seq: u64) -> Vec<LogRecord> {
+        let ring = self.shared.ring.lock().unwrap_or_else(|e| e.into_inner());
+        ring.entries.iter().filter(|r| r.seq >= seq).cloned().collect()
+    }
+
+    /// Sequence number the next record will get.
+    pub fn next_seq(&self) -> u64 { self.shared.ring.lock().unwrap_or_else(|e| e.into_inner()).next_seq }
+
+    pub fn len(&self) -> usize { self.shared.ring.lock().unwrap_or_else(|e| e.into_inner()).entries.len() }
+
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+    pub fn clear(&self) { self.shared.ring.lock().unwrap_or_else(|e| e.into_inner()).entries.clear(); }
+
+    pub fn set_capacity(&self, capacity: usize) {
+        let mut ring = self.shared.ring.lock().unwrap_or_else(|e| e.into_inner());
+        ring.capacity = capacity.max(1);
+        while ring.entries.len() > ring.capacity { ring.entries.pop_front(); }
+    }
+
+    // ----- runtime filtering -----
+    pub fn filter(&self) -> LogFilter { self.shared.filter.read().unwrap_or_else(|e| e.into_inner()).clone() }
+
+    pub fn set_filter(&self, filter: LogFilter) { *self.shared.filter.write().unwrap_or_else(|e| e.into_inner()) = filter; }
+
+    pub fn set_level(&self, target: &str, level: LevelFilter) {
+        self.shared.filter.write().unwrap_or_else(|e| e.into_inner()).set(target, level);
+    }
+
+    pub fn set_default_level(&self, level: LevelFilter) {
+        self.shared.filter.write().unwrap_or_else(|e| e.into_inner()).set_default(level);
+    }
+
+    pub fn enabled(&self, level: Level, target: &str) -> bool {
+        self.shared.filter.read().unwrap_or_else(|e| e.into_inner()).enabled(level, target)
+    }
+
+    /// Have the installed logger (`init`) also write records to stderr. Off by default;
+    /// `init` turns it on when `AUBREY_LOG_ECHO=1`.
+    pub fn set_echo(&self, echo: bool) { self.shared.echo.store(echo, Ordering::Relaxed); }
+
+    pub fn echo(&self) -> bool { self.shared.echo.load(Ordering::Relaxed) }
+}
+
+struct EngineLogger {
+    buffer: LogBuffer,
+}
+
+impl log::Log for EngineLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool { self.buffer.enabled(metadata.level(), metadata.target()) }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) { return; }
+        let message = record.args().to_string();
+        if self.buffer.echo() {
+            let _ = writeln!(std::io::stderr().lock(), "[{:<5} {}] {}", record.level(), record.target(), message);
+        }
+        self.buffer.push(record.level(), record.target(), message);
+    }
+
+    fn flush(&self) { let _ = std::io::stderr().flush(); }
+}
+
+static GLOBAL: OnceLock<LogBuffer> = OnceLock::new();
+
+/// Install the engine logger as the `log` facade backend (once per process).
+/// The initial filter comes from `AUBREY_LOG` (same syntax as `LogFilter::parse`), default `info`;
+/// `AUBREY_LOG_ECHO=1` also prints records to stderr.
+/// Returns the shared buffer; later calls return the same one.
+pub fn init() -> LogBuffer {
+    GLOBAL
+        .get_or_init(|| {
+            let buffer = LogBuffer::new(1024);
+            if let Ok(spec) = std::env::var("AUBREY_LOG") { buffer.set_filter(LogFilter::parse(&spec)); }
+            buffer.set_echo(std::env::var("AUBREY_LOG_ECHO").is_ok_and(|v| v == "1"));
+            let logger: &'static EngineLogger = Box::leak(Box::new(EngineLogger { buffer: buffer.clone() }));
+            // another logger may already be installed (e.g. by a test harness); keep the buffer anyway
+            if log::set_logger(logger).is_ok() { log::set_max_level(LevelFilter::Trace); }
+            buffer
+        })
+        .clone()
+}
+
+/// `init()` and insert the `LogBuffer` resource.
+pub fn register(app: &mut App) -> LogBuffer {
+    let buffer = init();
+    app.insert_resource(buffer.clone());
+    buffer
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_sets_default_and_targets() {
+        let f = LogFilter::parse("warn, aubrey_gui=debug ,aubrey_render::text = OFF");
+        assert_eq!(f.default_level(), LevelFilter::Warn);
+        assert_eq!(f.level_for("aubrey_gui"), LevelFilter::Debug);
+        assert_eq!(f.level_for("aubrey_render::text"), LevelFilter::Off);
+        assert_eq!(f.level_for("aubrey_render"), LevelFilter::Warn);
+        // later entries win
+        assert_eq!(LogFilter::parse("error,a=info,trace,a=warn").level_for("a"), LevelFilter::Warn);
+        assert_eq!(LogFilter::parse("error,a=info,trace").default_level(), LevelFilter::Trace);
+    }
+
+    #[test]
+    fn parse_ignores_bad_entries() {
+        let f = LogFilter::parse("loud,,=debug, =warn,a=,b=verbose,c=info=x,d=debug");
+        assert_eq!(f.default_level(), LevelFilter::Info);
+        for target in ["", "a", "b", "c"] { assert_eq!(f.level_for(target), LevelFilter::Info, "{:?}", target); }
+        assert_eq!(f.level_for("d"), LevelFilter::Debug);
+        assert_eq!(LogFilter::parse("").default_level(), LevelFilter::Info);
+    }
+
+    #[test]
+    fn level_for_matches_module_prefixes() {
+        let mut f = LogFilter::new(LevelFilter::Info);
+        f.set("a", LevelFilter::Error);
+        f.set("a::b", LevelFilter::Debug);
+        assert_eq!(f.level_for("a"), LevelFilter::Error);
+        assert_eq!(f.level_for("a::b"), LevelFilter::Debug);
+        // the longest matching prefix wins, whatever the insertion order
+        assert_eq!(f.level_for("a::b::c"), LevelFilter::Debug);
+        assert_eq!(f.level_for("a::c"), LevelFilter::Error);
+        // only whole path segments match
+        assert_eq!(f.level_for("a::bc"), LevelFilter::Error);
+        assert_eq!(f.level_for("ab"), LevelFilter::Info);
+        assert_eq!(f.level_for("x::a"), LevelFilter::Info);
+        assert!(f.enabled(Level::Debug, "a::b::c"));
+        assert!(!f.enabled(Level::Warn, "a::c"));
+        f.unset("a::b");
+        assert_eq!(f.level_for("a::b::c"), LevelFilter::Error);
+    }
+
+    #[test]
+    fn buffer_keeps_the_newest_records() {
+        let buffer = LogBuffer::new(2);
+        assert!(!buffer.echo());
+        for i in 0..3 { buffer.push(Level::Info, "t", format!("m{}", i)); }
+        let messages = |records: Vec<LogRecord>| records.into_iter().map(|r| r.message).collect::<Vec<_>>();
+        assert_eq!(messages(buffer.records()), ["m1", "m2"]);
+        assert_eq!(buffer.next_seq(), 3);
+        assert_eq!(messages(buffer.since(2)), ["m2"]);
+        buffer.set_capacity(1);
+        assert_eq!(messages(buffer.records()), ["m2"]);
+        buffer.set_level("t", LevelFilter::Error);
+        assert!(!buffer.clone().enabled(Level::Warn, "t::x"));
+    }
+}
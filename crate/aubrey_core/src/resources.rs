@@ -3,15 +3,18 @@ use std::collections::HashMap;
 
 pub struct Resources {
     map: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+    // type names for inspection/debug dumps
+    names: HashMap<TypeId, &'static str>,
 }
 
 impl Resources {
     pub fn new() -> Self {
-        Self { map: HashMap::new() }
+        Self { map: HashMap::new(), names: HashMap::new() }
     }
 
     pub fn insert<T: 'static + Send + Sync>(&mut self, value: T) {
         self.map.insert(TypeId::of::<T>(), Box::new(value));
+        self.names.insert(TypeId::of::<T>(), std::any::type_name::<T>());
     }
 
     pub fn contains<T: 'static>(&self) -> bool {
@@ -31,10 +34,21 @@ impl Resources {
     }
 
     pub fn remove<T: 'static + Send + Sync>(&mut self) -> Option<T> {
+        self.names.remove(&TypeId::of::<T>());
         self.map
             .remove(&TypeId::of::<T>())
             .and_then(|b| b.downcast::<T>().ok())
             .map(|boxed| *boxed)
     }
-}
 
+    pub fn len(&self) -> usize { self.map.len() }
+
+    pub fn is_empty(&self) -> bool { self.map.is_empty() }
+
+    /// Type names of all stored resources, sorted.
+    pub fn type_names(&self) -> Vec<&'static str> {
+        let mut out: Vec<&'static str> = self.names.values().copied().collect();
+        out.sort_unstable();
+        out
+    }
+}
@@ -0,0 +1,636 @@
+use std::any::Any;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::marker::PhantomData;
+
+use crate::asset::BoxError;
+use crate::ecs::{Ecs, Entity};
+use crate::fs::pack::crc32;
+use crate::fs::{Vfs, VfsError};
+use crate::rng::Rng;
+
+// .sav layout (little endian):
+//   magic "ASAV", format u16, flags u16 (unused), schema version u32, payload len u64, crc32 of payload u32,
+//   payload: resource count u32, per resource: key, data
+//            component type count u32, per type: key, entity count u32, per entity: id u64, data
+//   key = len u16 + utf-8, data = len u32 + bytes
+const MAGIC: &[u8; 4] = b"ASAV";
+const FORMAT: u16 = 1;
+const HEADER_LEN: usize = 24;
+
+type Boxed = Box<dyn Any + Send + Sync>;
+type Migration = Box<dyn Fn(&mut SaveFile) -> Result<(), BoxError> + Send + Sync>;
+
+/// Value that can be written to a save file. The encoding is up to the type; keep it stable
+/// or bump the store's schema version and add a migration.
+pub trait SaveData: Sized + Send + Sync + 'static {
+    fn save(&self) -> Vec<u8>;
+    fn load(data: &[u8]) -> Result<Self, BoxError>;
+}
+
+macro_rules! save_data_le {
+    ($($t:ty),*) => {$(
+        impl SaveData for $t {
+            fn save(&self) -> Vec<u8> { self.to_le_bytes().to_vec() }
+            fn load(data: &[u8]) -> Result<Self, BoxError> {
+                Ok(<$t>::from_le_bytes(data.try_into().map_err(|_| concat!("expected ", stringify!($t)))?))
+            }
+        }
+    )*};
+}
+save_data_le!(u8, u16, u32, u64, i8, i16, i32, i64, f32, f64);
+
+impl SaveData for bool {
+    fn save(&self) -> Vec<u8> { vec![*self as u8] }
+    fn load(data: &[u8]) -> Result<Self, BoxError> {
+        match data { [0] => Ok(false), [1] => Ok(true), _ => Err("expected bool".into()) }
+    }
+}
+
+impl SaveData for String {
+    fn save(&self) -> Vec<u8> { self.as_bytes().to_vec() }
+    fn load(data: &[u8]) -> Result<Self, BoxError> { Ok(String::from_utf8(data.to_vec())?) }
+}
+
+impl SaveData for Rng {
+    fn save(&self) -> Vec<u8> { self.to_bytes().to_vec() }
+    fn load(data: &[u8]) -> Result<Self, BoxError> { Rng::from_bytes(data).ok_or_else(|| "expected 32-byte rng state".into()) }
+}
+
+/// Decoded contents of a save file, before it is applied to the world.
+/// Migrations edit this directly.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SaveFile {
+    /// Schema version the data is in.
+    pub version: u32,
+    /// Resource key -> `SaveData::save` output.
+    pub resources: BTreeMap<String, Vec<u8>>,
+    /// Component key -> entity id at save time -> `SaveData::save` output.
+    pub components: BTreeMap<String, BTreeMap<u64, Vec<u8>>>,
+}
+
+impl SaveFile {
+    pub fn new(version: u32) -> Self { Self { version, ..Self::default() } }
+
+    /// Rename a resource or component key (for migrations). No-op if `from` is absent.
+    pub fn rename_key(&mut self, from: &str, to: &str) {
+        if let Some(v) = self.resources.remove(from) { self.resources.insert(to.to_string(), v); }
+        if let Some(v) = self.components.remove(from) { self.components.insert(to.to_string(), v); }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        fn key(out: &mut Vec<u8>, k: &str) {
+            out.extend_from_slice(&(k.len() as u16).to_le_bytes());
+            out.extend_from_slice(k.as_bytes());
+        }
+        fn data(out: &mut Vec<u8>, d: &[u8]) {
+            out.extend_from_slice(&(d.len() as u32).to_le_bytes());
+            out.extend_from_slice(d);
+        }
+        let mut payload = Vec::new();
+        payload.extend_from_slice(&(self.resources.len() as u32).to_le_bytes());
+        for (k, d) in &self.resources { key(&mut payload, k); data(&mut payload, d); }
+        payload.extend_from_slice(&(self.components.len() as u32).to_le_bytes());
+        for (k, entities) in &self.components {
+            key(&mut payload, k);
+            payload.extend_from_slice(&(entities.len() as u32).to_le_bytes());
+            for (id, d) in entities {
+                payload.extend_from_slice(&id.to_le_bytes());
+                data(&mut payload, d);
+            }
+        }
+        let mut out = Vec::with_capacity(HEADER_LEN + payload.len());
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&FORMAT.to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes());
+        out.extend_from_slice(&self.version.to_le_bytes());
+        out.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+        out.extend_from_slice(&crc32(&payload).to_le_bytes());
+        out.extend_from_slice(&payload);
+        out
+    }
+
+    /// Parse and verify a save file. None if it is not one, is truncated or fails its checksum.
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() < HEADER_LEN || &bytes[..4] != MAGIC { return None; }
+        if u16::from_le_bytes([bytes[4], bytes[5]]) != FORMAT { return None; }
+        let version = u32::from_le_bytes(bytes[8..12].try_into().ok()?);
+        let len = usize::try_from(u64::from_le_bytes(bytes[12..20].try_into().ok()?)).ok()?;
+        let crc = u32::from_le_bytes(bytes[20..24].try_into().ok()?);
+        let payload = bytes.get(HEADER_LEN..HEADER_LEN.checked_add(len)?)?;
+        if crc32(payload) != crc { return None; }
+
+        let mut r = Reader { data: payload, pos: 0 };
+        let mut file = SaveFile::new(version);
+        for _ in 0..r.u32()? {
+            let k = r.key()?;
+            file.resources.insert(k, r.data()?.to_vec());
+        }
+        for _ in 0..r.u32()? {
+            let k = r.key()?;
+            let mut entities = BTreeMap::new();
+            for _ in 0..r.u32()? {
+                let id = r.u64()?;
+                entities.insert(id, r.data()?.to_vec());
+            }
+            file.components.insert(k, entities);
+        }
+        Some(file)
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        let out = self.data.get(self.pos..self.pos.checked_add(n)?)?;
+        self.pos += n;
+        Some(out)
+    }
+    fn u32(&mut self) -> Option<u32> { self.take(4).map(|b| u32::from_le_bytes(b.try_into().unwrap())) }
+    fn u64(&mut self) -> Option<u64> { self.take(8).map(|b| u64::from_le_bytes(b.try_into().unwrap())) }
+    fn key(&mut self) -> Option<String> {
+        let len = self.take(2).map(|b| u16::from_le_bytes([b[0], b[1]]))? as usize;
+        String::from_utf8(self.take(len)?.to_vec()).ok()
+    }
+    fn data(&mut self) -> Option<&'a [u8]> {
+        let len = self.u32()? as usize;
+        self.take(len)
+    }
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+    /// Slot names can't be empty or contain path separators.
+    InvalidSlot(String),
+    Vfs(VfsError),
+    /// Not a save file, truncated or failed its checksum (and no usable backup).
+    Corrupt(String),
+    /// Written with a newer schema version than the store's.
+    TooNew { path: String, version: u32 },
+    /// No migration registered from this version.
+    MissingMigration { path: String, from: u32 },
+    Migration { path: String, from: u32, source: BoxError },
+    /// A registered type rejected its saved data.
+    Decode { key: String, source: BoxError },
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::InvalidSlot(s) => write!(f, "invalid save slot name: {:?}", s),
+            SaveError::Vfs(e) => write!(f, "{}", e),
+            SaveError::Corrupt(p) => write!(f, "corrupt save file: {}", p),
+            SaveError::TooNew { path, version } => write!(f, "{}: saved with newer schema version {}", path, version),
+            SaveError::MissingMigration { path, from } => write!(f, "{}: no migration from schema version {}", path, from),
+            SaveError::Migration { path, from, source } => write!(f, "{}: migration from schema version {} failed: {}", path, from, source),
+            SaveError::Decode { key, source } => write!(f, "saved {}: {}", key, source),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SaveError::Vfs(e) => Some(e),
+            SaveError::Migration { source, .. } | SaveError::Decode { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<VfsError> for SaveError {
+    fn from(e: VfsError) -> Self { SaveError::Vfs(e) }
+}
+
+/// Result of `SaveStore::load`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Loaded {
+    /// Entity id at save time -> entity spawned for it. Use it to fix up saved entity references.
+    pub entities: HashMap<u64, Entity>,
+    /// The slot's file was missing or corrupt and the backup was used.
+    pub from_backup: bool,
+    /// Schema version of the file before migrations ran.
+    pub version: u32,
+}
+
+// Type-erased access to one registered resource or component type.
+trait ErasedResource: Send + Sync {
+    fn capture(&self, ecs: &Ecs) -> Option<Vec<u8>>;
+    fn decode(&self, data: &[u8]) -> Result<Boxed, BoxError>;
+    fn insert(&self, ecs: &mut Ecs, value: Boxed);
+}
+
+trait ErasedComponent: Send + Sync {
+    fn capture(&self, ecs: &Ecs) -> BTreeMap<u64, Vec<u8>>;
+    fn entities(&self, ecs: &Ecs) -> Vec<Entity>;
+    fn decode(&self, data: &[u8]) -> Result<Boxed, BoxError>;
+    fn insert(&self, ecs: &mut Ecs, entity: Entity, value: Boxed);
+}
+
+struct Slot<T>(PhantomData<fn() -> T>);
+
+impl<T: SaveData> ErasedResource for Slot<T> {
+    fn capture(&self, ecs: &Ecs) -> Option<Vec<u8>> { ecs.get_resource::<T>().map(T::save) }
+    fn decode(&self, data: &[u8]) -> Result<Boxed, BoxError> { Ok(Box::new(T::load(data)?)) }
+    fn insert(&self, ecs: &mut Ecs, value: Boxed) {
+        if let Ok(v) = value.downcast::<T>() { ecs.insert_resource(*v); }
+    }
+}
+
+impl<T: SaveData> ErasedComponent for Slot<T> {
+    fn capture(&self, ecs: &Ecs) -> BTreeMap<u64, Vec<u8>> {
+        let mut out = BTreeMap::new();
+        ecs.for_each::<T, _>(|e, c| { out.insert(e.id(), c.save()); });
+        out
+    }
+    fn entities(&self, ecs: &Ecs) -> Vec<Entity> {
+        let mut out = Vec::new();
+        ecs.for_each::<T, _>(|e, _| out.push(e));
+        out
+    }
+    fn decode(&self, data: &[u8]) -> Result<Boxed, BoxError> { Ok(Box::new(T::load(data)?)) }
+    fn insert(&self, ecs: &mut Ecs, entity: Entity, value: Boxed) {
+        if let Ok(v) = value.downcast::<T>() { ecs.insert(entity, *v); }
+    }
+}
+
+/// Save slots in a `Vfs` directory (e.g. `user://saves`). Each slot is `<dir>/<slot>.sav`
+/// holding the registered resources and components, tagged with a schema version and checksummed.
+///
+/// Saving writes `<slot>.sav.tmp`, moves the previous file to `<slot>.sav.bak` and renames the new
+/// one into place, so an interrupted save leaves either the old or the new file plus the backup.
+/// Loading falls back to the backup when the file is missing or corrupt, runs migrations up to
+/// the current version, then replaces every entity that has a registered component.
+///
+/// ```ignore
+/// let store = SaveStore::new("user://saves")
+///     .with_version(2)
+///     .resource::<Rng>("rng")
+///     .component::<Health>("health")
+///     .migration(1, |file| { file.rename_key("hp", "health"); Ok(()) });
+/// store.save(&mut vfs, "slot1", &ecs)?;
+/// let loaded = store.load(&vfs, "slot1", &mut ecs)?;
+/// ```
+pub struct SaveStore {
+    dir: String,
+    version: u32,
+    resources: Vec<(String, Box<dyn ErasedResource>)>,
+    components: Vec<(String, Box<dyn ErasedComponent>)>,
+    migrations: BTreeMap<u32, Migration>,
+}
+
+impl SaveStore {
+    /// Schema version starts at 1.
+    pub fn new(dir: &str) -> Self {
+        Self { dir: dir.trim_end_matches('/').to_string(), version: 1, resources: Vec::new(), components: Vec::new(), migrations: BTreeMap::new() }
+    }
+
+    /// Schema version written to new saves; older files are migrated up to it on load.
+    pub fn with_version(mut self, version: u32) -> Self { self.version = version; self }
+
+    /// Save resource `T` under `key`.
+    pub fn resource<T: SaveData>(mut self, key: &str) -> Self {
+        self.resources.retain(|(k, _)| k != key);
+        self.resources.push((key.to_string(), Box::new(Slot::<T>(PhantomData))));
+        self
+    }
+
+    /// Save component `T` under `key`, for every entity that has it.
+    pub fn component<T: SaveData>(mut self, key: &str) -> Self {
+        self.components.retain(|(k, _)| k != key);
+        self.components.push((key.to_string(), Box::new(Slot::<T>(PhantomData))));
+        self
+    }
+
+    /// Upgrade files from schema version `from` to `from + 1`.
+    pub fn migration(mut self, from: u32, f: impl Fn(&mut SaveFile) -> Result<(), BoxError> + Send + Sync + 'static) -> Self {
+        self.migrations.insert(from, Box::new(f));
+        self
+    }
+
+    pub fn dir(&self) -> &str { &self.dir }
+
+    pub fn version(&self) -> u32 { self.version }
+
+    pub fn path(&self, slot: &str) -> Result<String, SaveError> {
+        if slot.is_empty() || slot == "." || slot == ".." || slot.contains(['/', '\\']) { return Err(SaveError::InvalidSlot(slot.to_string())); }
+        Ok(format!("{}/{}.sav", self.dir, slot))
+    }
+
+    /// Current values of the registered types.
+    pub fn capture(&self, ecs: &Ecs) -> SaveFile {
+        let mut file = SaveFile::new(self.version);
+        for (key, slot) in &self.resources {
+            if let Some(data) = slot.capture(ecs) { file.resources.insert(key.clone(), data); }
+        }
+        for (key, slot) in &self.components {
+            let entities = slot.capture(ecs);
+            if !entities.is_empty() { file.components.insert(key.clone(), entities); }
+        }
+        file
+    }
+
+    /// Replace the world's registered state with `file` (already at the current version).
+    /// Everything is decoded before the world is touched, so on error nothing changes.
+    /// Keys in the file that aren't registered are ignored.
+    pub fn apply(&self, ecs: &mut Ecs, file: &SaveFile) -> Result<HashMap<u64, Entity>, SaveError> {
+        let decode_err = |key: &str| { let key = key.to_string(); move |source| SaveError::Decode { key, source } };
+        let mut resources = Vec::new();
+        for (key, slot) in &self.resources {
+            let Some(data) = file.resources.get(key) else { continue };
+            resources.push((slot, slot.decode(data).map_err(decode_err(key))?));
+        }
+        let mut components = Vec::new();
+        let mut ids = BTreeSet::new();
+        for (key, slot) in &self.components {
+            let Some(entities) = file.components.get(key) else { continue };
+            for (id, data) in entities {
+                components.push((slot, *id, slot.decode(data).map_err(decode_err(key))?));
+                ids.insert(*id);
+            }
+        }
+        for (key, _) in file.resources.iter().filter(|(k, _)| !self.resources.iter().any(|(r, _)| r == *k)) {
+            log::warn!("save file resource {:?} is not registered; ignored", key);
+        }
+        for (key, _) in file.components.iter().filter(|(k, _)| !self.components.iter().any(|(c, _)| c == *k)) {
+            log::warn!("save file component {:?} is not registered; ignored", key);
+        }
+
+        for (_, slot) in &self.components {
+            for e in slot.entities(ecs) { ecs.despawn(e); }
+        }
+        let entities: HashMap<u64, Entity> = ids.into_iter().map(|id| (id, ecs.spawn_empty())).collect();
+        for (slot, value) in resources { slot.insert(ecs, value); }
+        for (slot, id, value) in components { slot.insert(ecs, entities[&id], value); }
+        Ok(entities)
+    }
+
+    /// Write the registered state to `slot`.
+    pub fn save(&self, vfs: &mut Vfs, slot: &str, ecs: &Ecs) -> Result<(), SaveError> { self.write(vfs, slot, &self.capture(ecs)) }
+
+    /// Write `file` to `slot` atomically, keeping the previous file as the backup.
+    pub fn write(&self, vfs: &mut Vfs, slot: &str, file: &SaveFile) -> Result<(), SaveError> {
+        let path = self.path(slot)?;
+        let (tmp, bak) = (format!("{}.tmp", path), format!("{}.bak", path));
+        vfs.create_dir_all(&self.dir)?;
+        vfs.write(&tmp, &file.encode())?;
+        if vfs.is_file(&path) { vfs.rename(&path, &bak)?; }
+        vfs.rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    /// Read `slot` (or its backup) and migrate it to the current version.
+    /// Returns the file, whether the backup was used, and the version it was saved with.
+    pub fn read(&self, vfs: &Vfs, slot: &str) -> Result<(SaveFile, bool, u32), SaveError> {
+        let path = self.path(slot)?;
+        let (mut file, from_backup, path) = match Self::read_file(vfs, &path) {
+            Ok(file) => (file, false, path),
+            Err(e @ (SaveError::Corrupt(_) | SaveError::Vfs(VfsError::NotFound(_)))) => {
+                let bak = format!("{}.bak", path);
+                match Self::read_file(vfs, &bak) {
+                    Ok(file) => {
+                        log::warn!("{}; using backup {}", e, bak);
+                        (file, true, bak)
+                    }
+                    Err(_) => return Err(e),
+                }
+            }
+            Err(e) => return Err(e),
+        };
+        let saved = file.version;
+        if saved > self.version { return Err(SaveError::TooNew { path, version: saved }); }
+        while file.version < self.version {
+            let from = file.version;
+            let migrate = self.migrations.get(&from).ok_or_else(|| SaveError::MissingMigration { path: path.clone(), from })?;
+            migrate(&mut file).map_err(|source| SaveError::Migration { path: path.clone(), from, source })?;
+            file.version = from + 1;
+        }
+        Ok((file, from_backup, saved))
+    }
+
+    fn read_file(vfs: &Vfs, path: &str) -> Result<SaveFile, SaveError> {
+        SaveFile::decode(&vfs.read(path)?).ok_or_else(|| SaveError::Corrupt(path.to_string()))
+    }
+
+    /// Read, migrate and apply `slot`.
+    pub fn load(&self, vfs: &Vfs, slot: &str, ecs: &mut Ecs) -> Result<Loaded, SaveError> {
+        let (file, from_backup, version) = self.read(vfs, slot)?;
+        let entities = self.apply(ecs, &file)?;
+        Ok(Loaded { entities, from_backup, version })
+    }
+
+    /// True if `slot` has a file or a backup.
+    pub fn exists(&self, vfs: &Vfs, slot: &str) -> bool {
+        self.path(slot).is_ok_and(|p| vfs.is_file(&p) || vfs.is_file(&format!("{}.bak", p)))
+    }
+
+    /// Slot names in the directory, sorted.
+    pub fn slots(&self, vfs: &Vfs) -> Vec<String> {
+        let names = vfs.list(&self.dir).unwrap_or_default();
+        let mut out: Vec<String> = names
+            .iter()
+            .filter_map(|n| n.strip_suffix(".sav").or_else(|| n.strip_suffix(".sav.bak")))
+            .map(str::to_string)
+            .collect();
+        out.sort();
+        out.dedup();
+        out
+    }
+
+    /// Remove `slot` and its backup.
+    pub fn delete(&self, vfs: &mut Vfs, slot: &str) -> Result<(), SaveError> {
+        let path = self.path(slot)?;
+        for p in [format!("{}.bak", path), format!("{}.tmp", path), path] {
+            match vfs.remove(&p) {
+                Ok(()) => {}
+                Err(e) if e.is_not_found() => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::MemBackend;
+
+    #[derive(Debug, PartialEq)]
+    struct Health(u32);
+
+    impl SaveData for Health {
+        fn save(&self) -> Vec<u8> { self.0.save() }
+        fn load(data: &[u8]) -> Result<Self, BoxError> { Ok(Health(u32::load(data)?)) }
+    }
+
+    fn vfs() -> Vfs {
+        let mut vfs = Vfs::new();
+        vfs.mount("/", Box::new(MemBackend::new()));
+        vfs
+    }
+
+    fn store() -> SaveStore {
+        SaveStore::new("/saves/").resource::<Rng>("rng").resource::<String>("name").component::<Health>("health")
+    }
+
+    fn health(ecs: &Ecs) -> Vec<(Entity, u32)> {
+        let mut out = Vec::new();
+        ecs.for_each::<Health, _>(|e, h| out.push((e, h.0)));
+        out.sort_by_key(|(e, _)| e.id());
+        out
+    }
+
+    #[test]
+    fn round_trip_remaps_entities() {
+        let (mut vfs, store) = (vfs(), store());
+        let mut ecs = Ecs::new();
+        ecs.spawn_empty();
+        let a = ecs.spawn_one(Health(10));
+        let b = ecs.spawn_one(Health(20));
+        ecs.insert_resource(Rng::seed_from_u64(5));
+        ecs.insert_resource("hero".to_string());
+        store.save(&mut vfs, "slot1", &ecs).unwrap();
+        assert_eq!(vfs.list("/saves").unwrap(), ["slot1.sav"]);
+
+        ecs.get_mut::<Health>(a).unwrap().0 = 0;
+        ecs.get_resource_mut::<Rng>().unwrap().next_u64();
+        ecs.insert_resource("villain".to_string());
+        let extra = ecs.spawn_one(Health(99));
+
+        let loaded = store.load(&vfs, "slot1", &mut ecs).unwrap();
+        assert!(!loaded.from_backup);
+        assert_eq!(loaded.version, 1);
+        assert_eq!(loaded.entities.len(), 2);
+        let (na, nb) = (loaded.entities[&a.id()], loaded.entities[&b.id()]);
+        assert_eq!(health(&ecs), [(na, 10), (nb, 20)]);
+        assert!(!ecs.is_alive(a) && !ecs.is_alive(b) && !ecs.is_alive(extra));
+        assert_eq!(ecs.get_resource::<Rng>(), Some(&Rng::seed_from_u64(5)));
+        assert_eq!(ecs.get_resource::<String>().map(String::as_str), Some("hero"));
+    }
+
+    #[test]
+    fn second_save_keeps_a_backup() {
+        let (mut vfs, store) = (vfs(), store());
+        let mut ecs = Ecs::new();
+        ecs.insert_resource("one".to_string());
+        store.save(&mut vfs, "s", &ecs).unwrap();
+        ecs.insert_resource("two".to_string());
+        store.save(&mut vfs, "s", &ecs).unwrap();
+        assert_eq!(vfs.list("/saves").unwrap(), ["s.sav", "s.sav.bak"]);
+        assert_eq!(SaveFile::decode(&vfs.read("/saves/s.sav.bak").unwrap()).unwrap().resources["name"], b"one");
+        assert!(store.exists(&vfs, "s"));
+        assert_eq!(store.slots(&vfs), ["s"]);
+        store.delete(&mut vfs, "s").unwrap();
+        assert!(!store.exists(&vfs, "s"));
+        assert!(store.slots(&vfs).is_empty());
+    }
+
+    #[test]
+    fn corrupt_file_falls_back_to_backup() {
+        let (mut vfs, store) = (vfs(), store());
+        let mut ecs = Ecs::new();
+        ecs.insert_resource("old".to_string());
+        store.save(&mut vfs, "s", &ecs).unwrap();
+        ecs.insert_resource("new".to_string());
+        store.save(&mut vfs, "s", &ecs).unwrap();
+
+        let mut bytes = vfs.read("/saves/s.sav").unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xFF;
+        vfs.write("/saves/s.sav", &bytes).unwrap();
+
+        let loaded = store.load(&vfs, "s", &mut ecs).unwrap();
+        assert!(loaded.from_backup);
+        assert_eq!(ecs.get_resource::<String>().map(String::as_str), Some("old"));
+
+        // a missing file uses the backup too
+        vfs.remove("/saves/s.sav").unwrap();
+        assert!(store.read(&vfs, "s").unwrap().1);
+        // no backup either: the original error
+        vfs.remove("/saves/s.sav.bak").unwrap();
+        assert!(matches!(store.read(&vfs, "s"), Err(SaveError::Vfs(VfsError::NotFound(_)))));
+    }
+
+    #[test]
+    fn truncated_files_are_corrupt() {
+        let (mut vfs, store) = (vfs(), store());
+        let mut ecs = Ecs::new();
+        ecs.insert_resource("x".to_string());
+        ecs.spawn_one(Health(1));
+        let bytes = store.capture(&ecs).encode();
+        for len in [0, 3, HEADER_LEN - 1, HEADER_LEN, bytes.len() - 1] {
+            vfs.create_dir_all("/saves").unwrap();
+            vfs.write("/saves/t.sav", &bytes[..len]).unwrap();
+            assert!(matches!(store.read(&vfs, "t"), Err(SaveError::Corrupt(p)) if p == "/saves/t.sav"), "len {}", len);
+        }
+        // length field pointing past the end
+        let mut long = bytes.clone();
+        long[12..20].copy_from_slice(&(bytes.len() as u64).to_le_bytes());
+        vfs.write("/saves/t.sav", &long).unwrap();
+        assert!(matches!(store.read(&vfs, "t"), Err(SaveError::Corrupt(_))));
+        let mut bad_magic = bytes.clone();
+        bad_magic[0] = b'X';
+        assert_eq!(SaveFile::decode(&bad_magic), None);
+        assert!(SaveFile::decode(&bytes).is_some());
+    }
+
+    #[test]
+    fn migrations_run_in_order() {
+        let mut vfs = vfs();
+        let mut v1 = SaveFile::new(1);
+        v1.components.insert("hp".into(), BTreeMap::from([(7, 3u32.save())]));
+        SaveStore::new("/saves").write(&mut vfs, "m", &v1).unwrap();
+
+        let store = SaveStore::new("/saves")
+            .with_version(3)
+            .component::<Health>("health")
+            .migration(1, |f| { f.rename_key("hp", "health"); Ok(()) })
+            .migration(2, |f| {
+                for d in f.components.get_mut("health").into_iter().flat_map(|m| m.values_mut()) {
+                    *d = (u32::load(d)? * 10).save();
+                }
+                Ok(())
+            });
+        let (file, from_backup, saved) = store.read(&vfs, "m").unwrap();
+        assert_eq!((file.version, from_backup, saved), (3, false, 1));
+        let mut ecs = Ecs::new();
+        let loaded = store.load(&vfs, "m", &mut ecs).unwrap();
+        assert_eq!(loaded.version, 1);
+        assert_eq!(health(&ecs), [(loaded.entities[&7], 30)]);
+
+        let gap = SaveStore::new("/saves").with_version(3).migration(1, |_| Ok(()));
+        assert!(matches!(gap.read(&vfs, "m"), Err(SaveError::MissingMigration { from: 2, .. })));
+        let failing = SaveStore::new("/saves").with_version(2).migration(1, |_| Err("nope".into()));
+        assert!(matches!(failing.read(&vfs, "m"), Err(SaveError::Migration { from: 1, .. })));
+
+        SaveStore::new("/saves").with_version(4).write(&mut vfs, "future", &SaveFile::new(4)).unwrap();
+        assert!(matches!(store.read(&vfs, "future"), Err(SaveError::TooNew { version: 4, .. })));
+    }
+
+    #[test]
+    fn bad_data_leaves_world_untouched() {
+        let store = store();
+        let mut ecs = Ecs::new();
+        let e = ecs.spawn_one(Health(1));
+        let mut file = SaveFile::new(1);
+        file.components.insert("health".into(), BTreeMap::from([(0, vec![1, 2])]));
+        assert!(matches!(store.apply(&mut ecs, &file), Err(SaveError::Decode { key, .. }) if key == "health"));
+        assert_eq!(health(&ecs), [(e, 1)]);
+    }
+
+    #[test]
+    fn slot_names_are_checked() {
+        let store = store();
+        assert_eq!(store.path("slot1").unwrap(), "/saves/slot1.sav");
+        for bad in ["", ".", "..", "a/b", "a\\b", "../x"] {
+            assert!(matches!(store.path(bad), Err(SaveError::InvalidSlot(_))), "{:?}", bad);
+        }
+        let mut vfs = vfs();
+        assert!(matches!(store.save(&mut vfs, "..", &Ecs::new()), Err(SaveError::InvalidSlot(_))));
+    }
+}
@@ -0,0 +1,24 @@
+// `#[derive(Bundle)]` from outside aubrey_core, with the default `::aubrey_core` path.
+use aubrey_core::ecs::{Bundle, Ecs};
+
+#[derive(Debug, PartialEq)]
+struct Pos(i32);
+#[derive(Debug, PartialEq)]
+struct Vel(i32);
+
+#[derive(Bundle, Debug, PartialEq)]
+struct Body {
+    pos: Pos,
+    #[bundle]
+    rest: (Vel, u8),
+}
+
+#[test]
+fn derive_uses_the_aubrey_core_path() {
+    let mut ecs = Ecs::new();
+    let e = ecs.spawn(Body { pos: Pos(1), rest: (Vel(2), 3) });
+    assert!(ecs.has_bundle::<Body>(e));
+    assert_eq!(ecs.get::<Vel>(e), Some(&Vel(2)));
+    ecs.remove_bundle::<Body>(e);
+    assert!(!ecs.has::<Pos>(e) && !ecs.has::<Vel>(e) && !ecs.has::<u8>(e));
+}
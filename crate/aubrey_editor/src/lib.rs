@@ -0,0 +1,126 @@
+use aubrey_core::app::App;
+use aubrey_window::{WindowDescriptor, WindowText};
+use aubrey_gui::{RootWidget, PlaceholderWidget, BoxWidget, Direction, MarginComponent, Size};
+use aubrey_gui::widgets::TextLabel;
+use aubrey_common::color::Rgba;
+use aubrey_core::ecs::{Children, Ecs, Entity, EventCursor, Events, Prefab, PrefabOverrides, Stage};
+use aubrey_core::fs::{user_data_dir, Vfs, VfsEvent, EmbeddedBackend, MemBackend, StdFsBackend};
+
+// Editor scene setup, shared by the binary and the golden-image test (tests/golden.rs).
+
+pub const WIDTH: u32 = 640;
+pub const HEIGHT: u32 = 400;
+
+/// The editor app and its window entity, with the project directory and `user://` mounted.
+pub fn build_app() -> (App, Entity) {
+    let mut vfs = base_vfs();
+    // Mount the project directory (cwd) read/write at /project when available
+    match StdFsBackend::new(".") {
+        Ok(be) => {
+            vfs.mount("/project", Box::new(be.watch_ignore("target").watch_ignore(".git")));
+            vfs.add_source("project", "/project");
+            // polled for changes so edited assets can be reloaded while the editor runs
+            if let Err(e) = vfs.watch("/project") { log::warn!("not watching /project: {}", e); }
+        }
+        Err(e) => log::warn!("project directory not mounted: {}", e),
+    }
+    // per-user settings and saves as user://
+    match user_data_dir("aubrey_editor") {
+        Some(dir) => match std::fs::create_dir_all(&dir).and_then(|_| StdFsBackend::new(&dir)) {
+            Ok(be) => {
+                vfs.mount("/user", Box::new(be));
+                vfs.add_source("user", "/user");
+            }
+            Err(e) => log::warn!("user:// not mounted: {}: {}", dir.display(), e),
+        },
+        None => log::warn!("user:// not mounted: no user data directory"),
+    }
+    build_with(vfs)
+}
+
+/// Same GUI without host mounts (memory and `embedded://` only), for offscreen rendering.
+pub fn headless_app() -> (App, Entity) { build_with(base_vfs()) }
+
+// In-memory root plus the built-in files.
+fn base_vfs() -> Vfs {
+    let mut vfs = Vfs::new();
+    vfs.mount("/", Box::new(MemBackend::new()));
+    // built-in files served straight from the binary as embedded://
+    let mut embedded = EmbeddedBackend::new();
+    aubrey_render::embed_assets(&mut embedded);
+    vfs.mount("/embedded", Box::new(embedded));
+    vfs.add_source("embedded", "/embedded");
+    vfs
+}
+
+fn build_with(vfs: Vfs) -> (App, Entity) {
+    let mut app = App::new();
+    aubrey_core::logging::register(&mut app);
+
+    // window systems are registered by aubrey_window::run
+
+    let e = app.spawn_one(WindowDescriptor::new("Aubrey Editor", WIDTH, HEIGHT));
+    app.insert_component(e, WindowText("Aubrey Editor".into()));
+
+    app.insert_resource(vfs);
+    aubrey_core::fs::watch::register(&mut app);
+    let mut vfs_changes = EventCursor::<VfsEvent>::default();
+    app.add_systems(Stage::Update, move |ecs: &mut Ecs| {
+        let Some(events) = ecs.get_resource::<Events<VfsEvent>>() else { return };
+        for ev in vfs_changes.read(events) { log::info!("vfs {:?}: {}", ev.kind, ev.path); }
+    });
+
+    let root = spawn_gui(&mut app);
+    app.insert_component(e, Children(vec![root]));
+
+    // register gui rendering
+    aubrey_gui::register(&mut app);
+    (app, e)
+}
+
+// GUI root and 2x2 grid using nested BoxWidget (Vertical -> two Horizontal rows)
+fn spawn_gui(app: &mut App) -> Entity {
+    let root = app.spawn_one(RootWidget);
+    let top_box = app.spawn_one(BoxWidget { dir: Direction::Down });
+
+    // rows
+    let row_top = app.spawn_one(BoxWidget { dir: Direction::Right });
+    let row_bottom = app.spawn_one(BoxWidget { dir: Direction::Right });
+
+    // cell prefab: a placeholder wrapped with a margin container to create spacing inside cells
+    let mut cell = Prefab::new();
+    cell.insert(Prefab::ROOT, MarginComponent::all(Size::Px(8.0)));
+    let cell_ph = cell.add_child(Prefab::ROOT).expect("prefab root");
+    cell.insert(cell_ph, PlaceholderWidget::default());
+    let mut spawn_cell = |color: Rgba| {
+        app.instantiate_with(&cell, &PrefabOverrides::new().set(cell_ph, PlaceholderWidget { color }))
+    };
+    let cell1 = spawn_cell(Rgba { r: 1.0, g: 0.0, b: 0.0, a: 1.0 }); // red
+    let cell2 = spawn_cell(Rgba { r: 0.0, g: 1.0, b: 0.0, a: 1.0 }); // green
+    let cell3 = spawn_cell(Rgba { r: 0.0, g: 0.0, b: 1.0, a: 1.0 }); // blue
+    let cell4 = spawn_cell(Rgba { r: 1.0, g: 1.0, b: 0.0, a: 1.0 }); // yellow
+
+    // rows contain the wrapped placeholders
+    app.insert_component(row_top, Children(vec![cell1.root(), cell2.root()]));
+    app.insert_component(row_bottom, Children(vec![cell3.root(), cell4.root()]));
+
+    // wrap rows with vertical margins to create spacing between rows
+    let row_top_wrap = app.spawn_one(MarginComponent::vertical(Size::Px(8.0)));
+    let row_bottom_wrap = app.spawn_one(MarginComponent::vertical(Size::Px(8.0)));
+    app.insert_component(row_top_wrap, Children(vec![row_top]));
+    app.insert_component(row_bottom_wrap, Children(vec![row_bottom]));
+
+    // top_box contains wrapped rows
+    app.insert_component(top_box, Children(vec![row_top_wrap, row_bottom_wrap]));
+
+    // Add a TextLabel in top-left cell
+    let label = app.spawn_one(TextLabel { text: "Hello, Aubrey!".into(), color: Rgba { r: 0.9, g: 0.9, b: 0.9, a: 1.0 }, font_path: aubrey_render::NOTO_SANS_REGULAR.into(), size_px: 18.0 });
+    let ph1 = cell1.entity(cell_ph).expect("prefab node");
+    app.insert_component(ph1, Children(vec![label]));
+
+    // add an outer margin around everything under root
+    let root_margin = app.spawn_one(MarginComponent::all(Size::Px(16.0)));
+    app.insert_component(root_margin, Children(vec![top_box]));
+    app.insert_component(root, Children(vec![root_margin]));
+    root
+}
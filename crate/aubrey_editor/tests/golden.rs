@@ -0,0 +1,14 @@
+// Editor GUI against golden/editor_grid.png; `AUBREY_BLESS=1 cargo test -p aubrey_editor` re-blesses it.
+
+use aubrey_editor::{headless_app, HEIGHT, WIDTH};
+use aubrey_gui::golden::Golden;
+
+#[test]
+fn editor_grid_matches_golden() {
+    let (mut app, window) = headless_app();
+    let reference = concat!(env!("CARGO_MANIFEST_DIR"), "/golden/editor_grid.png");
+    // small tolerance for anti-aliased text across platforms
+    if let Err(e) = Golden::new().tolerance(8).check_gui(&mut app, window, WIDTH as usize, HEIGHT as usize, reference) {
+        panic!("{}", e);
+    }
+}
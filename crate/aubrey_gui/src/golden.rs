@@ -0,0 +1,244 @@
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use aubrey_core::app::App;
+use aubrey_core::ecs::Entity;
+use aubrey_core::image::png::{self, PngError};
+use aubrey_render::Canvas;
+
+/// When set (to anything but `0`), checks write the rendered image as the new reference.
+pub const BLESS_ENV: &str = "AUBREY_BLESS";
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GoldenOutcome {
+    Matched,
+    /// The reference was (re)written.
+    Blessed,
+}
+
+#[derive(Debug)]
+pub enum GoldenError {
+    /// No reference yet; run with `AUBREY_BLESS=1` to create it.
+    MissingReference(PathBuf),
+    /// The window entity has no `RootWidget` child.
+    NoGui,
+    SizeMismatch { reference: PathBuf, expected: (usize, usize), actual: (usize, usize) },
+    /// More pixels than allowed differ by more than the tolerance. `diff` marks them in magenta.
+    Mismatch { reference: PathBuf, mismatched: usize, max_diff: u8, diff: PathBuf },
+    Io(PathBuf, io::Error),
+    Png(PathBuf, PngError),
+}
+
+impl fmt::Display for GoldenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GoldenError::MissingReference(p) => write!(f, "{}: no reference image (set {}=1 to create it)", p.display(), BLESS_ENV),
+            GoldenError::NoGui => write!(f, "no GUI root under the window entity"),
+            GoldenError::SizeMismatch { reference, expected, actual } => write!(
+                f, "{}: reference is {}x{} but rendered {}x{}", reference.display(), expected.0, expected.1, actual.0, actual.1
+            ),
+            GoldenError::Mismatch { reference, mismatched, max_diff, diff } => write!(
+                f, "{}: {} pixels differ (max channel difference {}); see {} (set {}=1 to accept)",
+                reference.display(), mismatched, max_diff, diff.display(), BLESS_ENV
+            ),
+            GoldenError::Io(p, e) => write!(f, "{}: {}", p.display(), e),
+            GoldenError::Png(p, e) => write!(f, "{}: {}", p.display(), e),
+        }
+    }
+}
+
+impl std::error::Error for GoldenError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            GoldenError::Io(_, e) => Some(e),
+            GoldenError::Png(_, e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Per-pixel difference between two same-sized canvases.
+#[derive(Clone, Debug)]
+pub struct Comparison {
+    /// Pixels where some channel differs by more than the tolerance.
+    pub mismatched: usize,
+    /// Largest channel difference over all pixels.
+    pub max_diff: u8,
+    /// Dimmed reference with mismatched pixels in magenta.
+    pub diff: Canvas,
+}
+
+/// Compare ARGB channels of `actual` and `expected`. None if their sizes differ.
+pub fn compare(actual: &Canvas, expected: &Canvas, tolerance: u8) -> Option<Comparison> {
+    if (actual.width(), actual.height()) != (expected.width(), expected.height()) { return None; }
+    let mut diff = Canvas::new(actual.width(), actual.height());
+    let (mut mismatched, mut max_diff) = (0, 0);
+    for ((a, e), d) in actual.pixels().iter().zip(expected.pixels()).zip(diff.pixels_mut()) {
+        let delta = (0..4).map(|i| ((a >> (i * 8)) as u8).abs_diff((e >> (i * 8)) as u8)).max().unwrap_or(0);
+        max_diff = max_diff.max(delta);
+        *d = if delta > tolerance {
+            mismatched += 1;
+            0xFFFF_00FF
+        } else {
+            let luma = (((e >> 16) & 0xFF) * 3 + ((e >> 8) & 0xFF) * 6 + (e & 0xFF)) / 10 / 3;
+            0xFF00_0000 | luma << 16 | luma << 8 | luma
+        };
+    }
+    Some(Comparison { mismatched, max_diff, diff })
+}
+
+/// Compares renders against reference PNGs on the host filesystem.
+/// On a mismatch `<name>.actual.png` and `<name>.diff.png` are written next to the reference;
+/// they are removed again once the check passes.
+///
+/// ```ignore
+/// Golden::new().tolerance(2).check_gui(&mut app, window, 640, 400, "golden/editor_grid.png")?;
+/// ```
+pub struct Golden {
+    tolerance: u8,
+    allowed_mismatches: usize,
+    bless: bool,
+}
+
+impl Default for Golden {
+    fn default() -> Self { Self::new() }
+}
+
+impl Golden {
+    /// Exact match; blessing follows `AUBREY_BLESS`.
+    pub fn new() -> Self {
+        let bless = std::env::var(BLESS_ENV).is_ok_and(|v| !v.is_empty() && v != "0");
+        Self { tolerance: 0, allowed_mismatches: 0, bless }
+    }
+
+    /// Largest per-channel difference still counted as equal.
+    pub fn tolerance(mut self, tolerance: u8) -> Self { self.tolerance = tolerance; self }
+
+    /// Number of pixels allowed to exceed the tolerance.
+    pub fn allowed_mismatches(mut self, n: usize) -> Self { self.allowed_mismatches = n; self }
+
+    /// Override `AUBREY_BLESS`.
+    pub fn bless(mut self, bless: bool) -> Self { self.bless = bless; self }
+
+    /// Render the GUI under window entity `w` offscreen at `width` x `height` and check it.
+    pub fn check_gui(&self, app: &mut App, w: Entity, width: usize, height: usize, reference: impl AsRef<Path>) -> Result<GoldenOutcome, GoldenError> {
+        let mut canvas = Canvas::new(width, height);
+        if !crate::render_offscreen(app, w, &mut canvas) { return Err(GoldenError::NoGui); }
+        self.check(&canvas, reference)
+    }
+
+    pub fn check(&self, canvas: &Canvas, reference: impl AsRef<Path>) -> Result<GoldenOutcome, GoldenError> {
+        let reference = reference.as_ref();
+        let actual_path = sibling(reference, "actual");
+        let diff_path = sibling(reference, "diff");
+        if self.bless {
+            if let Some(dir) = reference.parent().filter(|d| !d.as_os_str().is_empty()) {
+                std::fs::create_dir_all(dir).map_err(|e| GoldenError::Io(dir.to_path_buf(), e))?;
+            }
+            canvas.write_png(reference).map_err(|e| GoldenError::Io(reference.to_path_buf(), e))?;
+            remove_stale(&[&actual_path, &diff_path]);
+            return Ok(GoldenOutcome::Blessed);
+        }
+        let bytes = match std::fs::read(reference) {
+            Ok(b) => b,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Err(GoldenError::MissingReference(reference.to_path_buf())),
+            Err(e) => return Err(GoldenError::Io(reference.to_path_buf(), e)),
+        };
+        let expected = Canvas::from_image(&png::decode(&bytes).map_err(|e| GoldenError::Png(reference.to_path_buf(), e))?);
+        let write_actual = || canvas.write_png(&actual_path).map_err(|e| GoldenError::Io(actual_path.clone(), e));
+        let Some(cmp) = compare(canvas, &expected, self.tolerance) else {
+            write_actual()?;
+            return Err(GoldenError::SizeMismatch {
+                reference: reference.to_path_buf(),
+                expected: (expected.width(), expected.height()),
+                actual: (canvas.width(), canvas.height()),
+            });
+        };
+        if cmp.mismatched > self.allowed_mismatches {
+            write_actual()?;
+            cmp.diff.write_png(&diff_path).map_err(|e| GoldenError::Io(diff_path.clone(), e))?;
+            return Err(GoldenError::Mismatch { reference: reference.to_path_buf(), mismatched: cmp.mismatched, max_diff: cmp.max_diff, diff: diff_path });
+        }
+        remove_stale(&[&actual_path, &diff_path]);
+        Ok(GoldenOutcome::Matched)
+    }
+}
+
+// "dir/name.png" -> "dir/name.<tag>.png"
+fn sibling(reference: &Path, tag: &str) -> PathBuf {
+    let stem = reference.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    reference.with_file_name(format!("{}.{}.png", stem, tag))
+}
+
+fn remove_stale(paths: &[&Path]) {
+    for p in paths {
+        match std::fs::remove_file(p) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => log::warn!("could not remove {}: {}", p.display(), e),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn canvas(pixels: &[u32]) -> Canvas {
+        let mut c = Canvas::new(pixels.len(), 1);
+        c.pixels_mut().copy_from_slice(pixels);
+        c
+    }
+
+    #[test]
+    fn tolerance_is_inclusive() {
+        let expected = canvas(&[0xFF10_2030, 0xFF10_2030, 0x8010_2030]);
+        let actual = canvas(&[0xFF10_2030, 0xFF10_2038, 0x8910_2030]);
+        let cmp = compare(&actual, &expected, 8).unwrap();
+        assert_eq!((cmp.mismatched, cmp.max_diff), (1, 9));
+        let cmp = compare(&actual, &expected, 9).unwrap();
+        assert_eq!((cmp.mismatched, cmp.max_diff), (0, 9));
+        assert_eq!(compare(&expected, &expected, 0).unwrap().max_diff, 0);
+    }
+
+    #[test]
+    fn size_mismatch_is_none() {
+        assert!(compare(&Canvas::new(2, 1), &Canvas::new(1, 2), 255).is_none());
+        assert!(compare(&Canvas::new(2, 2), &Canvas::new(2, 3), 255).is_none());
+    }
+
+    #[test]
+    fn diff_marks_mismatches_in_magenta() {
+        let expected = canvas(&[0xFFFF_FFFF, 0xFF00_0000, 0xFF00_0000]);
+        let actual = canvas(&[0xFFFF_FFFF, 0xFFFF_0000, 0xFF00_0001]);
+        let cmp = compare(&actual, &expected, 0).unwrap();
+        assert_eq!(cmp.mismatched, 2);
+        // matching pixels show the reference dimmed to a third
+        assert_eq!(cmp.diff.pixels(), &[0xFF55_5555, 0xFFFF_00FF, 0xFFFF_00FF]);
+    }
+
+    #[test]
+    fn check_blesses_matches_and_reports() {
+        let dir = std::env::temp_dir().join(format!("aubrey_golden_{}", std::process::id()));
+        let reference = dir.join("ref.png");
+        let (actual_png, diff_png) = (dir.join("ref.actual.png"), dir.join("ref.diff.png"));
+        let image = canvas(&[0xFF00_0000, 0xFFFF_FFFF]);
+        let check = Golden::new().bless(false);
+
+        assert!(matches!(check.check(&image, &reference), Err(GoldenError::MissingReference(_))));
+        assert_eq!(Golden::new().bless(true).check(&image, &reference).unwrap(), GoldenOutcome::Blessed);
+        assert_eq!(check.check(&image, &reference).unwrap(), GoldenOutcome::Matched);
+
+        let changed = canvas(&[0xFF00_0000, 0xFF00_0000]);
+        match check.check(&changed, &reference) {
+            Err(GoldenError::Mismatch { mismatched: 1, max_diff: 255, .. }) => {}
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(actual_png.exists() && diff_png.exists());
+        assert_eq!(Golden::new().bless(false).allowed_mismatches(1).check(&changed, &reference).unwrap(), GoldenOutcome::Matched);
+        assert!(!actual_png.exists() && !diff_png.exists());
+        assert!(matches!(check.check(&Canvas::new(1, 1), &reference), Err(GoldenError::SizeMismatch { expected: (2, 1), actual: (1, 1), .. })));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
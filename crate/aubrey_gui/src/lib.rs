@@ -1,39 +1,22 @@
 use aubrey_core::app::{App, Stage};
 use aubrey_core::ecs::{Children, Entity};
 use aubrey_render as render;
-use aubrey_core::fs::Vfs;
+use aubrey_core::asset::{self, AssetServer, Handle, LoadState};
 use aubrey_window;
 use aubrey_core::ecs::ecs::Ecs;
+use aubrey_core::rng::Rng;
+use aubrey_core::fs::Vfs;
 
 pub mod widgets;
 pub mod layout;
+pub mod golden;
 
-pub use widgets::{RootWidget, PlaceholderWidget, BoxWidget, MarginComponent, MouseActionComponent};
+pub use widgets::{RootWidget, PlaceholderWidget, BoxWidget, MarginComponent, MouseActionComponent, ButtonBundle};
 pub use aubrey_common::{Direction, Size};
 
-use std::sync::atomic::{AtomicU64, Ordering};
-static RNG_SEED: AtomicU64 = AtomicU64::new(0);
-
-fn seed_rng_once() {
-    if RNG_SEED.load(Ordering::Relaxed) == 0 {
-        let nanos = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_nanos() as u64).unwrap_or(0);
-        let tid = std::thread::current().id();
-        // poor man's thread id hash
-        let tid_hash = unsafe { std::mem::transmute::<std::thread::ThreadId, u64>(tid) };
-        let seed = nanos ^ tid_hash ^ 0x9E3779B97F4A7C15u64;
-        RNG_SEED.store(seed, Ordering::Relaxed);
-    }
-}
-
-fn rand_f32() -> f32 {
-    seed_rng_once();
-    let mut s = RNG_SEED.load(Ordering::Relaxed);
-    // LCG
-    s = s.wrapping_mul(6364136223846793005).wrapping_add(1);
-    RNG_SEED.store(s, Ordering::Relaxed);
-    let v = ((s >> 32) as u32) as f32 / (u32::MAX as f32);
-    v
-}
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
 
 fn render_one(ecs: &mut Ecs, w: Entity) {
     // find GUI root under the window
@@ -46,7 +29,118 @@ fn render_one(ecs: &mut Ecs, w: Entity) {
     let (ww, wh) = match aubrey_window::window_size(w) { Some((w, h)) => (w as u32, h as u32), None => return };
     let items = layout::compute_items_ecs(ecs, root, ww, wh);
     if items.is_empty() { return; }
-    let _ = render::render_placeholders_wgpu(w, &items);
+    if render::render_placeholders_wgpu(w, &items).is_none() {
+        log::warn!("wgpu placeholder rendering failed for window {}", w.id());
+    }
+}
+
+// Report an unreadable font only once per path (the redraw handler runs every frame).
+fn warn_missing_font(path: &str, err: &dyn Display) {
+    thread_local! { static REPORTED: RefCell<HashSet<String>> = RefCell::new(HashSet::new()); }
+    if REPORTED.with(|r| r.borrow_mut().insert(path.to_string())) {
+        log::warn!("font not available: {}", err);
+    }
+}
+
+type FontHandles = HashMap<String, Handle<render::FontAsset>>;
+
+// entity and its (x, y, w, h), from `layout::layout_app`
+type LayoutNode = (Entity, (u32, u32, u32, u32));
+
+// Font handles per window for the labels drawn last frame. Fonts no label uses anymore
+// are dropped here and unloaded by the AssetServer.
+fn label_fonts(app: &mut App, w: Entity, labels: impl Iterator<Item = Entity>) -> FontHandles {
+    thread_local! { static FONTS: RefCell<HashMap<Entity, FontHandles>> = RefCell::new(HashMap::new()); }
+    let used: HashSet<String> = labels.filter_map(|e| app.get_component::<widgets::TextLabel>(e).map(|l| l.font_path.clone())).collect();
+    let Some(server) = app.resource_mut::<AssetServer>() else { return HashMap::new() };
+    FONTS.with(|f| {
+        let mut f = f.borrow_mut();
+        let fonts = f.entry(w).or_default();
+        fonts.retain(|p, _| used.contains(p));
+        for p in used { fonts.entry(p.clone()).or_insert_with(|| server.load(&p)); }
+        fonts.clone()
+    })
+}
+
+/// Which backend draws the GUI in windows. Insert as a resource; software (softbuffer) by default.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum GuiBackend {
+    #[default]
+    Software,
+    Wgpu,
+}
+
+/// Random stream for GUI effects (placeholder colours), kept apart from the gameplay `Rng` so
+/// clicks never advance it. Created on first use from `Rng::context_stream` if an `Rng` exists.
+pub struct GuiRng(pub Rng);
+
+fn gui_root(app: &App, w: Entity) -> Option<Entity> {
+    let children = app.get_component::<Children>(w)?;
+    children.0.iter().copied().find(|c| app.get_component::<RootWidget>(*c).is_some())
+}
+
+// One layout pass of the GUI under window `w`, turned into a display list.
+// With `block_on_fonts`, label fonts still loading are read from the `Vfs` on this thread first.
+fn build_frame(app: &mut App, w: Entity, width: u32, height: u32, block_on_fonts: bool) -> Option<render::DisplayList> {
+    let root = gui_root(app, w)?;
+    let nodes = layout::layout_app(app, root, width, height);
+    let fonts = label_fonts(app, w, nodes.iter().map(|(e, _)| *e));
+    if block_on_fonts {
+        // taken out so the server and the Vfs can be borrowed together
+        if let Some(mut server) = app.remove_resource::<AssetServer>() {
+            if let Some(vfs) = app.resource::<Vfs>() {
+                for path in fonts.keys() { server.load_blocking::<render::FontAsset>(vfs, path); }
+            }
+            app.insert_resource(server);
+        }
+    }
+    Some(paint_nodes(app, &nodes, &fonts))
+}
+
+fn paint_nodes(app: &App, nodes: &[LayoutNode], fonts: &FontHandles) -> render::DisplayList {
+    let assets = app.resource::<AssetServer>();
+    if assets.is_none() { log::debug!("no AssetServer resource; text labels are skipped"); }
+    let mut list = render::DisplayList::new();
+    list.clear(render::pack_rgba_u8(0,0,0,255));
+    for &(e, (x, y, w, h)) in nodes {
+        let cell = render::Rect::new(x as f32, y as f32, w as f32, h as f32);
+        if let Some(ph) = app.get_component::<widgets::PlaceholderWidget>(e) {
+            list.stroke_rect(cell, 1.0, render::pack_color(&ph.color));
+        }
+        // fonts appear once the AssetServer has loaded them
+        let Some(lbl) = app.get_component::<widgets::TextLabel>(e) else { continue };
+        let (Some(assets), Some(handle)) = (assets, fonts.get(&lbl.font_path)) else { continue };
+        match assets.load_state(handle) {
+            LoadState::Loaded => {
+                let Some(font) = assets.get(handle) else { continue };
+                // labels stay inside their cell
+                list.push_clip(cell)
+                    .text(((x + 4) as f32, (y + 4) as f32), &lbl.text, font, lbl.size_px, render::pack_color(&lbl.color))
+                    .pop_clip();
+            }
+            LoadState::Failed => {
+                if let Some(err) = assets.error(handle) { warn_missing_font(&lbl.font_path, err); }
+            }
+            LoadState::NotLoaded | LoadState::Loading => {}
+        }
+    }
+    list
+}
+
+/// Display list for the GUI under window entity `w`, laid out at `width` x `height`.
+/// This is what the window backends draw each frame; labels whose font is still loading are left out.
+pub fn display_list(app: &mut App, w: Entity, width: u32, height: u32) -> Option<render::DisplayList> {
+    build_frame(app, w, width, height, false)
+}
+
+/// Render the GUI under window entity `w` into `canvas`, laid out at the canvas size.
+/// `w` only needs `Children` with a `RootWidget`, not a live window, so this works headless.
+/// Label fonts still loading are read from the `Vfs` resource on this thread first, so the
+/// result doesn't depend on timing. Returns false if `w` has no GUI root.
+pub fn render_offscreen(app: &mut App, w: Entity, canvas: &mut render::Canvas) -> bool {
+    let Some(list) = build_frame(app, w, canvas.width() as u32, canvas.height() as u32, true) else { return false };
+    canvas.draw(|buf, width, height, stride| list.paint(buf, width, height, stride));
+    true
 }
 
 fn sys_gui_render(_ecs: &mut Ecs) { /* disabled: rendering handled by redraw handler */ }
@@ -54,45 +148,14 @@ fn sys_gui_render(_ecs: &mut Ecs) { /* disabled: rendering handled by redraw han
 pub fn register(app: &mut App) {
     // Immediate redraw handler during resize / redraw-request using App API
     fn render_one_app(app: &mut App, w: Entity) {
-        // Find root under window
-        let mut root: Option<Entity> = None;
-        if let Some(children) = app.get_component::<Children>(w) {
-            for c in &children.0 { if app.get_component::<RootWidget>(*c).is_some() { root = Some(*c); break; } }
-        }
-        let Some(root) = root else { return };
-
         let (ww, wh) = match aubrey_window::window_size(w) { Some((w, h)) => (w as u32, h as u32), None => return };
-        let items = layout::compute_items_app(app, root, ww, wh);
-        let vfs = app.resource::<Vfs>();
-        let _ = render::with_frame(w, |buf, width, height, stride| {
-            render::clear(buf, width, height, stride, render::pack_rgba_u8(0,0,0,255));
-            for it in &items {
-                let c = it.color;
-                let r = (c[0].clamp(0.0,1.0) * 255.0) as u8;
-                let g = (c[1].clamp(0.0,1.0) * 255.0) as u8;
-                let b = (c[2].clamp(0.0,1.0) * 255.0) as u8;
-                let a = (c[3].clamp(0.0,1.0) * 255.0) as u8;
-                let col = render::pack_rgba_u8(r,g,b,a);
-                render::draw_rect_outline(buf, width, height, stride, it.x as i32, it.y as i32, it.w as i32, it.h as i32, col);
-            }
-            // Draw TextLabel nodes
-            if let Some(vfs) = vfs {
-                let mut labels: Vec<(Entity, (u32,u32,u32,u32))> = layout::collect_textlabels_app(app, root, ww, wh);
-                for (e, (x,y,_w,_h)) in labels.drain(..) {
-                    if let Some(lbl) = app.get_component::<widgets::TextLabel>(e) {
-                        if let Some(bytes) = vfs.read(&lbl.font_path) {
-                            let color = render::pack_rgba_u8(
-                                (lbl.color.r.clamp(0.0,1.0) * 255.0) as u8,
-                                (lbl.color.g.clamp(0.0,1.0) * 255.0) as u8,
-                                (lbl.color.b.clamp(0.0,1.0) * 255.0) as u8,
-                                (lbl.color.a.clamp(0.0,1.0) * 255.0) as u8,
-                            );
-                            render::draw_text_mono(buf, width, height, stride, x as i32 + 4, y as i32 + 4, &lbl.text, &bytes, lbl.size_px, color);
-                        }
-                    }
-                }
-            }
-        });
+        let Some(list) = build_frame(app, w, ww, wh, false) else { return };
+        let backend = app.resource::<GuiBackend>().copied().unwrap_or_default();
+        let drawn = match backend {
+            GuiBackend::Software => render::with_frame(w, |buf, width, height, stride| list.paint(buf, width, height, stride)),
+            GuiBackend::Wgpu => render::render_display_list_wgpu(w, &list),
+        };
+        if drawn.is_none() { log::warn!("{:?} frame unavailable for window {}", backend, w.id()); }
     }
     fn on_click_app(app: &mut App, w: Entity, x: f32, y: f32) {
         // Find root under window
@@ -113,16 +176,32 @@ pub fn register(app: &mut App) {
             }
         }
         if let Some(e) = target {
+            if app.get_component::<widgets::PlaceholderWidget>(e).is_none() { return; }
+            if app.resource::<GuiRng>().is_none() {
+                let rng = app.resource::<Rng>().map(|r| r.context_stream::<GuiRng>()).unwrap_or_else(Rng::from_entropy);
+                app.insert_resource(GuiRng(rng));
+            }
+            let Some(GuiRng(rng)) = app.resource_mut::<GuiRng>() else { return };
+            // generate a random vivid-ish color
+            let (r, g, b) = (rng.next_f32(), rng.next_f32(), rng.next_f32());
             if let Some(ph) = app.get_component_mut::<widgets::PlaceholderWidget>(e) {
-                // generate a random vivid-ish color
-                let r = rand_f32();
-                let g = rand_f32();
-                let b = rand_f32();
                 ph.color = aubrey_common::color::Rgba { r, g, b, a: 1.0 };
             }
         }
     }
 
+    // widgets can be copied with clone_entity / clone_subtree / Prefab::from_entity
+    app.register_clone::<RootWidget>()
+        .register_clone::<PlaceholderWidget>()
+        .register_clone::<BoxWidget>()
+        .register_clone::<MarginComponent>()
+        .register_clone::<MouseActionComponent>()
+        .register_clone::<widgets::TextLabel>();
+
+    // label fonts are loaded from the Vfs through the AssetServer
+    asset::register(app);
+    if let Some(server) = app.resource_mut::<AssetServer>() { server.add_loader(render::FontLoader); }
+
     aubrey_window::set_redraw_handler(Some(render_one_app));
     aubrey_window::set_click_handler(Some(on_click_app));
     // Rendering is fully driven by the redraw handler now.
@@ -1,10 +1,12 @@
 use aubrey_common::color::Rgba;
 use aubrey_common::{Direction, Size};
 use aubrey_core::app::App;
-use aubrey_core::ecs::Entity;
+use aubrey_core::ecs::{Bundle, Entity};
 
+#[derive(Clone, Copy, Default)]
 pub struct RootWidget;
 
+#[derive(Clone)]
 pub struct PlaceholderWidget {
     pub color: Rgba,
 }
@@ -13,8 +15,10 @@ impl Default for PlaceholderWidget {
     fn default() -> Self { Self { color: Rgba { r: 1.0, g: 0.0, b: 1.0, a: 1.0 } } }
 }
 
+#[derive(Clone, Copy)]
 pub struct BoxWidget { pub dir: Direction }
 
+#[derive(Clone, Copy, Default)]
 pub struct MarginComponent {
     pub left: Size,
     pub right: Size,
@@ -28,6 +32,7 @@ impl MarginComponent {
     pub fn horizontal(size: Size) -> Self { Self { left: size, right: size, top: Size::ZERO, bottom: Size::ZERO } }
 }
 
+#[derive(Clone, Copy)]
 pub struct MouseActionComponent {
     pub on_click: Option<fn(&mut App, Entity)>,
     pub on_down: Option<fn(&mut App, Entity)>,
@@ -42,9 +47,25 @@ impl Default for MouseActionComponent {
     }
 }
 
+#[derive(Clone)]
 pub struct TextLabel {
     pub text: String,
     pub color: Rgba,
     pub font_path: String,
     pub size_px: f32,
 }
+
+impl Default for TextLabel {
+    fn default() -> Self {
+        Self { text: String::new(), color: Rgba { r: 1.0, g: 1.0, b: 1.0, a: 1.0 }, font_path: String::new(), size_px: 16.0 }
+    }
+}
+
+/// Clickable placeholder with a label. Use `..Default::default()` for the parts you don't set.
+#[derive(Bundle, Default)]
+pub struct ButtonBundle {
+    pub widget: PlaceholderWidget,
+    pub margin: MarginComponent,
+    pub mouse: MouseActionComponent,
+    pub label: TextLabel,
+}
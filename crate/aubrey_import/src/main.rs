@@ -0,0 +1,66 @@
+use std::path::Path;
+use std::process::ExitCode;
+
+use aubrey_core::asset::AssetProcessor;
+use aubrey_core::fs::{StdFsBackend, Vfs};
+use aubrey_render::FontAtlasImporter;
+
+const DEFAULT_CACHE: &str = "/.cache/imported";
+
+const USAGE: &str = "usage:
+  aubrey_import <project_dir> [--cache <dir>] [--force]   import assets into the cache
+                                                           (<dir> is relative to the project, default /.cache/imported)";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut project = None;
+    let mut cache = DEFAULT_CACHE.to_string();
+    let mut force = false;
+    let mut it = args.iter();
+    while let Some(arg) = it.next() {
+        match arg.as_str() {
+            "--force" => force = true,
+            "--cache" => match it.next() {
+                Some(dir) => cache = dir.clone(),
+                None => return usage(),
+            },
+            _ if project.is_none() && !arg.starts_with("--") => project = Some(arg.clone()),
+            _ => return usage(),
+        }
+    }
+    let Some(project) = project else { return usage() };
+    match run(Path::new(&project), &cache, force) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn usage() -> ExitCode {
+    eprintln!("{}", USAGE);
+    ExitCode::FAILURE
+}
+
+// Ok(false) if any asset failed to import.
+fn run(project: &Path, cache: &str, force: bool) -> Result<bool, String> {
+    let backend = StdFsBackend::new(project).map_err(|e| format!("{}: {}", project.display(), e))?;
+    let mut vfs = Vfs::new();
+    vfs.mount("/", Box::new(backend));
+    let processor = AssetProcessor::new(cache)
+        .with_importer(FontAtlasImporter)
+        .ignore("target")
+        .ignore(".git")
+        .force(force);
+    let report = processor.process_all(&mut vfs, "/");
+    for p in &report.imported { println!("imported {} -> {}", p.source, p.output); }
+    // errors already name the file
+    for (_, e) in &report.failed { eprintln!("failed   {}", e); }
+    println!(
+        "{} imported, {} up to date, {} failed, {} stale outputs removed",
+        report.imported.len(), report.cached.len(), report.failed.len(), report.removed
+    );
+    Ok(report.failed.is_empty())
+}
@@ -0,0 +1,114 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, Index, LitStr};
+
+/// `#[derive(Bundle)]` for structs. Each field is inserted as one component;
+/// fields marked `#[bundle]` are nested bundles and are flattened.
+/// The generated code names `::aubrey_core`; inside aubrey_core itself, or through a crate
+/// that re-exports it under another name, set the path with `#[bundle(crate = "crate")]`.
+/// ```ignore
+/// #[derive(Bundle, Default)]
+/// struct ButtonBundle {
+///     widget: PlaceholderWidget,
+///     margin: MarginComponent,
+///     #[bundle]
+///     extra: (MouseActionComponent, TextLabel),
+/// }
+/// ```
+#[proc_macro_derive(Bundle, attributes(bundle))]
+pub fn derive_bundle(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let fields = match &input.data {
+        Data::Struct(s) => &s.fields,
+        _ => {
+            return syn::Error::new_spanned(&input.ident, "Bundle can only be derived for structs")
+                .to_compile_error()
+                .into();
+        }
+    };
+
+    let mut krate: syn::Path = syn::parse_quote!(::aubrey_core);
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("bundle")) {
+        let res = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("crate") {
+                krate = meta.value()?.parse::<LitStr>()?.parse()?;
+                Ok(())
+            } else {
+                Err(meta.error("expected `crate = \"path\"`"))
+            }
+        });
+        if let Err(e) = res { return e.to_compile_error().into(); }
+    }
+    let core = quote!(#krate::ecs);
+    let mut insert = Vec::new();
+    let mut write = Vec::new();
+    let mut contains = Vec::new();
+    let mut remove = Vec::new();
+    let mut take = Vec::new();
+    let mut build_fields = Vec::new();
+
+    for (i, f) in fields.iter().enumerate() {
+        let nested = f.attrs.iter().any(|a| a.path().is_ident("bundle"));
+        let ty = &f.ty;
+        let access = match &f.ident {
+            Some(id) => quote!(#id),
+            None => {
+                let idx = Index::from(i);
+                quote!(#idx)
+            }
+        };
+        // component fields go through Single<T>, nested bundles are used as is
+        let (bundle_ty, wrap, unwrap) = if nested {
+            (quote!(#ty), quote!(self.#access), quote!())
+        } else {
+            (quote!(#core::One<#ty>), quote!(#core::One(self.#access)), quote!(.0))
+        };
+        insert.push(quote!(#core::Bundle::insert_immediate(#wrap, ecs, entity);));
+        write.push(quote!(#core::Bundle::write_commands(#wrap, entity, out, ecs);));
+        contains.push(quote!(<#bundle_ty as #core::Bundle>::contains(ecs, entity)));
+        remove.push(quote!(<#bundle_ty as #core::Bundle>::remove(ecs, entity);));
+        // every field is taken before any is checked, so a partial match still removes all it found
+        let var = quote::format_ident!("__field{}", i);
+        take.push(quote!(let #var = <#bundle_ty as #core::Bundle>::take_components(ecs, entity);));
+        let value = quote!(#var? #unwrap);
+        build_fields.push(match &f.ident {
+            Some(id) => quote!(#id: #value),
+            None => value,
+        });
+    }
+
+    let build = match fields {
+        Fields::Named(_) => quote!(Self { #(#build_fields),* }),
+        Fields::Unnamed(_) => quote!(Self( #(#build_fields),* )),
+        Fields::Unit => quote!(Self),
+    };
+
+    quote! {
+        impl #impl_generics #core::Bundle for #name #ty_generics #where_clause {
+            fn insert_immediate(self, ecs: &mut #core::Ecs, entity: #core::Entity) {
+                #(#insert)*
+            }
+            fn write_commands(self, entity: #core::Entity, out: &mut ::std::vec::Vec<#core::ecs::Command>, ecs: &mut #core::Ecs) {
+                let _ = (&entity, &out, &ecs);
+                #(#write)*
+            }
+            fn contains(ecs: &#core::Ecs, entity: #core::Entity) -> bool {
+                let _ = (&ecs, &entity);
+                true #(&& #contains)*
+            }
+            fn remove(ecs: &mut #core::Ecs, entity: #core::Entity) {
+                let _ = (&ecs, &entity);
+                #(#remove)*
+            }
+            fn take_components(ecs: &mut #core::Ecs, entity: #core::Entity) -> ::std::option::Option<Self> {
+                let _ = (&ecs, &entity);
+                #(#take)*
+                ::std::option::Option::Some(#build)
+            }
+        }
+    }
+    .into()
+}
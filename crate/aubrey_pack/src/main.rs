@@ -0,0 +1,121 @@
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use aubrey_core::fs::{Compression, PackBackend, PackWriter, Vfs, VfsError};
+
+const USAGE: &str = "usage:
+  aubrey_pack <input_dir> <output.pak> [--store]   build a pack from a directory
+  aubrey_pack --list <file.pak>                    list entries";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let res = match args.iter().map(String::as_str).collect::<Vec<_>>().as_slice() {
+        ["--list", pak] => list(Path::new(pak)),
+        [input, output] => pack(Path::new(input), Path::new(output), Compression::Deflate),
+        [input, output, "--store"] => pack(Path::new(input), Path::new(output), Compression::Store),
+        _ => Err(USAGE.to_string()),
+    };
+    match res {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn pack(input: &Path, output: &Path, method: Compression) -> Result<(), String> {
+    let mut w = PackWriter::new();
+    let count = w.add_dir_with(input, method).map_err(|e| format!("{}: {}", input.display(), e))?;
+    let bytes = w.finish();
+    // verified before writing, so a failed check leaves no pack behind
+    let len = bytes.len();
+    verify(input, bytes.clone())?;
+    std::fs::write(output, &bytes).map_err(|e| format!("{}: {}", output.display(), e))?;
+    println!("packed {} files into {} ({} bytes)", count, output.display(), len);
+    Ok(())
+}
+
+// Mount the pack image and compare every file (and the directory tree) with the input.
+fn verify(input: &Path, pack: Vec<u8>) -> Result<(), String> {
+    let backend = PackBackend::from_bytes(pack).map_err(|e| format!("verify failed: {}", e))?;
+    let mut vfs = Vfs::new();
+    vfs.mount("/", Box::new(backend));
+
+    let mut stack: Vec<(PathBuf, String)> = vec![(input.to_path_buf(), String::new())];
+    while let Some((host, vpath)) = stack.pop() {
+        let dir = if vpath.is_empty() { "/".to_string() } else { vpath.clone() };
+        // directories are not stored in the pack, so one holding no files is simply absent
+        let listed = match vfs.list(&dir) {
+            Ok(listed) => listed,
+            Err(VfsError::NotFound(_)) if !vpath.is_empty() => Vec::new(),
+            Err(e) => return Err(format!("verify failed: {}", e)),
+        };
+        let mut host_names: Vec<String> = Vec::new();
+        let entries = std::fs::read_dir(&host).map_err(|e| format!("{}: {}", host.display(), e))?;
+        for entry in entries.flatten() {
+            let Ok(name) = entry.file_name().into_string() else { continue };
+            let child = format!("{}/{}", vpath, name);
+            let Ok(ty) = entry.file_type() else { continue };
+            if ty.is_dir() {
+                host_names.push(name);
+                stack.push((entry.path(), child));
+            } else if ty.is_file() {
+                if !listed.contains(&name) {
+                    return Err(format!("verify failed: {} missing from listing", child));
+                }
+                host_names.push(name);
+                let expected = std::fs::read(entry.path()).map_err(|e| format!("{}: {}", entry.path().display(), e))?;
+                if vfs.read(&child).ok().as_deref() != Some(expected.as_slice()) {
+                    return Err(format!("verify failed: {} differs after packing", child));
+                }
+            }
+        }
+        // empty directories are not listed, so only check the listing has nothing extra
+        if listed.iter().any(|n| !host_names.contains(n)) {
+            return Err(format!("verify failed: listing of {} differs after packing", dir));
+        }
+    }
+    Ok(())
+}
+
+fn list(pak: &Path) -> Result<(), String> {
+    let backend = PackBackend::open(pak).map_err(|e| e.to_string())?;
+    for path in backend.paths() {
+        if let Some((method, stored, size)) = backend.entry_info(path) {
+            println!("{:>10} {:>10} {:?}\t{}", size, stored, method, path);
+        }
+    }
+    println!("{} files", backend.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn packs_trees_with_empty_directories() {
+        let dir = std::env::temp_dir().join(format!("aubrey_pack_{}", std::process::id()));
+        let input = dir.join("in");
+        std::fs::create_dir_all(input.join("empty")).unwrap();
+        std::fs::create_dir_all(input.join("sub/nested_empty")).unwrap();
+        std::fs::write(input.join("sub/a.txt"), "hi").unwrap();
+        std::fs::write(input.join("top.txt"), "top").unwrap();
+        let output = dir.join("out.pak");
+
+        pack(&input, &output, Compression::Deflate).unwrap();
+        let mut vfs = Vfs::new();
+        vfs.mount("/", Box::new(PackBackend::open(&output).unwrap()));
+        assert_eq!(vfs.read("/sub/a.txt").unwrap(), b"hi");
+        assert_eq!(vfs.list("/").unwrap(), ["sub", "top.txt"]);
+        assert!(!vfs.exists("/empty"));
+
+        // a pack that does not match its input is rejected
+        let mut w = PackWriter::new();
+        w.add("/top.txt", b"changed");
+        assert!(verify(&input, w.finish()).unwrap_err().contains("differs"));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
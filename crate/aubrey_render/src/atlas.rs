@@ -0,0 +1,216 @@
+use std::collections::HashMap;
+
+use ab_glyph::{Font, FontRef, PxScale, ScaleFont, point};
+use aubrey_core::asset::{AssetLoader, BoxError, ImportSettings, Importer, LoadContext};
+
+use crate::raster::DrawContext;
+
+// .afnt layout (little endian):
+//   magic "AFNT", version u16, flags u16 (unused),
+//   size_px f32, ascent f32, descent f32, line_gap f32, width u32, height u32, glyph count u32,
+//   per glyph: char u32, x u16, y u16, w u16, h u16, bearing_x f32, bearing_y f32, advance f32,
+//   width * height coverage bytes
+const MAGIC: &[u8; 4] = b"AFNT";
+const VERSION: u16 = 1;
+const HEADER_LEN: usize = 36;
+const GLYPH_LEN: usize = 24;
+
+/// Placement of one glyph in a `GlyphAtlas`. Bearings are relative to the pen position on the baseline.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct AtlasGlyph {
+    pub x: u16,
+    pub y: u16,
+    pub w: u16,
+    pub h: u16,
+    pub bearing_x: f32,
+    /// Negative for ink above the baseline.
+    pub bearing_y: f32,
+    pub advance: f32,
+}
+
+/// Glyphs pre-rasterized at one pixel size into a single coverage (alpha) image.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GlyphAtlas {
+    pub size_px: f32,
+    pub ascent: f32,
+    /// Negative, below the baseline.
+    pub descent: f32,
+    pub line_gap: f32,
+    pub width: u32,
+    pub height: u32,
+    pub glyphs: HashMap<char, AtlasGlyph>,
+    /// `width * height` bytes, row-major.
+    pub coverage: Vec<u8>,
+}
+
+impl GlyphAtlas {
+    pub fn glyph(&self, ch: char) -> Option<&AtlasGlyph> { self.glyphs.get(&ch) }
+
+    pub fn line_height(&self) -> f32 { self.ascent - self.descent + self.line_gap }
+
+    /// Rasterize `chars` from `font` at `size_px`, leaving `padding` empty pixels around each glyph.
+    /// Characters the font lacks are skipped.
+    pub fn rasterize(font: &impl Font, size_px: f32, chars: impl IntoIterator<Item = char>, padding: u32) -> Self {
+        let scaled = font.as_scaled(PxScale::from(size_px));
+        let mut rasters = Vec::new();
+        for ch in chars {
+            let id = font.glyph_id(ch);
+            if id.0 == 0 && ch != '\0' { continue; }
+            let advance = scaled.h_advance(id);
+            let raster = match font.outline_glyph(id.with_scale_and_position(size_px, point(0.0, 0.0))) {
+                Some(outline) => {
+                    let bb = outline.px_bounds();
+                    let (w, h) = (bb.width() as u32, bb.height() as u32);
+                    let mut cov = vec![0u8; (w * h) as usize];
+                    outline.draw(|gx, gy, c| {
+                        if gx < w && gy < h { cov[(gy * w + gx) as usize] = (c.clamp(0.0, 1.0) * 255.0) as u8; }
+                    });
+                    (bb.min.x, bb.min.y, w, h, cov)
+                }
+                // whitespace: advance only
+                None => (0.0, 0.0, 0, 0, Vec::new()),
+            };
+            rasters.push((ch, advance, raster));
+        }
+
+        // shelf packing into a power-of-two wide image
+        let area: u32 = rasters.iter().map(|(_, _, r)| (r.2 + padding) * (r.3 + padding)).sum();
+        let widest = rasters.iter().map(|(_, _, r)| r.2 + padding * 2).max().unwrap_or(0);
+        let width = ((area as f32).sqrt().ceil() as u32).max(widest).max(16).next_power_of_two();
+        let mut order: Vec<usize> = (0..rasters.len()).collect();
+        order.sort_by_key(|&i| std::cmp::Reverse(rasters[i].2.3));
+        let (mut x, mut y, mut shelf) = (padding, padding, 0);
+        let mut places = vec![(0, 0); rasters.len()];
+        for &i in &order {
+            let (w, h) = (rasters[i].2.2, rasters[i].2.3);
+            if x + w + padding > width { x = padding; y += shelf + padding; shelf = 0; }
+            places[i] = (x, y);
+            x += w + padding;
+            shelf = shelf.max(h);
+        }
+        let height = (y + shelf + padding).max(1);
+
+        let mut coverage = vec![0u8; (width * height) as usize];
+        let mut glyphs = HashMap::new();
+        for ((ch, advance, (bx, by, w, h, cov)), (px, py)) in rasters.into_iter().zip(places) {
+            for row in 0..h {
+                let dst = ((py + row) * width + px) as usize;
+                coverage[dst..dst + w as usize].copy_from_slice(&cov[(row * w) as usize..((row + 1) * w) as usize]);
+            }
+            glyphs.insert(ch, AtlasGlyph { x: px as u16, y: py as u16, w: w as u16, h: h as u16, bearing_x: bx, bearing_y: by, advance });
+        }
+        Self { size_px, ascent: scaled.ascent(), descent: scaled.descent(), line_gap: scaled.line_gap(), width, height, glyphs, coverage }
+    }
+
+    /// Serialize as `.afnt`.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(HEADER_LEN + self.glyphs.len() * GLYPH_LEN + self.coverage.len());
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&VERSION.to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes());
+        for v in [self.size_px, self.ascent, self.descent, self.line_gap] { out.extend_from_slice(&v.to_le_bytes()); }
+        for v in [self.width, self.height, self.glyphs.len() as u32] { out.extend_from_slice(&v.to_le_bytes()); }
+        // sorted so identical input gives identical bytes
+        let mut chars: Vec<&char> = self.glyphs.keys().collect();
+        chars.sort();
+        for ch in chars {
+            let g = &self.glyphs[ch];
+            out.extend_from_slice(&(*ch as u32).to_le_bytes());
+            for v in [g.x, g.y, g.w, g.h] { out.extend_from_slice(&v.to_le_bytes()); }
+            for v in [g.bearing_x, g.bearing_y, g.advance] { out.extend_from_slice(&v.to_le_bytes()); }
+        }
+        out.extend_from_slice(&self.coverage);
+        out
+    }
+
+    /// Parse `.afnt` data. None if malformed.
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        if data.len() < HEADER_LEN || &data[..4] != MAGIC { return None; }
+        if u16::from_le_bytes([data[4], data[5]]) != VERSION { return None; }
+        let f32_at = |i: usize| data.get(i..i + 4).map(|b| f32::from_le_bytes(b.try_into().unwrap()));
+        let u32_at = |i: usize| data.get(i..i + 4).map(|b| u32::from_le_bytes(b.try_into().unwrap()));
+        let u16_at = |i: usize| data.get(i..i + 2).map(|b| u16::from_le_bytes([b[0], b[1]]));
+        let (width, height, count) = (u32_at(24)?, u32_at(28)?, u32_at(32)? as usize);
+        let mut glyphs = HashMap::with_capacity(count);
+        for n in 0..count {
+            let at = HEADER_LEN + n * GLYPH_LEN;
+            let ch = char::from_u32(u32_at(at)?)?;
+            let g = AtlasGlyph {
+                x: u16_at(at + 4)?, y: u16_at(at + 6)?, w: u16_at(at + 8)?, h: u16_at(at + 10)?,
+                bearing_x: f32_at(at + 12)?, bearing_y: f32_at(at + 16)?, advance: f32_at(at + 20)?,
+            };
+            if g.x as u32 + g.w as u32 > width || g.y as u32 + g.h as u32 > height { return None; }
+            glyphs.insert(ch, g);
+        }
+        let start = HEADER_LEN + count * GLYPH_LEN;
+        let len = (width as usize).checked_mul(height as usize)?;
+        let coverage = data.get(start..start.checked_add(len)?)?.to_vec();
+        Some(Self { size_px: f32_at(8)?, ascent: f32_at(12)?, descent: f32_at(16)?, line_gap: f32_at(20)?, width, height, glyphs, coverage })
+    }
+}
+
+// "32-126,169" -> chars
+fn parse_chars(spec: &str) -> Result<Vec<char>, BoxError> {
+    let mut out = Vec::new();
+    for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let (lo, hi) = part.split_once('-').unwrap_or((part, part));
+        let (lo, hi): (u32, u32) = (lo.trim().parse()?, hi.trim().parse()?);
+        if lo > hi { return Err(format!("empty character range: {}", part).into()); }
+        out.extend((lo..=hi).filter_map(char::from_u32));
+    }
+    Ok(out)
+}
+
+/// TTF/OTF -> `.afnt` glyph atlas.
+/// Settings: `size_px` (default 32), `chars` (decimal code point ranges, default `32-126`), `padding` (default 1).
+pub struct FontAtlasImporter;
+
+impl Importer for FontAtlasImporter {
+    fn name(&self) -> &str { "font_atlas" }
+    fn extensions(&self) -> &[&str] { &["ttf", "otf"] }
+    fn output_extension(&self) -> &str { "afnt" }
+    fn default_settings(&self) -> ImportSettings {
+        ImportSettings::new().with("size_px", 32).with("chars", "32-126").with("padding", 1)
+    }
+    fn import(&self, _path: &str, source: &[u8], settings: &ImportSettings) -> Result<Vec<u8>, BoxError> {
+        let font = FontRef::try_from_slice(source)?;
+        let size_px: f32 = settings.parse_or("size_px", 32.0);
+        if !(size_px > 0.0 && size_px <= 1024.0) { return Err(format!("size_px out of range: {}", size_px).into()); }
+        let chars = parse_chars(settings.str_or("chars", "32-126"))?;
+        let atlas = GlyphAtlas::rasterize(&font, size_px, chars, settings.parse_or("padding", 1));
+        if atlas.width > u16::MAX as u32 || atlas.height > u16::MAX as u32 { return Err("atlas too large; reduce size_px or chars".into()); }
+        Ok(atlas.encode())
+    }
+}
+
+/// `AssetLoader` for `.afnt` files produced by `FontAtlasImporter`.
+pub struct GlyphAtlasLoader;
+
+impl AssetLoader for GlyphAtlasLoader {
+    type Asset = GlyphAtlas;
+    fn extensions(&self) -> &[&str] { &["afnt"] }
+    fn load(&self, ctx: &mut LoadContext) -> Result<GlyphAtlas, BoxError> {
+        GlyphAtlas::decode(ctx.bytes()).ok_or_else(|| "invalid .afnt data".into())
+    }
+}
+
+/// Draw UTF-8 text from a pre-rasterized atlas, with the top-left of the first line at `pos`,
+/// inside `ctx`'s clip. Characters missing from the atlas fall back to `?`, or are skipped.
+pub fn draw_text_atlas(ctx: &mut DrawContext, pos: (i32, i32), text: &str, atlas: &GlyphAtlas, color: u32) {
+    let mut pen_x = pos.0 as f32;
+    let mut baseline = pos.1 as f32 + atlas.ascent;
+    for ch in text.chars() {
+        if ch == '\n' { pen_x = pos.0 as f32; baseline += atlas.line_height(); continue; }
+        let Some(g) = atlas.glyph(ch).or_else(|| atlas.glyph('?')) else { continue };
+        let gx = (pen_x + g.bearing_x).round() as i32;
+        let gy = (baseline + g.bearing_y).round() as i32;
+        for row in 0..g.h as usize {
+            let src = (g.y as usize + row) * atlas.width as usize + g.x as usize;
+            for col in 0..g.w as usize {
+                let a = atlas.coverage[src + col];
+                if a > 0 { ctx.blend_pixel(gx + col as i32, gy + row as i32, color, a); }
+            }
+        }
+        pen_x += g.advance;
+    }
+}
@@ -0,0 +1,80 @@
+use std::io;
+use std::path::Path;
+
+use aubrey_core::fs::{Vfs, VfsResult};
+use aubrey_core::image::{png, RgbaImage};
+
+use crate::raster::{premultiply, unpremultiply, DrawContext};
+
+/// Offscreen ARGB8888 render target. The drawing functions (`clear`, `draw_line`,
+/// `draw_rect_outline`, `draw_text`, ...) target it through `draw`, the same way they
+/// target a window through `with_frame`, so rendering works without a display.
+/// `context` gives a `DrawContext` for filled and anti-aliased shapes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Canvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<u32>,
+}
+
+impl Canvas {
+    /// Transparent black canvas.
+    pub fn new(width: usize, height: usize) -> Self { Self { width, height, pixels: vec![0; width * height] } }
+
+    pub fn width(&self) -> usize { self.width }
+
+    pub fn height(&self) -> usize { self.height }
+
+    /// Pixels per row in `pixels` (rows are tightly packed).
+    pub fn stride(&self) -> usize { self.width }
+
+    pub fn pixels(&self) -> &[u32] { &self.pixels }
+
+    pub fn pixels_mut(&mut self) -> &mut [u32] { &mut self.pixels }
+
+    pub fn pixel(&self, x: usize, y: usize) -> Option<u32> {
+        if x < self.width && y < self.height { Some(self.pixels[y * self.width + x]) } else { None }
+    }
+
+    /// Run `f` with (buf, width, height, stride), like `with_frame`.
+    pub fn draw<R>(&mut self, f: impl FnOnce(&mut [u32], usize, usize, usize) -> R) -> R {
+        f(&mut self.pixels, self.width, self.height, self.width)
+    }
+
+    /// Resize, keeping the overlapping top-left part; new pixels are transparent black.
+    pub fn resize(&mut self, width: usize, height: usize) {
+        if (width, height) == (self.width, self.height) { return; }
+        let mut pixels = vec![0; width * height];
+        for y in 0..height.min(self.height) {
+            let n = width.min(self.width);
+            pixels[y * width..y * width + n].copy_from_slice(&self.pixels[y * self.width..y * self.width + n]);
+        }
+        *self = Self { width, height, pixels };
+    }
+
+    /// Drawing context over the whole canvas.
+    pub fn context(&mut self) -> DrawContext<'_> { DrawContext::new(&mut self.pixels, self.width, self.height, self.width) }
+
+    /// Convert to straight-alpha RGBA8 bytes (pixels hold premultiplied alpha, see `DrawContext`).
+    pub fn to_image(&self) -> RgbaImage {
+        let mut pixels = Vec::with_capacity(self.pixels.len() * 4);
+        for &p in &self.pixels {
+            let p = unpremultiply(p);
+            pixels.extend_from_slice(&[(p >> 16) as u8, (p >> 8) as u8, p as u8, (p >> 24) as u8]);
+        }
+        RgbaImage { width: self.width as u32, height: self.height as u32, pixels }
+    }
+
+    pub fn from_image(image: &RgbaImage) -> Self {
+        let pixels = image.pixels.chunks_exact(4).map(|p| premultiply(crate::pack_rgba_u8(p[0], p[1], p[2], p[3]))).collect();
+        Self { width: image.width as usize, height: image.height as usize, pixels }
+    }
+
+    pub fn encode_png(&self) -> Vec<u8> { png::encode(&self.to_image()) }
+
+    /// Write as PNG into the `Vfs`.
+    pub fn save_png(&self, vfs: &mut Vfs, path: &str) -> VfsResult<()> { vfs.write(path, &self.encode_png()) }
+
+    /// Write as PNG to a host file (e.g. CI artifacts).
+    pub fn write_png(&self, path: impl AsRef<Path>) -> io::Result<()> { std::fs::write(path, self.encode_png()) }
+}
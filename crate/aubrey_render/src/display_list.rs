@@ -0,0 +1,612 @@
+use std::ops::Range;
+use std::sync::Arc;
+
+use aubrey_core::image::RgbaImage;
+
+use crate::raster::{clamp_radii, DrawContext};
+use crate::text::{self, FontAsset};
+
+/// Axis-aligned rectangle in pixels.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Rect {
+    pub x: f32,
+    pub y: f32,
+    pub w: f32,
+    pub h: f32,
+}
+
+impl Rect {
+    pub const fn new(x: f32, y: f32, w: f32, h: f32) -> Self { Self { x, y, w, h } }
+
+    pub fn right(&self) -> f32 { self.x + self.w }
+
+    pub fn bottom(&self) -> f32 { self.y + self.h }
+
+    pub fn is_empty(&self) -> bool { self.w <= 0.0 || self.h <= 0.0 }
+
+    pub fn contains(&self, x: f32, y: f32) -> bool { x >= self.x && y >= self.y && x < self.right() && y < self.bottom() }
+
+    /// Overlap of both rectangles; empty if they don't overlap.
+    pub fn intersect(&self, other: &Rect) -> Rect {
+        let (x, y) = (self.x.max(other.x), self.y.max(other.y));
+        Rect::new(x, y, (self.right().min(other.right()) - x).max(0.0), (self.bottom().min(other.bottom()) - y).max(0.0))
+    }
+}
+
+/// Uniform scale followed by a translation.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Transform {
+    pub scale: f32,
+    pub tx: f32,
+    pub ty: f32,
+}
+
+impl Default for Transform {
+    fn default() -> Self { Self::IDENTITY }
+}
+
+impl Transform {
+    pub const IDENTITY: Transform = Transform { scale: 1.0, tx: 0.0, ty: 0.0 };
+
+    pub fn translate(tx: f32, ty: f32) -> Self { Self { scale: 1.0, tx, ty } }
+
+    pub fn scale(scale: f32) -> Self { Self { scale, tx: 0.0, ty: 0.0 } }
+
+    /// `inner` applied first, then `self`.
+    pub fn compose(&self, inner: &Transform) -> Transform {
+        Transform { scale: self.scale * inner.scale, tx: self.tx + self.scale * inner.tx, ty: self.ty + self.scale * inner.ty }
+    }
+
+    pub fn point(&self, x: f32, y: f32) -> (f32, f32) { (x * self.scale + self.tx, y * self.scale + self.ty) }
+
+    pub fn rect(&self, r: &Rect) -> Rect {
+        let (x, y) = self.point(r.x, r.y);
+        Rect::new(x, y, r.w * self.scale, r.h * self.scale)
+    }
+}
+
+/// One display list entry. Colors are ARGB8888 like the drawing functions.
+#[derive(Clone)]
+pub enum DrawCmd {
+    /// Fill the current clip rect with `color`.
+    Clear(u32),
+    FillRect { rect: Rect, color: u32 },
+    /// Border of `width` pixels drawn inside `rect`.
+    StrokeRect { rect: Rect, width: f32, color: u32 },
+    /// Filled rectangle with corner radii (top-left, top-right, bottom-right, bottom-left).
+    RoundedRect { rect: Rect, radii: [f32; 4], color: u32 },
+    /// Border of `width` pixels inside a rounded rectangle.
+    StrokeRoundedRect { rect: Rect, radii: [f32; 4], width: f32, color: u32 },
+    /// Filled ellipse with radii (rx, ry).
+    Ellipse { center: (f32, f32), radii: (f32, f32), color: u32 },
+    Line { from: (f32, f32), to: (f32, f32), width: f32, color: u32 },
+    /// Text with the top-left of the first line at `pos`, as `draw_text`.
+    Text { pos: (f32, f32), text: String, font: FontAsset, size_px: f32, color: u32 },
+    /// Image (straight alpha) scaled to `rect`.
+    Image { rect: Rect, image: Arc<RgbaImage> },
+    /// Intersect the clip with `rect` (in the current transform) until the matching `PopClip`.
+    PushClip(Rect),
+    PopClip,
+    /// Apply the transform to everything until the matching `PopTransform`.
+    PushTransform(Transform),
+    PopTransform,
+}
+
+/// Retained list of drawing commands, built once per frame and consumed by a backend:
+/// `paint` rasterizes it into an ARGB buffer (window or `Canvas`), `render_display_list_wgpu`
+/// draws it with wgpu.
+///
+/// ```ignore
+/// let mut list = DisplayList::new();
+/// list.clear(pack_rgba_u8(0, 0, 0, 255))
+///     .push_clip(Rect::new(10.0, 10.0, 100.0, 20.0))
+///     .text((12.0, 12.0), "hello", &font, 16.0, pack_rgba_u8(255, 255, 255, 255))
+///     .pop_clip();
+/// canvas.draw(|buf, width, height, stride| list.paint(buf, width, height, stride));
+/// ```
+#[derive(Clone, Default)]
+pub struct DisplayList {
+    cmds: Vec<DrawCmd>,
+}
+
+impl DisplayList {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn cmds(&self) -> &[DrawCmd] { &self.cmds }
+
+    pub fn len(&self) -> usize { self.cmds.len() }
+
+    pub fn is_empty(&self) -> bool { self.cmds.is_empty() }
+
+    /// Remove all commands, keeping the allocation for the next frame.
+    pub fn reset(&mut self) { self.cmds.clear(); }
+
+    pub fn push(&mut self, cmd: DrawCmd) -> &mut Self { self.cmds.push(cmd); self }
+
+    pub fn clear(&mut self, color: u32) -> &mut Self { self.push(DrawCmd::Clear(color)) }
+
+    pub fn fill_rect(&mut self, rect: Rect, color: u32) -> &mut Self { self.push(DrawCmd::FillRect { rect, color }) }
+
+    pub fn stroke_rect(&mut self, rect: Rect, width: f32, color: u32) -> &mut Self { self.push(DrawCmd::StrokeRect { rect, width, color }) }
+
+    pub fn rounded_rect(&mut self, rect: Rect, radii: [f32; 4], color: u32) -> &mut Self { self.push(DrawCmd::RoundedRect { rect, radii, color }) }
+
+    pub fn stroke_rounded_rect(&mut self, rect: Rect, radii: [f32; 4], width: f32, color: u32) -> &mut Self {
+        self.push(DrawCmd::StrokeRoundedRect { rect, radii, width, color })
+    }
+
+    pub fn ellipse(&mut self, center: (f32, f32), radii: (f32, f32), color: u32) -> &mut Self { self.push(DrawCmd::Ellipse { center, radii, color }) }
+
+    pub fn circle(&mut self, center: (f32, f32), radius: f32, color: u32) -> &mut Self { self.ellipse(center, (radius, radius), color) }
+
+    pub fn line(&mut self, from: (f32, f32), to: (f32, f32), width: f32, color: u32) -> &mut Self { self.push(DrawCmd::Line { from, to, width, color }) }
+
+    pub fn text(&mut self, pos: (f32, f32), text: &str, font: &FontAsset, size_px: f32, color: u32) -> &mut Self {
+        self.push(DrawCmd::Text { pos, text: text.to_string(), font: font.clone(), size_px, color })
+    }
+
+    pub fn image(&mut self, rect: Rect, image: Arc<RgbaImage>) -> &mut Self { self.push(DrawCmd::Image { rect, image }) }
+
+    pub fn push_clip(&mut self, rect: Rect) -> &mut Self { self.push(DrawCmd::PushClip(rect)) }
+
+    pub fn pop_clip(&mut self) -> &mut Self { self.push(DrawCmd::PopClip) }
+
+    pub fn push_transform(&mut self, transform: Transform) -> &mut Self { self.push(DrawCmd::PushTransform(transform)) }
+
+    pub fn pop_transform(&mut self) -> &mut Self { self.push(DrawCmd::PopTransform) }
+
+    /// Walk the drawing commands with the transform and clip (device pixels, within `viewport`)
+    /// that apply to them. Push/pop commands are consumed here; unmatched pops are ignored, and
+    /// commands with an empty clip are skipped.
+    pub fn resolve(&self, viewport: Rect, mut f: impl FnMut(&DrawCmd, &Transform, &Rect)) {
+        let (mut transform, mut clip) = (Transform::IDENTITY, viewport);
+        let (mut transforms, mut clips) = (Vec::new(), Vec::new());
+        for cmd in &self.cmds {
+            match cmd {
+                DrawCmd::PushClip(r) => {
+                    clips.push(clip);
+                    clip = transform.rect(r).intersect(&clip);
+                }
+                DrawCmd::PopClip => { if let Some(c) = clips.pop() { clip = c; } }
+                DrawCmd::PushTransform(t) => {
+                    transforms.push(transform);
+                    transform = transform.compose(t);
+                }
+                DrawCmd::PopTransform => { if let Some(t) = transforms.pop() { transform = t; } }
+                _ if clip.is_empty() => {}
+                _ => f(cmd, &transform, &clip),
+            }
+        }
+    }
+
+    /// Rasterize into an ARGB8888 buffer, e.g. from `with_frame` or `Canvas::draw`, with `DrawContext`.
+    pub fn paint(&self, buf: &mut [u32], width: usize, height: usize, stride: usize) {
+        let mut ctx = DrawContext::new(buf, width, height, stride);
+        self.resolve(ctx.clip(), |cmd, t, clip| ctx.clipped(clip, |ctx| paint_cmd(ctx, cmd, t)));
+    }
+}
+
+fn paint_cmd(ctx: &mut DrawContext, cmd: &DrawCmd, t: &Transform) {
+    match cmd {
+        DrawCmd::Clear(color) => ctx.clear(*color),
+        DrawCmd::FillRect { rect, color } => ctx.fill_rect(&t.rect(rect), *color),
+        DrawCmd::StrokeRect { rect, width, color } => ctx.stroke_rect(&t.rect(rect), width * t.scale, *color),
+        DrawCmd::RoundedRect { rect, radii, color } => ctx.fill_rounded_rect(&t.rect(rect), radii.map(|v| v * t.scale), *color),
+        DrawCmd::StrokeRoundedRect { rect, radii, width, color } => {
+            ctx.stroke_rounded_rect(&t.rect(rect), radii.map(|v| v * t.scale), width * t.scale, *color)
+        }
+        DrawCmd::Ellipse { center, radii, color } => ctx.fill_ellipse(t.point(center.0, center.1), (radii.0 * t.scale, radii.1 * t.scale), *color),
+        DrawCmd::Line { from, to, width, color } => ctx.line(t.point(from.0, from.1), t.point(to.0, to.1), width * t.scale, *color),
+        DrawCmd::Text { pos, text, font, size_px, color } => ctx.text(t.point(pos.0, pos.1), text, font, size_px * t.scale, *color),
+        DrawCmd::Image { rect, image } => ctx.image(&t.rect(rect), image),
+        DrawCmd::PushClip(_) | DrawCmd::PopClip | DrawCmd::PushTransform(_) | DrawCmd::PopTransform => {}
+    }
+}
+
+// Pixel range covered by `r` (pixel centers inside), clamped to the buffer.
+fn pixel_span(r: &Rect, width: usize, height: usize) -> (Range<usize>, Range<usize>) {
+    let span = |lo: f32, hi: f32, max: usize| (lo.round().max(0.0) as usize).min(max)..(hi.round().max(0.0) as usize).min(max);
+    (span(r.x, r.right(), width), span(r.y, r.bottom(), height))
+}
+
+// Top, bottom, left and right bands of a border `bw` wide inside `r`.
+fn border_rects(r: &Rect, bw: f32) -> [Rect; 4] {
+    let bw = bw.min(r.w / 2.0).min(r.h / 2.0).max(0.0);
+    [
+        Rect::new(r.x, r.y, r.w, bw),
+        Rect::new(r.x, r.bottom() - bw, r.w, bw),
+        Rect::new(r.x, r.y + bw, bw, r.h - 2.0 * bw),
+        Rect::new(r.right() - bw, r.y + bw, bw, r.h - 2.0 * bw),
+    ]
+}
+
+// ===== triangles for the wgpu backend =====
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub(crate) struct Vertex {
+    pub pos: [f32; 2],
+    pub uv: [f32; 2],
+    pub color: [f32; 4],
+}
+
+/// Indices drawn with one texture and scissor rect (x, y, w, h).
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Batch {
+    pub texture: usize,
+    pub scissor: [u32; 4],
+    pub indices: Range<u32>,
+}
+
+/// A display list as textured triangles. Texture 0 is a white pixel for untextured shapes;
+/// text runs are rasterized into white coverage masks tinted by the vertex color.
+pub(crate) struct Mesh {
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+    pub textures: Vec<Arc<RgbaImage>>,
+    pub batches: Vec<Batch>,
+    /// Color of a leading full-viewport `Clear`, for the render pass load op.
+    pub clear: Option<[f32; 4]>,
+}
+
+const ARC_SEGMENTS: usize = 8;
+const ELLIPSE_SEGMENTS: usize = 48;
+
+// Outline of a rounded rect, clockwise from the top-left corner. Every corner contributes
+// the same number of points (repeated for square corners) so outlines can be stitched.
+fn rounded_outline(r: &Rect, radii: [f32; 4]) -> Vec<(f32, f32)> {
+    let [tl, tr, br, bl] = radii;
+    let corners = [(r.x + tl, r.y + tl, tl, 180.0f32), (r.right() - tr, r.y + tr, tr, 270.0), (r.right() - br, r.bottom() - br, br, 0.0), (r.x + bl, r.bottom() - bl, bl, 90.0)];
+    let mut points = Vec::with_capacity(4 * (ARC_SEGMENTS + 1));
+    for (cx, cy, rad, start) in corners {
+        for i in 0..=ARC_SEGMENTS {
+            let a = (start + 90.0 * i as f32 / ARC_SEGMENTS as f32).to_radians();
+            points.push((cx + rad * a.cos(), cy + rad * a.sin()));
+        }
+    }
+    points
+}
+
+fn color_f32(c: u32) -> [f32; 4] {
+    [(c >> 16) as u8, (c >> 8) as u8, c as u8, (c >> 24) as u8].map(|v| v as f32 / 255.0)
+}
+
+impl Mesh {
+    pub fn build(list: &DisplayList, width: u32, height: u32) -> Mesh {
+        let white = RgbaImage { width: 1, height: 1, pixels: vec![255; 4] };
+        let mut mesh = Mesh { vertices: Vec::new(), indices: Vec::new(), textures: vec![Arc::new(white)], batches: Vec::new(), clear: None };
+        let viewport = Rect::new(0.0, 0.0, width as f32, height as f32);
+        list.resolve(viewport, |cmd, t, clip| {
+            let (xs, ys) = pixel_span(clip, width as usize, height as usize);
+            if xs.is_empty() || ys.is_empty() { return; }
+            let scissor = [xs.start as u32, ys.start as u32, xs.len() as u32, ys.len() as u32];
+            mesh.add(cmd, t, clip, scissor, viewport);
+        });
+        mesh
+    }
+
+    fn add(&mut self, cmd: &DrawCmd, t: &Transform, clip: &Rect, scissor: [u32; 4], viewport: Rect) {
+        const NO_UV: [f32; 4] = [0.0; 4];
+        match cmd {
+            DrawCmd::Clear(color) => {
+                if self.indices.is_empty() && *clip == viewport {
+                    self.clear = Some(color_f32(*color));
+                } else {
+                    self.quad(0, scissor, clip, NO_UV, *color);
+                }
+            }
+            DrawCmd::FillRect { rect, color } => self.quad(0, scissor, &t.rect(rect), NO_UV, *color),
+            DrawCmd::StrokeRect { rect, width, color } => {
+                for r in border_rects(&t.rect(rect), (width * t.scale).max(1.0)) { self.quad(0, scissor, &r, NO_UV, *color); }
+            }
+            DrawCmd::RoundedRect { rect, radii, color } => {
+                let r = t.rect(rect);
+                self.fan(scissor, &rounded_outline(&r, clamp_radii(&r, radii.map(|v| v * t.scale))), *color);
+            }
+            DrawCmd::StrokeRoundedRect { rect, radii, width, color } => {
+                let (r, width) = (t.rect(rect), width * t.scale);
+                if width <= 0.0 { return; }
+                let radii = clamp_radii(&r, radii.map(|v| v * t.scale));
+                let inner = Rect::new(r.x + width, r.y + width, r.w - 2.0 * width, r.h - 2.0 * width);
+                if inner.is_empty() {
+                    self.fan(scissor, &rounded_outline(&r, radii), *color);
+                } else {
+                    self.ring(scissor, &rounded_outline(&r, radii), &rounded_outline(&inner, radii.map(|v| (v - width).max(0.0))), *color);
+                }
+            }
+            DrawCmd::Ellipse { center, radii, color } => {
+                let ((cx, cy), (rx, ry)) = (t.point(center.0, center.1), (radii.0 * t.scale, radii.1 * t.scale));
+                if rx <= 0.0 || ry <= 0.0 { return; }
+                let points: Vec<(f32, f32)> = (0..ELLIPSE_SEGMENTS)
+                    .map(|i| (i as f32 / ELLIPSE_SEGMENTS as f32 * std::f32::consts::TAU).sin_cos())
+                    .map(|(sin, cos)| (cx + rx * cos, cy + ry * sin))
+                    .collect();
+                self.fan(scissor, &points, *color);
+            }
+            DrawCmd::Line { from, to, width, color } => {
+                let (a, b) = (t.point(from.0, from.1), t.point(to.0, to.1));
+                let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+                let len = (dx * dx + dy * dy).sqrt();
+                if len == 0.0 { return; }
+                let half = (width * t.scale).max(1.0) / 2.0;
+                let (nx, ny) = (-dy / len * half, dx / len * half);
+                self.fan(scissor, &[(a.0 + nx, a.1 + ny), (b.0 + nx, b.1 + ny), (b.0 - nx, b.1 - ny), (a.0 - nx, a.1 - ny)], *color);
+            }
+            DrawCmd::Text { pos, text, font, size_px, color } => {
+                let (x, y) = t.point(pos.0, pos.1);
+                let Some(((ox, oy), mask)) = text::text_mask(text, font, size_px * t.scale) else { return };
+                let r = Rect::new((x as i32 + ox) as f32, (y as i32 + oy) as f32, mask.width as f32, mask.height as f32);
+                self.textures.push(Arc::new(mask));
+                self.quad(self.textures.len() - 1, scissor, &r, [0.0, 0.0, 1.0, 1.0], *color);
+            }
+            DrawCmd::Image { rect, image } => {
+                if image.width == 0 || image.height == 0 { return; }
+                let texture = match self.textures.iter().position(|i| Arc::ptr_eq(i, image)) {
+                    Some(i) => i,
+                    None => { self.textures.push(image.clone()); self.textures.len() - 1 }
+                };
+                self.quad(texture, scissor, &t.rect(rect), [0.0, 0.0, 1.0, 1.0], 0xFFFF_FFFF);
+            }
+            DrawCmd::PushClip(_) | DrawCmd::PopClip | DrawCmd::PushTransform(_) | DrawCmd::PopTransform => {}
+        }
+    }
+
+    // Continue the last batch if texture and scissor match.
+    fn batch(&mut self, texture: usize, scissor: [u32; 4]) {
+        let end = self.indices.len() as u32;
+        match self.batches.last_mut() {
+            Some(b) if b.texture == texture && b.scissor == scissor => {}
+            _ => self.batches.push(Batch { texture, scissor, indices: end..end }),
+        }
+    }
+
+    fn finish(&mut self) {
+        if let Some(b) = self.batches.last_mut() { b.indices.end = self.indices.len() as u32; }
+    }
+
+    fn quad(&mut self, texture: usize, scissor: [u32; 4], r: &Rect, uv: [f32; 4], color: u32) {
+        if r.is_empty() { return; }
+        self.batch(texture, scissor);
+        let (base, color) = (self.vertices.len() as u32, color_f32(color));
+        let [u0, v0, u1, v1] = uv;
+        for (pos, uv) in [([r.x, r.y], [u0, v0]), ([r.right(), r.y], [u1, v0]), ([r.right(), r.bottom()], [u1, v1]), ([r.x, r.bottom()], [u0, v1])] {
+            self.vertices.push(Vertex { pos, uv, color });
+        }
+        self.indices.extend_from_slice(&[base, base + 1, base + 2, base, base + 2, base + 3]);
+        self.finish();
+    }
+
+    // Band between two closed outlines with the same number of points, untextured.
+    fn ring(&mut self, scissor: [u32; 4], outer: &[(f32, f32)], inner: &[(f32, f32)], color: u32) {
+        let n = outer.len().min(inner.len()) as u32;
+        if n < 3 { return; }
+        self.batch(0, scissor);
+        let (base, color) = (self.vertices.len() as u32, color_f32(color));
+        for &(x, y) in outer[..n as usize].iter().chain(&inner[..n as usize]) { self.vertices.push(Vertex { pos: [x, y], uv: [0.0, 0.0], color }); }
+        for i in 0..n {
+            let j = (i + 1) % n;
+            self.indices.extend_from_slice(&[base + i, base + j, base + n + j, base + i, base + n + j, base + n + i]);
+        }
+        self.finish();
+    }
+
+    // Convex polygon as a triangle fan, untextured.
+    fn fan(&mut self, scissor: [u32; 4], points: &[(f32, f32)], color: u32) {
+        if points.len() < 3 { return; }
+        self.batch(0, scissor);
+        let (base, color) = (self.vertices.len() as u32, color_f32(color));
+        self.vertices.extend(points.iter().map(|&(x, y)| Vertex { pos: [x, y], uv: [0.0, 0.0], color }));
+        for i in 1..points.len() as u32 - 1 { self.indices.extend_from_slice(&[base, base + i, base + i + 1]); }
+        self.finish();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: u32 = 0xFFFF_0000;
+
+    fn build(w: u32, h: u32, f: impl FnOnce(&mut DisplayList)) -> Mesh {
+        let mut list = DisplayList::new();
+        f(&mut list);
+        let mesh = Mesh::build(&list, w, h);
+        // batches cover the indices in order, and every index is in range
+        let mut next = 0;
+        for b in &mesh.batches {
+            assert_eq!(b.indices.start, next);
+            assert!(b.indices.end > b.indices.start && b.texture < mesh.textures.len());
+            next = b.indices.end;
+        }
+        assert_eq!(next as usize, mesh.indices.len());
+        assert!(mesh.indices.iter().all(|&i| (i as usize) < mesh.vertices.len()));
+        mesh
+    }
+
+    fn area(mesh: &Mesh) -> f32 {
+        mesh.indices.chunks_exact(3).map(|t| {
+            let [a, b, c] = [t[0], t[1], t[2]].map(|i| mesh.vertices[i as usize].pos);
+            ((b[0] - a[0]) * (c[1] - a[1]) - (c[0] - a[0]) * (b[1] - a[1])).abs() / 2.0
+        }).sum()
+    }
+
+    fn bounds(mesh: &Mesh) -> Rect {
+        let (mut x0, mut y0, mut x1, mut y1) = (f32::MAX, f32::MAX, f32::MIN, f32::MIN);
+        for v in &mesh.vertices {
+            (x0, y0, x1, y1) = (x0.min(v.pos[0]), y0.min(v.pos[1]), x1.max(v.pos[0]), y1.max(v.pos[1]));
+        }
+        Rect::new(x0, y0, x1 - x0, y1 - y0)
+    }
+
+    fn close(a: f32, b: f32) -> bool { (a - b).abs() < 1e-3 }
+
+    fn close_rect(a: Rect, b: Rect) -> bool { close(a.x, b.x) && close(a.y, b.y) && close(a.w, b.w) && close(a.h, b.h) }
+
+    #[test]
+    fn leading_clear_becomes_the_load_op() {
+        let mesh = build(8, 8, |l| { l.clear(0xFF00_FF00).fill_rect(Rect::new(1.0, 1.0, 2.0, 2.0), RED); });
+        assert_eq!(mesh.clear, Some([0.0, 1.0, 0.0, 1.0]));
+        assert_eq!(mesh.indices.len(), 6);
+        // later or clipped clears are quads over the clip
+        let mesh = build(8, 8, |l| { l.fill_rect(Rect::new(1.0, 1.0, 2.0, 2.0), RED).clear(RED); });
+        assert_eq!(mesh.clear, None);
+        assert_eq!(mesh.indices.len(), 12);
+        let mesh = build(8, 8, |l| { l.push_clip(Rect::new(2.0, 2.0, 3.0, 3.0)).clear(RED).pop_clip(); });
+        assert_eq!(mesh.clear, None);
+        assert!(close_rect(bounds(&mesh), Rect::new(2.0, 2.0, 3.0, 3.0)));
+        assert_eq!(mesh.batches[0].scissor, [2, 2, 3, 3]);
+    }
+
+    #[test]
+    fn rects_and_colors() {
+        let mesh = build(16, 16, |l| { l.fill_rect(Rect::new(1.0, 2.0, 3.0, 4.0), 0x8000_00FF); });
+        let pos: Vec<[f32; 2]> = mesh.vertices.iter().map(|v| v.pos).collect();
+        assert_eq!(pos, [[1.0, 2.0], [4.0, 2.0], [4.0, 6.0], [1.0, 6.0]]);
+        assert_eq!(mesh.vertices[0].color, [0.0, 0.0, 1.0, 128.0 / 255.0]);
+        assert_eq!(mesh.batches, [Batch { texture: 0, scissor: [0, 0, 16, 16], indices: 0..6 }]);
+        // a stroke is four bands inside the rect
+        let mesh = build(16, 16, |l| { l.stroke_rect(Rect::new(2.0, 2.0, 10.0, 8.0), 2.0, RED); });
+        assert!(close(area(&mesh), 10.0 * 8.0 - 6.0 * 4.0));
+        assert!(close_rect(bounds(&mesh), Rect::new(2.0, 2.0, 10.0, 8.0)));
+        // empty rects add nothing
+        assert!(build(16, 16, |l| { l.fill_rect(Rect::new(1.0, 1.0, 0.0, 5.0), RED); }).indices.is_empty());
+    }
+
+    #[test]
+    fn rounded_rects_follow_their_radii() {
+        let r = Rect::new(2.0, 3.0, 20.0, 10.0);
+        let mesh = build(32, 32, |l| { l.rounded_rect(r, [4.0; 4], RED); });
+        assert_eq!(mesh.vertices.len(), 4 * (ARC_SEGMENTS + 1));
+        assert!(close_rect(bounds(&mesh), r));
+        let expected = 200.0 - (4.0 - std::f32::consts::PI) * 16.0;
+        assert!((area(&mesh) - expected).abs() < 0.03 * 16.0, "area {}", area(&mesh));
+
+        // square corners are the plain rect; oversized radii are clamped to half the short side
+        let square = build(32, 32, |l| { l.rounded_rect(r, [0.0; 4], RED); });
+        assert!(close(area(&square), 200.0));
+        let pill = build(32, 32, |l| { l.rounded_rect(r, [50.0; 4], RED); });
+        assert!(close_rect(bounds(&pill), r));
+        assert!((area(&pill) - (100.0 + std::f32::consts::PI * 25.0)).abs() < 0.03 * 100.0);
+        // per-corner radii: only the top-left one is round
+        let one = build(32, 32, |l| { l.rounded_rect(r, [4.0, 0.0, 0.0, 0.0], RED); });
+        assert!((area(&one) - (200.0 - (1.0 - std::f32::consts::PI / 4.0) * 16.0)).abs() < 0.01 * 16.0);
+        assert!(one.vertices.iter().any(|v| v.pos == [22.0, 3.0]) && !one.vertices.iter().any(|v| v.pos == [2.0, 3.0]));
+    }
+
+    #[test]
+    fn stroked_rounded_rects_are_rings() {
+        let r = Rect::new(0.0, 0.0, 20.0, 20.0);
+        let ring = build(32, 32, |l| { l.stroke_rounded_rect(r, [5.0; 4], 2.0, RED); });
+        let outer = build(32, 32, |l| { l.rounded_rect(r, [5.0; 4], RED); });
+        let inner = build(32, 32, |l| { l.rounded_rect(Rect::new(2.0, 2.0, 16.0, 16.0), [3.0; 4], RED); });
+        assert!(close(area(&ring), area(&outer) - area(&inner)));
+        assert_eq!(ring.vertices.len(), 2 * 4 * (ARC_SEGMENTS + 1));
+        // too wide for a hole: filled
+        let full = build(32, 32, |l| { l.stroke_rounded_rect(r, [5.0; 4], 10.0, RED); });
+        assert!(close(area(&full), area(&outer)));
+        assert!(build(32, 32, |l| { l.stroke_rounded_rect(r, [5.0; 4], 0.0, RED); }).indices.is_empty());
+    }
+
+    #[test]
+    fn ellipses_and_lines() {
+        let mesh = build(32, 32, |l| { l.ellipse((10.0, 8.0), (6.0, 3.0), RED); });
+        assert_eq!(mesh.vertices.len(), ELLIPSE_SEGMENTS);
+        assert!(mesh.vertices.iter().all(|v| close(((v.pos[0] - 10.0) / 6.0).powi(2) + ((v.pos[1] - 8.0) / 3.0).powi(2), 1.0)));
+        assert!((area(&mesh) - std::f32::consts::PI * 18.0).abs() < 0.01 * std::f32::consts::PI * 18.0);
+        assert!(build(32, 32, |l| { l.circle((4.0, 4.0), 0.0, RED); }).indices.is_empty());
+
+        let line = build(32, 32, |l| { l.line((2.0, 5.0), (12.0, 5.0), 4.0, RED); });
+        assert!(close_rect(bounds(&line), Rect::new(2.0, 3.0, 10.0, 4.0)));
+        // thinner than a pixel is widened to one
+        let hair = build(32, 32, |l| { l.line((0.0, 0.0), (3.0, 4.0), 0.1, RED); });
+        assert!(close(area(&hair), 5.0));
+        assert!(build(32, 32, |l| { l.line((3.0, 3.0), (3.0, 3.0), 2.0, RED); }).indices.is_empty());
+    }
+
+    #[test]
+    fn transforms_compose_and_pop() {
+        let mesh = build(64, 64, |l| {
+            l.push_transform(Transform::translate(10.0, 20.0))
+                .push_transform(Transform::scale(2.0))
+                .fill_rect(Rect::new(1.0, 1.0, 3.0, 2.0), RED)
+                .rounded_rect(Rect::new(0.0, 0.0, 4.0, 4.0), [2.0; 4], RED)
+                .pop_transform()
+                .fill_rect(Rect::new(0.0, 0.0, 1.0, 1.0), RED)
+                .pop_transform()
+                .pop_transform()
+                .fill_rect(Rect::new(0.0, 0.0, 1.0, 1.0), RED);
+        });
+        let pos = |i: usize| mesh.vertices[i].pos;
+        assert_eq!((pos(0), pos(2)), ([12.0, 22.0], [18.0, 26.0]));
+        // radii scale too: a circle of radius 4 at (14, 24)
+        let circle = &mesh.vertices[4..4 + 4 * (ARC_SEGMENTS + 1)];
+        assert!(circle.iter().all(|v| close((v.pos[0] - 14.0).hypot(v.pos[1] - 24.0), 4.0)));
+        let n = mesh.vertices.len();
+        assert_eq!((mesh.vertices[n - 8].pos, mesh.vertices[n - 6].pos), ([10.0, 20.0], [11.0, 21.0]));
+        assert_eq!((mesh.vertices[n - 4].pos, mesh.vertices[n - 2].pos), ([0.0, 0.0], [1.0, 1.0]));
+        // stroke widths scale with the transform
+        let stroke = build(64, 64, |l| { l.push_transform(Transform::scale(2.0)).stroke_rect(Rect::new(0.0, 0.0, 10.0, 10.0), 1.0, RED); });
+        assert!(close(area(&stroke), 400.0 - 16.0 * 16.0));
+    }
+
+    #[test]
+    fn clips_become_scissors() {
+        let mesh = build(32, 32, |l| {
+            l.fill_rect(Rect::new(0.0, 0.0, 4.0, 4.0), RED)
+                .fill_rect(Rect::new(4.0, 0.0, 4.0, 4.0), RED)
+                .push_clip(Rect::new(2.5, 3.0, 10.0, 40.0))
+                .fill_rect(Rect::new(0.0, 0.0, 8.0, 8.0), RED)
+                .push_transform(Transform::translate(4.0, 4.0))
+                // clip rects are in the current transform and nest
+                .push_clip(Rect::new(0.0, 0.0, 2.0, 2.0))
+                .ellipse((0.0, 0.0), (3.0, 3.0), RED)
+                .pop_clip()
+                .pop_transform()
+                .pop_clip()
+                .pop_clip()
+                .fill_rect(Rect::new(0.0, 0.0, 1.0, 1.0), RED);
+        });
+        let batches: Vec<([u32; 4], u32)> = mesh.batches.iter().map(|b| (b.scissor, b.indices.len() as u32)).collect();
+        // geometry isn't cut, only the scissor rect changes; same-scissor runs share a batch
+        assert_eq!(batches, [([0, 0, 32, 32], 12), ([3, 3, 10, 29], 6), ([4, 4, 2, 2], 3 * (ELLIPSE_SEGMENTS as u32 - 2)), ([0, 0, 32, 32], 6)]);
+        assert!(close_rect(Rect::new(mesh.vertices[8].pos[0], mesh.vertices[8].pos[1], 8.0, 8.0), Rect::new(0.0, 0.0, 8.0, 8.0)));
+
+        // nothing inside an empty or off-screen clip is emitted
+        let mesh = build(32, 32, |l| {
+            l.push_clip(Rect::new(40.0, 0.0, 5.0, 5.0)).fill_rect(Rect::new(0.0, 0.0, 50.0, 50.0), RED).pop_clip();
+            l.push_clip(Rect::new(0.0, 0.0, 5.0, 5.0)).push_clip(Rect::new(6.0, 6.0, 5.0, 5.0)).clear(RED).pop_clip().pop_clip();
+            l.push_clip(Rect::new(1.0, 1.0, 0.2, 0.2)).fill_rect(Rect::new(0.0, 0.0, 5.0, 5.0), RED).pop_clip();
+        });
+        assert!(mesh.indices.is_empty() && mesh.batches.is_empty() && mesh.clear.is_none());
+    }
+
+    #[test]
+    fn images_and_text_are_textured_quads() {
+        let image = Arc::new(RgbaImage::new(2, 2));
+        let font = FontAsset::from_bytes(text::noto_sans_regular().to_vec()).unwrap();
+        let mesh = build(64, 64, |l| {
+            l.image(Rect::new(0.0, 0.0, 4.0, 4.0), image.clone())
+                .image(Rect::new(4.0, 0.0, 4.0, 4.0), image.clone())
+                .fill_rect(Rect::new(0.0, 0.0, 1.0, 1.0), RED)
+                .text((10.0, 10.0), "Hi", &font, 16.0, RED)
+                .text((10.0, 10.0), " ", &font, 16.0, RED)
+                .image(Rect::new(0.0, 0.0, 4.0, 4.0), Arc::new(RgbaImage::new(0, 0)));
+        });
+        // the same image is uploaded once; blank text adds nothing
+        assert_eq!(mesh.textures.len(), 3);
+        assert!(Arc::ptr_eq(&mesh.textures[1], &image));
+        let batches: Vec<(usize, u32)> = mesh.batches.iter().map(|b| (b.texture, b.indices.len() as u32)).collect();
+        assert_eq!(batches, [(1, 12), (0, 6), (2, 6)]);
+        assert_eq!(mesh.vertices[0].color, [1.0; 4]);
+        assert_eq!((mesh.vertices[0].uv, mesh.vertices[2].uv), ([0.0, 0.0], [1.0, 1.0]));
+
+        let mask = &mesh.textures[2];
+        let text = &mesh.vertices[12..16];
+        assert!(close(text[2].pos[0] - text[0].pos[0], mask.width as f32) && close(text[2].pos[1] - text[0].pos[1], mask.height as f32));
+        assert!(text[0].pos[0] >= 10.0 && text[0].pos[1] >= 10.0);
+        assert_eq!(text[0].color, [1.0, 0.0, 0.0, 1.0]);
+        // scaled text is rasterized at the device size
+        let big = build(64, 64, |l| { l.push_transform(Transform::scale(2.0)).text((0.0, 0.0), "Hi", &font, 16.0, RED); });
+        assert!(big.textures[1].height > mask.height);
+    }
+}
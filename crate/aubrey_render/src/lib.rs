@@ -8,9 +8,12 @@ use std::cell::RefCell;
 use std::collections::HashMap;
 // text rendering is in text.rs
 
-// ===== wgpu backend for placeholder rendering =====
+// ===== wgpu backend for placeholders and display lists =====
 mod wgpu_backend {
     use super::*;
+    use aubrey_core::image::RgbaImage;
+    use wgpu::util::DeviceExt;
+    use crate::display_list::{Mesh, Vertex};
 
     pub struct GpuState {
         pub surface: wgpu::Surface<'static>,
@@ -22,6 +25,8 @@ mod wgpu_backend {
         pub uniform_buf: wgpu::Buffer,
         pub bind_group: wgpu::BindGroup,
         pub bg_layout_label: &'static str,
+        /// Display list pipeline, created on first use.
+        pub list: Option<ListPipeline>,
     }
 
     #[repr(C)]
@@ -122,77 +127,86 @@ fn fs_main(in: VsOut) -> @location(0) vec4<f32> {
         (pipeline, bind_group_layout)
     }
 
-    pub fn render_placeholder(win: Entity, color: [f32; 4]) -> Option<()> {
-        // get or init state
-        super::with_window(win, |window| {
-            // SAFETY: we extend window lifetime for surface; wgpu expects 'static surface. We ensure drop after window is alive for the app lifetime.
-            let window_static: &'static Window = unsafe { std::mem::transmute::<&Window, &'static Window>(window) };
-            let size = window.inner_size();
-            let width = size.width.max(1);
-            let height = size.height.max(1);
-
-            let state = super::GPU_STATES.with(|cell| {
-                let mut map = cell.borrow_mut();
-                if let Some(state) = map.get(&win) { return state as *const GpuState as usize; }
-
-                let instance = wgpu::Instance::default();
-                let surface = instance.create_surface(window_static).expect("create surface");
-                let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
-                    power_preference: wgpu::PowerPreference::HighPerformance,
-                    compatible_surface: Some(&surface), force_fallback_adapter: false,
-                })).expect("request adapter");
-                let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
-                    label: Some("device"),
-                    required_features: wgpu::Features::empty(),
-                    required_limits: wgpu::Limits::downlevel_defaults(),
-                }, None)).expect("request device");
-                let surface_caps = surface.get_capabilities(&adapter);
-                let format = surface_caps.formats.iter().copied().find(|f| f.is_srgb()).unwrap_or(surface_caps.formats[0]);
-                let config = wgpu::SurfaceConfiguration {
-                    usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-                    format,
-                    width,
-                    height,
-                    present_mode: wgpu::PresentMode::Fifo,
-                    alpha_mode: surface_caps.alpha_modes[0],
-                    view_formats: vec![],
-                    desired_maximum_frame_latency: 2,
-                };
-                surface.configure(&device, &config);
-
-                let (pipeline, bgl) = create_pipeline(&device, config.format);
-                let uniform_buf = device.create_buffer(&wgpu::BufferDescriptor {
-                    label: Some("params-ubo"),
-                    size: std::mem::size_of::<Params>() as u64,
-                    usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-                    mapped_at_creation: false,
-                });
-                let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-                    label: Some("params-bg"),
-                    layout: &bgl,
-                    entries: &[wgpu::BindGroupEntry { binding: 0, resource: uniform_buf.as_entire_binding() }],
-                });
-                let new_state = GpuState { surface, device, queue, config, pipeline, bind_group_layout: bgl, uniform_buf, bind_group, bg_layout_label: "params-bgl" };
-                map.insert(win, new_state);
-                map.get(&win).unwrap() as *const GpuState as usize
+    // Surface and device for `win`, created on first use and reconfigured on resize.
+    fn state_for(win: Entity, window: &Window) -> Option<&'static mut GpuState> {
+        // SAFETY: we extend window lifetime for surface; wgpu expects 'static surface. We ensure drop after window is alive for the app lifetime.
+        let window_static: &'static Window = unsafe { std::mem::transmute::<&Window, &'static Window>(window) };
+        let size = window.inner_size();
+        let width = size.width.max(1);
+        let height = size.height.max(1);
+
+        let state = super::GPU_STATES.with(|cell| {
+            let mut map = cell.borrow_mut();
+            if let Some(state) = map.get(&win) { return state as *const GpuState as usize; }
+
+            let instance = wgpu::Instance::default();
+            let surface = instance.create_surface(window_static).expect("create surface");
+            let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::HighPerformance,
+                compatible_surface: Some(&surface), force_fallback_adapter: false,
+            })).expect("request adapter");
+            let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+                label: Some("device"),
+                required_features: wgpu::Features::empty(),
+                required_limits: wgpu::Limits::downlevel_defaults(),
+            }, None)).expect("request device");
+            let surface_caps = surface.get_capabilities(&adapter);
+            let format = surface_caps.formats.iter().copied().find(|f| f.is_srgb()).unwrap_or(surface_caps.formats[0]);
+            let config = wgpu::SurfaceConfiguration {
+                usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
+                format,
+                width,
+                height,
+                present_mode: wgpu::PresentMode::Fifo,
+                alpha_mode: surface_caps.alpha_modes[0],
+                view_formats: vec![],
+                desired_maximum_frame_latency: 2,
+            };
+            surface.configure(&device, &config);
+
+            let (pipeline, bgl) = create_pipeline(&device, config.format);
+            let uniform_buf = device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some("params-ubo"),
+                size: std::mem::size_of::<Params>() as u64,
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
             });
-
-            let res: &mut GpuState = super::GPU_STATES.with(|cell| {
-                let _map = cell.borrow_mut();
-                let ptr = state as *mut GpuState;
-                unsafe { &mut *ptr }
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("params-bg"),
+                layout: &bgl,
+                entries: &[wgpu::BindGroupEntry { binding: 0, resource: uniform_buf.as_entire_binding() }],
             });
+            let new_state = GpuState { surface, device, queue, config, pipeline, bind_group_layout: bgl, uniform_buf, bind_group, bg_layout_label: "params-bgl", list: None };
+            map.insert(win, new_state);
+            map.get(&win).unwrap() as *const GpuState as usize
+        });
 
-            // Reconfigure on resize
-            if res.config.width != width || res.config.height != height {
-                res.config.width = width; res.config.height = height;
-                res.surface.configure(&res.device, &res.config);
-            }
+        let res: &mut GpuState = super::GPU_STATES.with(|cell| {
+            let _map = cell.borrow_mut();
+            let ptr = state as *mut GpuState;
+            unsafe { &mut *ptr }
+        });
+
+        // Reconfigure on resize
+        if res.config.width != width || res.config.height != height {
+            res.config.width = width; res.config.height = height;
+            res.surface.configure(&res.device, &res.config);
+        }
+        Some(res)
+    }
+
+    fn current_frame(res: &GpuState) -> Option<wgpu::SurfaceTexture> {
+        match res.surface.get_current_texture() { Ok(f) => Some(f), Err(_) => {
+            res.surface.configure(&res.device, &res.config);
+            res.surface.get_current_texture().ok()
+        }}
+    }
 
-            let frame = match res.surface.get_current_texture() { Ok(f) => f, Err(_) => {
-                res.surface.configure(&res.device, &res.config);
-                res.surface.get_current_texture().ok()?
-            }};
+    pub fn render_placeholder(win: Entity, color: [f32; 4]) -> Option<()> {
+        super::with_window(win, |window| {
+            let res = state_for(win, window)?;
+            let (width, height) = (res.config.width, res.config.height);
+            let frame = current_frame(res)?;
             let view = frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
 
             let params = Params { color, resolution: [width as f32, height as f32], thickness_px: 1.5, _pad: 0.0 };
@@ -225,72 +239,8 @@ fn fs_main(in: VsOut) -> @location(0) vec4<f32> {
 
     pub fn render_batch(win: Entity, items: &[Item]) -> Option<()> {
         super::with_window(win, |window| {
-            let window_static: &'static Window = unsafe { std::mem::transmute::<&Window, &'static Window>(window) };
-            let size = window.inner_size();
-            let width = size.width.max(1);
-            let height = size.height.max(1);
-
-            let state = super::GPU_STATES.with(|cell| {
-                let mut map = cell.borrow_mut();
-                if let Some(state) = map.get(&win) { return state as *const GpuState as usize; }
-
-                let instance = wgpu::Instance::default();
-                let surface = instance.create_surface(window_static).expect("create surface");
-                let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
-                    power_preference: wgpu::PowerPreference::HighPerformance,
-                    compatible_surface: Some(&surface), force_fallback_adapter: false,
-                })).expect("request adapter");
-                let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
-                    label: Some("device"),
-                    required_features: wgpu::Features::empty(),
-                    required_limits: wgpu::Limits::downlevel_defaults(),
-                }, None)).expect("request device");
-                let surface_caps = surface.get_capabilities(&adapter);
-                let format = surface_caps.formats.iter().copied().find(|f| f.is_srgb()).unwrap_or(surface_caps.formats[0]);
-                let config = wgpu::SurfaceConfiguration {
-                    usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
-                    format,
-                    width,
-                    height,
-                    present_mode: wgpu::PresentMode::Fifo,
-                    alpha_mode: surface_caps.alpha_modes[0],
-                    view_formats: vec![],
-                    desired_maximum_frame_latency: 2,
-                };
-                surface.configure(&device, &config);
-
-                let (pipeline, bgl) = create_pipeline(&device, config.format);
-                let uniform_buf = device.create_buffer(&wgpu::BufferDescriptor {
-                    label: Some("params-ubo"),
-                    size: std::mem::size_of::<Params>() as u64,
-                    usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
-                    mapped_at_creation: false,
-                });
-                let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-                    label: Some("params-bg"),
-                    layout: &bgl,
-                    entries: &[wgpu::BindGroupEntry { binding: 0, resource: uniform_buf.as_entire_binding() }],
-                });
-                let new_state = GpuState { surface, device, queue, config, pipeline, bind_group_layout: bgl, uniform_buf, bind_group, bg_layout_label: "params-bgl" };
-                map.insert(win, new_state);
-                map.get(&win).unwrap() as *const GpuState as usize
-            });
-
-            let res: &mut GpuState = super::GPU_STATES.with(|cell| {
-                let _map = cell.borrow_mut();
-                let ptr = state as *mut GpuState;
-                unsafe { &mut *ptr }
-            });
-
-            if res.config.width != width || res.config.height != height {
-                res.config.width = width; res.config.height = height;
-                res.surface.configure(&res.device, &res.config);
-            }
-
-            let frame = match res.surface.get_current_texture() { Ok(f) => f, Err(_) => {
-                res.surface.configure(&res.device, &res.config);
-                res.surface.get_current_texture().ok()?
-            }};
+            let res = state_for(win, window)?;
+            let frame = current_frame(res)?;
             let view = frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
 
             // Prepare per-item uniform bind groups
@@ -338,6 +288,239 @@ fn fs_main(in: VsOut) -> @location(0) vec4<f32> {
             Some(())
         }).flatten()
     }
+
+    // ----- display lists: textured triangles from `Mesh` -----
+
+    pub struct ListPipeline {
+        pipeline: wgpu::RenderPipeline,
+        globals_layout: wgpu::BindGroupLayout,
+        texture_layout: wgpu::BindGroupLayout,
+        sampler: wgpu::Sampler,
+    }
+
+    #[repr(C)]
+    #[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+    struct Globals {
+        resolution: [f32; 2],
+        _pad: [f32; 2],
+    }
+
+    const LIST_SHADER: &str = r#"
+struct Globals {
+  resolution: vec2<f32>,
+  _pad: vec2<f32>,
+};
+
+@group(0) @binding(0)
+var<uniform> globals: Globals;
+@group(1) @binding(0)
+var tex: texture_2d<f32>;
+@group(1) @binding(1)
+var samp: sampler;
+
+struct VsIn {
+  @location(0) pos: vec2<f32>,
+  @location(1) uv: vec2<f32>,
+  @location(2) color: vec4<f32>,
+};
+
+struct VsOut {
+  @builtin(position) pos: vec4<f32>,
+  @location(0) uv: vec2<f32>,
+  @location(1) color: vec4<f32>,
+};
+
+@vertex
+fn vs_main(v: VsIn) -> VsOut {
+  var out: VsOut;
+  // pixels (y down) -> clip space
+  let ndc = v.pos / globals.resolution * 2.0 - vec2<f32>(1.0, 1.0);
+  out.pos = vec4<f32>(ndc.x, -ndc.y, 0.0, 1.0);
+  out.uv = v.uv;
+  out.color = v.color;
+  return out;
+}
+
+@fragment
+fn fs_main(in: VsOut) -> @location(0) vec4<f32> {
+  return textureSample(tex, samp, in.uv) * in.color;
+}
+"#;
+
+    fn create_list_pipeline(device: &wgpu::Device, format: wgpu::TextureFormat) -> ListPipeline {
+        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("display-list-shader"),
+            source: wgpu::ShaderSource::Wgsl(LIST_SHADER.into()),
+        });
+        let globals_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("display-list-globals-bgl"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStages::VERTEX,
+                ty: wgpu::BindingType::Buffer { ty: wgpu::BufferBindingType::Uniform, has_dynamic_offset: false, min_binding_size: std::num::NonZeroU64::new(std::mem::size_of::<Globals>() as u64) },
+                count: None,
+            }],
+        });
+        let texture_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("display-list-texture-bgl"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Texture { sample_type: wgpu::TextureSampleType::Float { filterable: true }, view_dimension: wgpu::TextureViewDimension::D2, multisampled: false },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler(wgpu::SamplerBindingType::Filtering),
+                    count: None,
+                },
+            ],
+        });
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("display-list-pipeline-layout"),
+            bind_group_layouts: &[&globals_layout, &texture_layout],
+            push_constant_ranges: &[],
+        });
+        let attributes = wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x2, 2 => Float32x4];
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("display-list-pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[wgpu::VertexBufferLayout { array_stride: std::mem::size_of::<Vertex>() as u64, step_mode: wgpu::VertexStepMode::Vertex, attributes: &attributes }],
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                compilation_options: wgpu::PipelineCompilationOptions::default(),
+                targets: &[Some(wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                    write_mask: wgpu::ColorWrites::ALL,
+                })],
+            }),
+            primitive: wgpu::PrimitiveState::default(),
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState::default(),
+            multiview: None,
+        });
+        // nearest, like the software blit
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor { label: Some("display-list-sampler"), ..Default::default() });
+        ListPipeline { pipeline, globals_layout, texture_layout, sampler }
+    }
+
+    fn srgb_to_linear(c: f32) -> f32 {
+        if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+    }
+
+    fn upload_texture(res: &GpuState, list: &ListPipeline, image: &RgbaImage, format: wgpu::TextureFormat) -> wgpu::BindGroup {
+        let size = wgpu::Extent3d { width: image.width, height: image.height, depth_or_array_layers: 1 };
+        let texture = res.device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("display-list-texture"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        });
+        res.queue.write_texture(
+            wgpu::ImageCopyTexture { texture: &texture, mip_level: 0, origin: wgpu::Origin3d::ZERO, aspect: wgpu::TextureAspect::All },
+            &image.pixels,
+            wgpu::ImageDataLayout { offset: 0, bytes_per_row: Some(4 * image.width), rows_per_image: Some(image.height) },
+            size,
+        );
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+        res.device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("display-list-texture-bg"),
+            layout: &list.texture_layout,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: wgpu::BindingResource::TextureView(&view) },
+                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::Sampler(&list.sampler) },
+            ],
+        })
+    }
+
+    pub fn render_list(win: Entity, display_list: &DisplayList) -> Option<()> {
+        super::with_window(win, |window| {
+            let res = state_for(win, window)?;
+            let (width, height, format) = (res.config.width, res.config.height, res.config.format);
+            if res.list.is_none() { res.list = Some(create_list_pipeline(&res.device, format)); }
+            let list = res.list.as_ref()?;
+            let mut mesh = Mesh::build(display_list, width, height);
+            // colors are sRGB like the software backend; an sRGB target expects linear values
+            let srgb = format.is_srgb();
+            if srgb {
+                for v in &mut mesh.vertices { for c in &mut v.color[..3] { *c = srgb_to_linear(*c); } }
+            }
+            let texture_format = if srgb { wgpu::TextureFormat::Rgba8UnormSrgb } else { wgpu::TextureFormat::Rgba8Unorm };
+
+            let frame = current_frame(res)?;
+            let view = frame.texture.create_view(&wgpu::TextureViewDescriptor::default());
+            let globals = Globals { resolution: [width as f32, height as f32], _pad: [0.0; 2] };
+            let globals_buf = res.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                label: Some("display-list-globals"),
+                contents: bytemuck::bytes_of(&globals),
+                usage: wgpu::BufferUsages::UNIFORM,
+            });
+            let globals_bg = res.device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("display-list-globals-bg"),
+                layout: &list.globals_layout,
+                entries: &[wgpu::BindGroupEntry { binding: 0, resource: globals_buf.as_entire_binding() }],
+            });
+            let textures: Vec<wgpu::BindGroup> = mesh.textures.iter().map(|image| upload_texture(res, list, image, texture_format)).collect();
+            let buffers = (!mesh.indices.is_empty()).then(|| {
+                let vertices = res.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("display-list-vertices"),
+                    contents: bytemuck::cast_slice(&mesh.vertices),
+                    usage: wgpu::BufferUsages::VERTEX,
+                });
+                let indices = res.device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+                    label: Some("display-list-indices"),
+                    contents: bytemuck::cast_slice(&mesh.indices),
+                    usage: wgpu::BufferUsages::INDEX,
+                });
+                (vertices, indices)
+            });
+
+            let [r, g, b, a] = mesh.clear.unwrap_or([0.0, 0.0, 0.0, 1.0]).map(|c| c as f64);
+            let (r, g, b) = if srgb { (srgb_to_linear(r as f32) as f64, srgb_to_linear(g as f32) as f64, srgb_to_linear(b as f32) as f64) } else { (r, g, b) };
+            let mut encoder = res.device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("display-list-encoder") });
+            {
+                let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("display-list-pass"),
+                    color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                        view: &view,
+                        resolve_target: None,
+                        ops: wgpu::Operations { load: wgpu::LoadOp::Clear(wgpu::Color { r, g, b, a }), store: wgpu::StoreOp::Store },
+                    })],
+                    depth_stencil_attachment: None,
+                    occlusion_query_set: None,
+                    timestamp_writes: None,
+                });
+                if let Some((vertices, indices)) = &buffers {
+                    rpass.set_pipeline(&list.pipeline);
+                    rpass.set_bind_group(0, &globals_bg, &[]);
+                    rpass.set_vertex_buffer(0, vertices.slice(..));
+                    rpass.set_index_buffer(indices.slice(..), wgpu::IndexFormat::Uint32);
+                    for batch in &mesh.batches {
+                        let [x, y, w, h] = batch.scissor;
+                        rpass.set_scissor_rect(x, y, w, h);
+                        rpass.set_bind_group(1, &textures[batch.texture], &[]);
+                        rpass.draw_indexed(batch.indices.clone(), 0, 0..1);
+                    }
+                }
+            }
+            res.queue.submit(std::iter::once(encoder.finish()));
+            frame.present();
+            Some(())
+        }).flatten()
+    }
 }
 
 thread_local! { pub static GPU_STATES: RefCell<HashMap<Entity, wgpu_backend::GpuState>> = RefCell::new(HashMap::new()); }
@@ -347,6 +530,11 @@ pub fn render_placeholder_wgpu(win: Entity, color: [f32; 4]) -> Option<()> {
     wgpu_backend::render_placeholder(win, color)
 }
 
+/// Draw `list` into the window with wgpu. The software equivalent is `list.paint` inside `with_frame`.
+pub fn render_display_list_wgpu(win: Entity, list: &DisplayList) -> Option<()> {
+    wgpu_backend::render_list(win, list)
+}
+
 pub struct PlaceholderItem { pub x: u32, pub y: u32, pub w: u32, pub h: u32, pub color: [f32; 4], pub thickness_px: f32 }
 
 pub fn render_placeholders_wgpu(win: Entity, items: &[PlaceholderItem]) -> Option<()> {
@@ -358,17 +546,22 @@ fn with_surface<R>(win: Entity, f: impl FnOnce(&mut Surface<&Window, &Window>, (
     with_window(win, |wnd| {
         let sz = wnd.inner_size();
         let (w, h) = (sz.width, sz.height);
-        let ctx = Context::new(wnd).ok()?;
-        let mut surf = Surface::new(&ctx, wnd).ok()?;
-        let _ = surf.resize(NonZeroU32::new(w).unwrap(), NonZeroU32::new(h).unwrap());
+        let ctx = Context::new(wnd).map_err(|e| log::error!("softbuffer context: {}", e)).ok()?;
+        let mut surf = Surface::new(&ctx, wnd).map_err(|e| log::error!("softbuffer surface: {}", e)).ok()?;
+        let (Some(nw), Some(nh)) = (NonZeroU32::new(w), NonZeroU32::new(h)) else {
+            log::debug!("skip frame for zero-sized window {}", win.id());
+            return None;
+        };
+        if let Err(e) = surf.resize(nw, nh) { log::warn!("softbuffer resize to {}x{}: {}", w, h, e); }
         Some(f(&mut surf, (w, h)))
     }).flatten()
 }
 /// Execute a closure with a CPU frame buffer for the given window entity.
 /// The closure receives: (buf, width, height, stride). Buffer format: ARGB8888.
+/// `Canvas::draw` offers the same for offscreen rendering.
 pub fn with_frame(win: Entity, f: impl FnOnce(&mut [u32], usize, usize, usize)) -> Option<()> {
     with_surface(win, |surf, (wpx, hpx)| {
-        let mut buf = match surf.buffer_mut() { Ok(b) => b, Err(_) => return (), };
+        let mut buf = match surf.buffer_mut() { Ok(b) => b, Err(e) => { log::error!("softbuffer buffer_mut: {}", e); return; } };
         let width = wpx as usize;
         let height = hpx as usize;
         let stride = width; // tightly packed
@@ -376,7 +569,7 @@ pub fn with_frame(win: Entity, f: impl FnOnce(&mut [u32], usize, usize, usize))
             let slice: &mut [u32] = &mut buf;
             f(slice, width, height, stride);
         }
-        let _ = buf.present();
+        if let Err(e) = buf.present() { log::warn!("softbuffer present: {}", e); }
     })
 }
 
@@ -403,12 +596,13 @@ pub fn clear(buf: &mut [u32], width: usize, height: usize, stride: usize, color:
     }
 }
 
+/// Blend `color` over the pixel at (x, y); opaque colors replace it.
 #[inline]
 pub fn put_pixel(buf: &mut [u32], width: usize, height: usize, stride: usize, x: i32, y: i32, color: u32) {
     if x >= 0 && y >= 0 {
         let ux = x as usize; let uy = y as usize;
         if ux < width && uy < height {
-            buf[uy * stride + ux] = color;
+            raster::blend_over(&mut buf[uy * stride + ux], raster::premultiply(color));
         }
     }
 }
@@ -445,4 +639,12 @@ pub fn draw_rect_outline(buf: &mut [u32], width: usize, height: usize, stride: u
 }
 
 pub mod text;
-pub use text::{draw_text_mono, noto_sans_regular};
+pub use text::{draw_text, draw_text_mono, embed_assets, noto_sans_regular, FontAsset, FontLoader, NOTO_SANS_REGULAR};
+pub mod atlas;
+pub use atlas::{draw_text_atlas, AtlasGlyph, FontAtlasImporter, GlyphAtlas, GlyphAtlasLoader};
+pub mod canvas;
+pub use canvas::Canvas;
+pub mod display_list;
+pub use display_list::{DisplayList, DrawCmd, Rect, Transform};
+pub mod raster;
+pub use raster::{blend_coverage, blend_over, premultiply, unpremultiply, DrawContext};
@@ -0,0 +1,531 @@
+use std::ops::Range;
+
+use aubrey_core::image::RgbaImage;
+
+use crate::display_list::Rect;
+use crate::atlas::{draw_text_atlas, GlyphAtlas};
+use crate::text::{draw_text, FontAsset};
+
+/// Straight ARGB -> premultiplied ARGB (color channels scaled by alpha, rounded).
+#[inline]
+pub fn premultiply(color: u32) -> u32 {
+    let a = color >> 24;
+    if a == 255 { return color; }
+    let ch = |shift: u32| (((color >> shift) & 0xFF) * a + 127) / 255;
+    a << 24 | ch(16) << 16 | ch(8) << 8 | ch(0)
+}
+
+/// Premultiplied ARGB -> straight ARGB.
+#[inline]
+pub fn unpremultiply(color: u32) -> u32 {
+    let a = color >> 24;
+    if a == 255 || a == 0 { return if a == 0 { 0 } else { color }; }
+    let ch = |shift: u32| ((((color >> shift) & 0xFF) * 255 + a / 2) / a).min(255);
+    a << 24 | ch(16) << 16 | ch(8) << 8 | ch(0)
+}
+
+// All four channels times k/255.
+#[inline]
+fn scale(color: u32, k: u8) -> u32 {
+    let ch = |shift: u32| ((((color >> shift) & 0xFF) * k as u32 + 127) / 255) << shift;
+    ch(24) | ch(16) | ch(8) | ch(0)
+}
+
+/// Source-over: composite premultiplied `src` onto premultiplied `dst`.
+#[inline]
+pub fn blend_over(dst: &mut u32, src: u32) {
+    let sa = src >> 24;
+    if sa == 255 { *dst = src; return; }
+    if src == 0 { return; }
+    let inv = 255 - sa;
+    let ch = |shift: u32| (((src >> shift) & 0xFF) + ((((*dst >> shift) & 0xFF) * inv + 127) / 255)).min(255) << shift;
+    *dst = ch(24) | ch(16) | ch(8) | ch(0);
+}
+
+/// Blend straight ARGB `color` onto `dst` with `coverage` (0..=255), e.g. from a glyph rasterizer.
+#[inline]
+pub fn blend_coverage(dst: &mut u32, color: u32, coverage: u8) {
+    blend_over(dst, scale(premultiply(color), coverage));
+}
+
+// Pixels touching [lo, hi), limited to `within`.
+fn span(lo: f32, hi: f32, within: &Range<usize>) -> Range<usize> {
+    clamp_to(lo.floor(), within)..clamp_to(hi.ceil(), within)
+}
+
+// Pixels whose centers lie in [lo, hi), limited to `within`.
+fn center_span(lo: f32, hi: f32, within: &Range<usize>) -> Range<usize> {
+    clamp_to(lo.round(), within)..clamp_to(hi.round(), within)
+}
+
+fn clamp_to(v: f32, within: &Range<usize>) -> usize { (v.max(within.start as f32) as usize).min(within.end) }
+
+#[inline]
+fn coverage(c: f32) -> u8 { (c.clamp(0.0, 1.0) * 255.0 + 0.5) as u8 }
+
+// Signed distance to a rect with corner radii (tl, tr, br, bl); negative inside.
+fn rounded_rect_distance(r: &Rect, radii: &[f32; 4], x: f32, y: f32) -> f32 {
+    let (hw, hh) = (r.w / 2.0, r.h / 2.0);
+    let (px, py) = (x - r.x - hw, y - r.y - hh);
+    let [tl, tr, br, bl] = *radii;
+    let rad = match (px < 0.0, py < 0.0) { (true, true) => tl, (false, true) => tr, (false, false) => br, (true, false) => bl };
+    let (qx, qy) = (px.abs() - hw + rad, py.abs() - hh + rad);
+    (qx.max(0.0).powi(2) + qy.max(0.0).powi(2)).sqrt() + qx.max(qy).min(0.0) - rad
+}
+
+// Radii limited to half the shorter side.
+pub(crate) fn clamp_radii(r: &Rect, radii: [f32; 4]) -> [f32; 4] {
+    let max = (r.w.min(r.h) / 2.0).max(0.0);
+    radii.map(|v| v.clamp(0.0, max))
+}
+
+/// Anti-aliased drawing into an ARGB8888 buffer holding premultiplied alpha (opaque pixels
+/// are the same either way). Colors are straight ARGB as from `pack_rgba_u8` and are blended
+/// source-over; coordinates are pixels with (0, 0) the top-left corner of the first pixel.
+/// Every primitive stays inside the current clip rect (`push_clip` / `pop_clip`).
+///
+/// ```ignore
+/// canvas.draw(|buf, width, height, stride| {
+///     let mut ctx = DrawContext::new(buf, width, height, stride);
+///     ctx.fill_rounded_rect(&Rect::new(10.0, 10.0, 120.0, 40.0), [6.0; 4], pack_rgba_u8(40, 40, 40, 255));
+///     ctx.stroke_rect(&Rect::new(10.0, 10.0, 120.0, 40.0), 2.0, pack_rgba_u8(255, 255, 255, 128));
+///     ctx.clipped(&Rect::new(10.0, 10.0, 120.0, 40.0), |ctx| ctx.text((14.0, 14.0), "clipped to the box", &font, 16.0, pack_rgba_u8(255, 255, 255, 255)));
+///     ctx.line((0.0, 0.0), (100.0, 60.0), 3.0, pack_rgba_u8(255, 0, 0, 255));
+/// });
+/// ```
+pub struct DrawContext<'a> {
+    buf: &'a mut [u32],
+    width: usize,
+    height: usize,
+    stride: usize,
+    // current clip in pixel columns and rows, and the ones `pop_clip` restores
+    clip_x: Range<usize>,
+    clip_y: Range<usize>,
+    clips: Vec<(Range<usize>, Range<usize>)>,
+}
+
+impl<'a> DrawContext<'a> {
+    pub fn new(buf: &'a mut [u32], width: usize, height: usize, stride: usize) -> Self {
+        Self { buf, width, height, stride, clip_x: 0..width, clip_y: 0..height, clips: Vec::new() }
+    }
+
+    pub fn width(&self) -> usize { self.width }
+
+    pub fn height(&self) -> usize { self.height }
+
+    /// Limit drawing to `r` (rounded to whole pixels) within the current clip, until the matching `pop_clip`.
+    pub fn push_clip(&mut self, r: &Rect) {
+        let (xs, ys) = (center_span(r.x, r.right(), &self.clip_x), center_span(r.y, r.bottom(), &self.clip_y));
+        self.clips.push((std::mem::replace(&mut self.clip_x, xs), std::mem::replace(&mut self.clip_y, ys)));
+    }
+
+    /// Restore the clip from before the last `push_clip`; unmatched pops are ignored.
+    pub fn pop_clip(&mut self) {
+        if let Some((xs, ys)) = self.clips.pop() { (self.clip_x, self.clip_y) = (xs, ys); }
+    }
+
+    /// Run `f` with the clip narrowed to `r`.
+    pub fn clipped<R>(&mut self, r: &Rect, f: impl FnOnce(&mut Self) -> R) -> R {
+        self.push_clip(r);
+        let out = f(self);
+        self.pop_clip();
+        out
+    }
+
+    /// Current clip rect; the whole buffer unless narrowed by `push_clip`.
+    pub fn clip(&self) -> Rect {
+        let (xs, ys) = (&self.clip_x, &self.clip_y);
+        Rect::new(xs.start as f32, ys.start as f32, xs.len() as f32, ys.len() as f32)
+    }
+
+    /// Run `f` on the clip rect as a buffer of its own (buf, width, height, stride), with the
+    /// offset of its top-left corner, so the drawing functions that only check the buffer
+    /// edges (`draw_line`, `draw_text_mono`, ...) are clipped too. Not called if the clip is empty.
+    pub fn in_clip(&mut self, f: impl FnOnce(&mut [u32], usize, usize, usize, (i32, i32))) {
+        let (xs, ys) = (self.clip_x.clone(), self.clip_y.clone());
+        if xs.is_empty() || ys.is_empty() { return; }
+        f(&mut self.buf[ys.start * self.stride + xs.start..], xs.len(), ys.len(), self.stride, (xs.start as i32, ys.start as i32));
+    }
+
+    /// Overwrite every pixel in the clip with `color` (not blended).
+    pub fn clear(&mut self, color: u32) {
+        self.in_clip(|buf, width, height, stride, _| crate::clear(buf, width, height, stride, premultiply(color)));
+    }
+
+    /// Blend `color` at pixel (x, y) with `coverage`; ignored outside the clip.
+    pub fn blend_pixel(&mut self, x: i32, y: i32, color: u32, coverage: u8) {
+        if x < 0 || y < 0 || !self.clip_x.contains(&(x as usize)) || !self.clip_y.contains(&(y as usize)) { return; }
+        blend_coverage(&mut self.buf[y as usize * self.stride + x as usize], color, coverage);
+    }
+
+    // Blend `color` into the pixels around `bounds`, covered by `0.5 - distance` at each pixel center.
+    fn fill_distance(&mut self, bounds: &Rect, color: u32, distance: impl Fn(f32, f32) -> f32) {
+        let color = premultiply(color);
+        if color >> 24 == 0 || bounds.is_empty() { return; }
+        let xs = span(bounds.x - 1.0, bounds.right() + 1.0, &self.clip_x);
+        for y in span(bounds.y - 1.0, bounds.bottom() + 1.0, &self.clip_y) {
+            for x in xs.clone() {
+                let c = coverage(0.5 - distance(x as f32 + 0.5, y as f32 + 0.5));
+                if c > 0 { blend_over(&mut self.buf[y * self.stride + x], scale(color, c)); }
+            }
+        }
+    }
+
+    /// Filled rectangle; fractional edges are partially covered.
+    pub fn fill_rect(&mut self, r: &Rect, color: u32) {
+        let color = premultiply(color);
+        if color >> 24 == 0 || r.is_empty() { return; }
+        // overlap of pixel i with [lo, hi)
+        let overlap = |i: usize, lo: f32, hi: f32| ((i as f32 + 1.0).min(hi) - (i as f32).max(lo)).clamp(0.0, 1.0);
+        let xs = span(r.x, r.right(), &self.clip_x);
+        for y in span(r.y, r.bottom(), &self.clip_y) {
+            let cy = overlap(y, r.y, r.bottom());
+            let row = y * self.stride;
+            for x in xs.clone() {
+                let c = coverage(cy * overlap(x, r.x, r.right()));
+                if c > 0 { blend_over(&mut self.buf[row + x], scale(color, c)); }
+            }
+        }
+    }
+
+    /// Border `width` pixels wide inside `r`.
+    pub fn stroke_rect(&mut self, r: &Rect, width: f32, color: u32) { self.stroke_rounded_rect(r, [0.0; 4], width, color); }
+
+    /// Filled rectangle with corner radii (top-left, top-right, bottom-right, bottom-left).
+    pub fn fill_rounded_rect(&mut self, r: &Rect, radii: [f32; 4], color: u32) {
+        let radii = clamp_radii(r, radii);
+        self.fill_distance(r, color, |x, y| rounded_rect_distance(r, &radii, x, y));
+    }
+
+    /// Border `width` pixels wide inside a rounded rectangle; the inner corners follow the outer ones.
+    pub fn stroke_rounded_rect(&mut self, r: &Rect, radii: [f32; 4], width: f32, color: u32) {
+        if width <= 0.0 { return; }
+        let radii = clamp_radii(r, radii);
+        let inner = Rect::new(r.x + width, r.y + width, r.w - 2.0 * width, r.h - 2.0 * width);
+        if inner.is_empty() {
+            self.fill_rounded_rect(r, radii, color);
+        } else {
+            let inner_radii = radii.map(|v| (v - width).max(0.0));
+            self.fill_distance(r, color, |x, y| rounded_rect_distance(r, &radii, x, y).max(-rounded_rect_distance(&inner, &inner_radii, x, y)));
+        }
+    }
+
+    /// Filled ellipse with radii `(rx, ry)` around `center`.
+    pub fn fill_ellipse(&mut self, center: (f32, f32), radii: (f32, f32), color: u32) {
+        let (rx, ry) = radii;
+        if rx <= 0.0 || ry <= 0.0 { return; }
+        let bounds = Rect::new(center.0 - rx, center.1 - ry, 2.0 * rx, 2.0 * ry);
+        self.fill_distance(&bounds, color, |x, y| {
+            // implicit function over its gradient length, close to the true distance near the edge
+            let (nx, ny) = ((x - center.0) / rx, (y - center.1) / ry);
+            let grad = 2.0 * ((nx / rx).powi(2) + (ny / ry).powi(2)).sqrt();
+            if grad > 0.0 { (nx * nx + ny * ny - 1.0) / grad } else { -rx.min(ry) }
+        });
+    }
+
+    pub fn fill_circle(&mut self, center: (f32, f32), radius: f32, color: u32) { self.fill_ellipse(center, (radius, radius), color); }
+
+    /// Line `width` pixels thick with square (butt) ends. Lines thinner than a pixel are drawn
+    /// one pixel wide and fainter.
+    pub fn line(&mut self, from: (f32, f32), to: (f32, f32), width: f32, color: u32) {
+        let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+        let len = (dx * dx + dy * dy).sqrt();
+        if len == 0.0 || width <= 0.0 { return; }
+        let color = if width < 1.0 { (coverage(width * (color >> 24) as f32 / 255.0) as u32) << 24 | (color & 0x00FF_FFFF) } else { color };
+        let (ux, uy) = (dx / len, dy / len);
+        let half = width.max(1.0) / 2.0;
+        let bounds = Rect::new(from.0.min(to.0) - half, from.1.min(to.1) - half, dx.abs() + 2.0 * half, dy.abs() + 2.0 * half);
+        self.fill_distance(&bounds, color, |x, y| {
+            let (px, py) = (x - from.0, y - from.1);
+            let (along, across) = (px * ux + py * uy, py * ux - px * uy);
+            ((along - len / 2.0).abs() - len / 2.0).max(across.abs() - half)
+        });
+    }
+
+    /// Image (straight alpha) scaled to `r`, sampling the nearest texel.
+    pub fn image(&mut self, r: &Rect, image: &RgbaImage) {
+        if image.width == 0 || image.height == 0 || r.is_empty() { return; }
+        let sample = |v: usize, origin: f32, extent: f32, size: u32| (((v as f32 + 0.5 - origin) / extent * size as f32) as u32).min(size - 1);
+        let xs = center_span(r.x, r.right(), &self.clip_x);
+        for y in center_span(r.y, r.bottom(), &self.clip_y) {
+            let sy = sample(y, r.y, r.h, image.height);
+            for x in xs.clone() {
+                let [red, g, b, a] = image.pixel(sample(x, r.x, r.w, image.width), sy);
+                blend_over(&mut self.buf[y * self.stride + x], premultiply(crate::pack_rgba_u8(red, g, b, a)));
+            }
+        }
+    }
+
+    /// Text as `draw_text`, with the top-left of the first line at `pos`.
+    pub fn text(&mut self, pos: (f32, f32), text: &str, font: &FontAsset, px: f32, color: u32) {
+        draw_text(self, (pos.0 as i32, pos.1 as i32), text, font, px, color);
+    }
+
+    /// Text as `draw_text_atlas`, with the top-left of the first line at `pos`.
+    pub fn text_atlas(&mut self, pos: (f32, f32), text: &str, atlas: &GlyphAtlas, color: u32) {
+        draw_text_atlas(self, (pos.0 as i32, pos.1 as i32), text, atlas, color);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WHITE: u32 = 0xFFFF_FFFF;
+
+    // Draw onto a transparent w x h buffer; with opaque white, each pixel's alpha is its coverage.
+    fn draw(w: usize, h: usize, f: impl FnOnce(&mut DrawContext)) -> Vec<u32> {
+        let mut buf = vec![0; w * h];
+        f(&mut DrawContext::new(&mut buf, w, h, w));
+        buf
+    }
+
+    fn alpha(buf: &[u32], w: usize, x: usize, y: usize) -> u32 { buf[y * w + x] >> 24 }
+
+    fn alphas(buf: &[u32]) -> Vec<u32> { buf.iter().map(|p| p >> 24).collect() }
+
+    #[test]
+    fn fill_rect_integer_edges_are_exact() {
+        let buf = draw(4, 4, |ctx| ctx.fill_rect(&Rect::new(1.0, 1.0, 2.0, 2.0), WHITE));
+        assert_eq!(alphas(&buf), [
+            0, 0, 0, 0,
+            0, 255, 255, 0,
+            0, 255, 255, 0,
+            0, 0, 0, 0,
+        ]);
+        assert_eq!(buf[5], WHITE);
+    }
+
+    #[test]
+    fn fill_rect_fractional_edges_cover_by_area() {
+        let buf = draw(4, 1, |ctx| ctx.fill_rect(&Rect::new(0.5, 0.0, 2.0, 1.0), WHITE));
+        assert_eq!(alphas(&buf), [128, 255, 128, 0]);
+        let buf = draw(3, 3, |ctx| ctx.fill_rect(&Rect::new(0.25, 0.25, 1.0, 1.0), WHITE));
+        // 0.75 * 0.75, 0.25 * 0.75 and 0.25 * 0.25 of each pixel
+        assert_eq!(alphas(&buf), [143, 48, 0, 48, 16, 0, 0, 0, 0]);
+        // half-transparent colors scale the coverage
+        let buf = draw(2, 1, |ctx| ctx.fill_rect(&Rect::new(0.5, 0.0, 1.0, 1.0), 0x80FF_FFFF));
+        assert_eq!(alphas(&buf), [64, 64]);
+    }
+
+    #[test]
+    fn fill_rounded_rect_has_per_corner_radii() {
+        let buf = draw(20, 20, |ctx| ctx.fill_rounded_rect(&Rect::new(0.0, 0.0, 20.0, 20.0), [8.0, 0.0, 4.0, 0.0], WHITE));
+        assert_eq!(alpha(&buf, 20, 0, 0), 0);
+        assert_eq!(alpha(&buf, 20, 19, 19), 0);
+        assert_eq!(alpha(&buf, 20, 19, 0), 255);
+        assert_eq!(alpha(&buf, 20, 0, 19), 255);
+        assert_eq!(alpha(&buf, 20, 10, 10), 255);
+        // the larger radius cuts deeper along the edge
+        assert!(alpha(&buf, 20, 2, 0) < alpha(&buf, 20, 19, 17));
+        let edge = alpha(&buf, 20, 1, 3);
+        assert!(edge > 0 && edge < 255, "{}", edge);
+
+        // radii beyond half the shorter side are clamped
+        let buf = draw(10, 10, |ctx| ctx.fill_rounded_rect(&Rect::new(0.0, 0.0, 10.0, 10.0), [100.0; 4], WHITE));
+        let circle = draw(10, 10, |ctx| ctx.fill_circle((5.0, 5.0), 5.0, WHITE));
+        for (a, b) in alphas(&buf).iter().zip(alphas(&circle)) { assert!(a.abs_diff(b) <= 8, "{} vs {}", a, b); }
+    }
+
+    #[test]
+    fn circles_and_ellipses_are_symmetric() {
+        let n = 16;
+        let circle = draw(n, n, |ctx| ctx.fill_circle((8.0, 8.0), 5.3, WHITE));
+        for y in 0..n {
+            for x in 0..n {
+                let a = alpha(&circle, n, x, y);
+                assert_eq!(a, alpha(&circle, n, n - 1 - x, y));
+                assert_eq!(a, alpha(&circle, n, x, n - 1 - y));
+                assert_eq!(a, alpha(&circle, n, y, x));
+            }
+        }
+        let area: f32 = alphas(&circle).iter().map(|a| *a as f32 / 255.0).sum();
+        assert!((area - std::f32::consts::PI * 5.3 * 5.3).abs() < 1.0, "{}", area);
+        assert_eq!(alpha(&circle, n, 8, 8), 255);
+        assert_eq!(alpha(&circle, n, 2, 2), 0);
+
+        let wide = draw(n, n, |ctx| ctx.fill_ellipse((8.0, 8.0), (6.0, 3.0), WHITE));
+        let tall = draw(n, n, |ctx| ctx.fill_ellipse((8.0, 8.0), (3.0, 6.0), WHITE));
+        for y in 0..n {
+            for x in 0..n {
+                let a = alpha(&wide, n, x, y);
+                assert_eq!(a, alpha(&wide, n, n - 1 - x, y));
+                assert_eq!(a, alpha(&wide, n, x, n - 1 - y));
+                assert_eq!(a, alpha(&tall, n, y, x));
+            }
+        }
+        assert_eq!(alpha(&wide, n, 3, 8), 255);
+        assert_eq!(alpha(&wide, n, 8, 3), 0);
+    }
+
+    #[test]
+    fn line_thickness_and_falloff() {
+        let w = 14;
+        let column = |buf: &[u32], x: usize| (0..10).map(|y| alpha(buf, w, x, y)).collect::<Vec<_>>();
+        // 2px on pixel boundaries: two full rows, butt ends
+        let buf = draw(w, 10, |ctx| ctx.line((2.0, 5.0), (12.0, 5.0), 2.0, WHITE));
+        assert_eq!(column(&buf, 6), [0, 0, 0, 0, 255, 255, 0, 0, 0, 0]);
+        assert_eq!(column(&buf, 1), [0; 10]);
+        assert_eq!(column(&buf, 12), [0; 10]);
+        // 3px: the outer rows are half covered
+        let buf = draw(w, 10, |ctx| ctx.line((2.0, 5.0), (12.0, 5.0), 3.0, WHITE));
+        assert_eq!(column(&buf, 6), [0, 0, 0, 128, 255, 255, 128, 0, 0, 0]);
+        // thinner than a pixel: one pixel wide, fainter
+        let buf = draw(w, 10, |ctx| ctx.line((2.0, 5.5), (12.0, 5.5), 0.5, WHITE));
+        assert_eq!(column(&buf, 6), [0, 0, 0, 0, 0, 128, 0, 0, 0, 0]);
+
+        // diagonal: full on the line, fading symmetrically to either side
+        let buf = draw(10, 10, |ctx| ctx.line((0.5, 0.5), (9.5, 9.5), 1.0, WHITE));
+        assert_eq!(alpha(&buf, 10, 5, 5), 255);
+        let side = alpha(&buf, 10, 6, 5);
+        assert!(side > 0 && side < 255, "{}", side);
+        assert_eq!(side, alpha(&buf, 10, 5, 6));
+        assert_eq!(alpha(&buf, 10, 7, 5), 0);
+    }
+
+    #[test]
+    fn stroke_rect_border_width() {
+        let (w, h) = (14, 12);
+        let buf = draw(w, h, |ctx| ctx.stroke_rect(&Rect::new(2.0, 2.0, 10.0, 8.0), 2.0, WHITE));
+        for y in 0..h {
+            for x in 0..w {
+                let outer = (2..12).contains(&x) && (2..10).contains(&y);
+                let inner = (4..10).contains(&x) && (4..8).contains(&y);
+                assert_eq!(alpha(&buf, w, x, y), if outer && !inner { 255 } else { 0 }, "({}, {})", x, y);
+            }
+        }
+        // wider than half the rect: filled
+        let full = draw(w, h, |ctx| ctx.stroke_rect(&Rect::new(2.0, 2.0, 10.0, 8.0), 5.0, WHITE));
+        assert_eq!(alpha(&full, w, 6, 6), 255);
+    }
+
+    #[test]
+    fn blend_over_half_alpha_source() {
+        let src = premultiply(0x80FF_0000);
+        assert_eq!(src, 0x8080_0000);
+        let mut opaque = 0xFF00_0000;
+        blend_over(&mut opaque, src);
+        assert_eq!(opaque, 0xFF80_0000);
+        let mut white = 0xFFFF_FFFF;
+        blend_over(&mut white, src);
+        assert_eq!(white, 0xFFFF_7F7F);
+        let mut transparent = 0;
+        blend_over(&mut transparent, src);
+        assert_eq!(transparent, src);
+        assert_eq!(unpremultiply(transparent), 0x80FF_0000);
+        // half over half: alpha 0.5 + 0.5 * 0.5
+        let mut half = src;
+        blend_over(&mut half, src);
+        assert_eq!(half >> 24, 192);
+        // opaque replaces, fully transparent leaves alone
+        blend_over(&mut half, 0xFF12_3456);
+        assert_eq!(half, 0xFF12_3456);
+        blend_over(&mut half, 0);
+        assert_eq!(half, 0xFF12_3456);
+    }
+
+    #[test]
+    fn premultiply_round_trip() {
+        for a in 0..=255u32 {
+            // premultiplied values survive unpremultiply + premultiply exactly
+            for c in 0..=a {
+                let p = (a << 24) | (c << 16) | (c << 8) | c;
+                assert_eq!(premultiply(unpremultiply(p)), p, "a={} c={}", a, c);
+            }
+            // straight values come back within the precision alpha leaves them
+            for v in (0..=255u32).step_by(5) {
+                let s = (a << 24) | (v << 16) | ((255 - v) << 8) | (v / 2);
+                let back = unpremultiply(premultiply(s));
+                if a == 0 { assert_eq!(back, 0); continue; }
+                assert_eq!(back >> 24, a);
+                for shift in [0, 8, 16] {
+                    let (x, y) = ((s >> shift) & 0xFF, (back >> shift) & 0xFF);
+                    assert!(x.abs_diff(y) * a <= 255, "a={} {} -> {}", a, x, y);
+                }
+            }
+        }
+        assert_eq!(premultiply(0xFF12_3456), 0xFF12_3456);
+    }
+
+    // Draw unclipped and clipped to x0..x1 x y0..y1: the clipped result keeps exactly the
+    // unclipped pixels inside the clip.
+    fn assert_clipped(w: usize, h: usize, (x0, y0, x1, y1): (usize, usize, usize, usize), f: impl Fn(&mut DrawContext)) {
+        let full = draw(w, h, &f);
+        let clipped = draw(w, h, |ctx| {
+            ctx.push_clip(&Rect::new(x0 as f32, y0 as f32, (x1 - x0) as f32, (y1 - y0) as f32));
+            f(ctx);
+        });
+        assert!(full.iter().any(|p| *p != 0), "nothing drawn");
+        let mut inside = 0;
+        for y in 0..h {
+            for x in 0..w {
+                let keep = (x0..x1).contains(&x) && (y0..y1).contains(&y);
+                assert_eq!(clipped[y * w + x], if keep { full[y * w + x] } else { 0 }, "({}, {})", x, y);
+                if keep && full[y * w + x] != 0 { inside += 1; }
+            }
+        }
+        assert!(inside > 0, "nothing drawn inside the clip");
+    }
+
+    #[test]
+    fn primitives_stay_inside_the_clip() {
+        let clip = (4, 3, 12, 9);
+        assert_clipped(16, 12, clip, |ctx| ctx.fill_rect(&Rect::new(0.5, 0.5, 15.0, 11.0), WHITE));
+        assert_clipped(16, 12, clip, |ctx| ctx.stroke_rounded_rect(&Rect::new(6.0, 1.0, 9.0, 10.0), [3.0; 4], 2.0, WHITE));
+        assert_clipped(16, 12, clip, |ctx| ctx.fill_circle((8.0, 6.0), 7.0, WHITE));
+        assert_clipped(16, 12, clip, |ctx| ctx.line((0.0, 0.0), (16.0, 12.0), 3.0, WHITE));
+        assert_clipped(16, 12, clip, |ctx| {
+            for i in 0..16 { ctx.blend_pixel(i, i * 3 / 4, WHITE, 200); }
+        });
+        assert_clipped(16, 12, clip, |ctx| ctx.in_clip(|buf, w, h, stride, (ox, oy)| {
+            crate::draw_line(buf, w, h, stride, -ox, 11 - oy, 15 - ox, -oy, WHITE)
+        }));
+
+        let image = RgbaImage { width: 2, height: 2, pixels: vec![255, 0, 0, 255, 0, 255, 0, 255, 0, 0, 255, 255, 255, 255, 255, 128] };
+        assert_clipped(16, 12, clip, |ctx| ctx.image(&Rect::new(1.0, 1.0, 14.0, 10.0), &image));
+
+        let font = FontAsset::from_bytes(crate::text::noto_sans_regular().to_vec()).unwrap();
+        let (w, h) = (64, 24);
+        assert_clipped(w, h, (10, 4, 30, 14), |ctx| ctx.text((2.0, 2.0), "Hello", &font, 18.0, WHITE));
+    }
+
+    #[test]
+    fn nested_clips_intersect() {
+        let buf = draw(16, 12, |ctx| {
+            ctx.push_clip(&Rect::new(2.0, 2.0, 8.0, 6.0));
+            ctx.push_clip(&Rect::new(6.0, 4.0, 8.0, 6.0));
+            assert_eq!(ctx.clip(), Rect::new(6.0, 4.0, 4.0, 4.0));
+            ctx.fill_rect(&Rect::new(0.0, 0.0, 16.0, 12.0), WHITE);
+            // disjoint clips leave nothing to draw into
+            ctx.push_clip(&Rect::new(12.0, 0.0, 4.0, 4.0));
+            assert!(ctx.clip().is_empty());
+            ctx.fill_rect(&Rect::new(0.0, 0.0, 16.0, 12.0), 0xFFFF_0000);
+            ctx.in_clip(|_, _, _, _, _| panic!("in_clip called with an empty clip"));
+            ctx.pop_clip();
+            ctx.pop_clip();
+            assert_eq!(ctx.clip(), Rect::new(2.0, 2.0, 8.0, 6.0));
+        });
+        for y in 0..12 {
+            for x in 0..16 {
+                let inside = (6..10).contains(&x) && (4..8).contains(&y);
+                assert_eq!(buf[y * 16 + x], if inside { WHITE } else { 0 }, "({}, {})", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn unmatched_pop_clip_is_a_no_op() {
+        let buf = draw(8, 8, |ctx| {
+            let full = ctx.clip();
+            assert_eq!(full, Rect::new(0.0, 0.0, 8.0, 8.0));
+            ctx.pop_clip();
+            assert_eq!(ctx.clip(), full);
+            ctx.push_clip(&Rect::new(2.0, 2.0, 4.0, 4.0));
+            ctx.pop_clip();
+            ctx.pop_clip();
+            assert_eq!(ctx.clip(), full);
+            ctx.fill_rect(&Rect::new(0.0, 0.0, 8.0, 8.0), WHITE);
+        });
+        assert!(buf.iter().all(|p| *p == WHITE));
+    }
+}
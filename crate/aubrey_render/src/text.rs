@@ -1,54 +1,96 @@
-use ab_glyph::{FontRef, PxScale, point, Font, Glyph};
+use ab_glyph::{FontArc, FontRef, InvalidFont, PxScale, point, Font, Glyph};
+use aubrey_core::asset::{AssetLoader, BoxError, LoadContext};
+use aubrey_core::fs::EmbeddedBackend;
+use aubrey_core::image::RgbaImage;
 
-use crate::pack_rgba_u8;
+use crate::raster::DrawContext;
 
 // Embedded font accessor
 pub fn noto_sans_regular() -> &'static [u8] { include_bytes!("../assets/NotoSans-Regular.ttf") }
 
-/// Draw UTF-8 text into ARGB8888 buffer using ab_glyph (monochrome alpha blended)
+/// `embedded://` path of `noto_sans_regular` once `embed_assets` has registered it.
+pub const NOTO_SANS_REGULAR: &str = "embedded://fonts/NotoSans-Regular.ttf";
+
+/// Register the renderer's built-in files with the `embedded` source's backend.
+pub fn embed_assets(backend: &mut EmbeddedBackend) {
+    backend.insert(NOTO_SANS_REGULAR.trim_start_matches("embedded://"), noto_sans_regular());
+}
+
+/// Parsed TTF/OTF font, loaded through the `AssetServer` with `FontLoader`. Clones share the font data.
+#[derive(Clone)]
+pub struct FontAsset(FontArc);
+
+impl FontAsset {
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self, InvalidFont> { FontArc::try_from_vec(data).map(Self) }
+
+    pub fn font(&self) -> &FontArc { &self.0 }
+}
+
+/// `AssetLoader` for `.ttf` / `.otf` files.
+pub struct FontLoader;
+
+impl AssetLoader for FontLoader {
+    type Asset = FontAsset;
+    fn extensions(&self) -> &[&str] { &["ttf", "otf"] }
+    fn load(&self, ctx: &mut LoadContext) -> Result<FontAsset, BoxError> { Ok(FontAsset::from_bytes(ctx.bytes().to_vec())?) }
+}
+
+/// Draw UTF-8 text into ARGB8888 buffer, parsing `font_bytes` on every call.
+/// Prefer `draw_text` with a `FontAsset` for anything drawn repeatedly.
 pub fn draw_text_mono(buf: &mut [u32], width: usize, height: usize, stride: usize, x: i32, y: i32, text: &str, font_bytes: &[u8], px: f32, color: u32) {
-    if let Ok(font) = FontRef::try_from_slice(font_bytes) {
-        let scale = PxScale::from(px);
-        let ascent = px; // rough baseline estimate; good enough for now
-        let line_gap = px * 0.2;
-        let mut caret = point(x as f32, y as f32 + ascent);
-        for ch in text.chars() {
-            if ch == '\n' { caret.x = x as f32; caret.y += px + line_gap; continue; }
-            let id = font.glyph_id(ch);
-            let sg = Glyph { id, scale, position: caret };
-            if let Some(outline) = font.outline_glyph(sg) {
-                let bb = outline.px_bounds();
-                outline.draw(|gx, gy, cov| {
-                    let gx = gx as i32 + bb.min.x as i32;
-                    let gy = gy as i32 + bb.min.y as i32;
-                    if gx >= 0 && gy >= 0 {
-                        let ux = gx as usize; let uy = gy as usize;
-                        if ux < width && uy < height {
-                            let a = (cov.clamp(0.0, 1.0) * 255.0) as u8;
-                            let src = color;
-                            let sr = ((src >> 16) & 0xFF) as u8;
-                            let sg = ((src >> 8) & 0xFF) as u8;
-                            let sb = (src & 0xFF) as u8;
-                            let dst = &mut buf[uy * stride + ux];
-                            let dr = ((*dst >> 16) & 0xFF) as u8;
-                            let dg = ((*dst >> 8) & 0xFF) as u8;
-                            let db = (*dst & 0xFF) as u8;
-                            let inv_a = 255u16 - a as u16;
-                            let r = ((sr as u16 * a as u16 + dr as u16 * inv_a) / 255) as u8;
-                            let g = ((sg as u16 * a as u16 + dg as u16 * inv_a) / 255) as u8;
-                            let b = ((sb as u16 * a as u16 + db as u16 * inv_a) / 255) as u8;
-                            *dst = pack_rgba_u8(r, g, b, 255);
-                        }
-                    }
-                });
-                let mut adv = (bb.max.x - bb.min.x).ceil();
-                if adv <= 0.0 { adv = px * 0.6; }
-                caret.x += adv + (px * 0.1);
-            } else {
-                // Fallback advance
-                caret.x += px * 0.6;
-            }
+    match FontRef::try_from_slice(font_bytes) {
+        Ok(font) => {
+            let mut ctx = DrawContext::new(buf, width, height, stride);
+            layout_glyphs(x, y, text, &font, px, |gx, gy, a| ctx.blend_pixel(gx, gy, color, a));
         }
+        Err(e) => log::error!("failed to parse font ({} bytes): {}", font_bytes.len(), e),
     }
 }
 
+/// Draw UTF-8 text using ab_glyph (monochrome alpha blended), with the top-left of the first
+/// line at `pos`, inside `ctx`'s clip.
+pub fn draw_text(ctx: &mut DrawContext, pos: (i32, i32), text: &str, font: &FontAsset, px: f32, color: u32) {
+    layout_glyphs(pos.0, pos.1, text, &font.0, px, |gx, gy, a| ctx.blend_pixel(gx, gy, color, a));
+}
+
+// Calls `plot(x, y, coverage)` for every covered pixel of `text` drawn at (x, y).
+fn layout_glyphs(x: i32, y: i32, text: &str, font: &impl Font, px: f32, mut plot: impl FnMut(i32, i32, u8)) {
+    let scale = PxScale::from(px);
+    let ascent = px; // rough baseline estimate; good enough for now
+    let line_gap = px * 0.2;
+    let mut caret = point(x as f32, y as f32 + ascent);
+    for ch in text.chars() {
+        if ch == '\n' { caret.x = x as f32; caret.y += px + line_gap; continue; }
+        let id = font.glyph_id(ch);
+        let sg = Glyph { id, scale, position: caret };
+        if let Some(outline) = font.outline_glyph(sg) {
+            let bb = outline.px_bounds();
+            outline.draw(|gx, gy, cov| {
+                plot(gx as i32 + bb.min.x as i32, gy as i32 + bb.min.y as i32, (cov.clamp(0.0, 1.0) * 255.0) as u8);
+            });
+            let mut adv = (bb.max.x - bb.min.x).ceil();
+            if adv <= 0.0 { adv = px * 0.6; }
+            caret.x += adv + (px * 0.1);
+        } else {
+            // Fallback advance
+            caret.x += px * 0.6;
+        }
+    }
+}
+
+/// Coverage of `text` as drawn by `draw_text` at (0, 0): white pixels with coverage in alpha,
+/// and the offset of the image's top-left corner. None if nothing is covered.
+pub(crate) fn text_mask(text: &str, font: &FontAsset, px: f32) -> Option<((i32, i32), RgbaImage)> {
+    let mut points = Vec::new();
+    layout_glyphs(0, 0, text, &font.0, px, |x, y, a| if a > 0 { points.push((x, y, a)) });
+    if points.is_empty() { return None; }
+    let (x0, y0) = points.iter().fold((i32::MAX, i32::MAX), |(mx, my), &(x, y, _)| (mx.min(x), my.min(y)));
+    let (x1, y1) = points.iter().fold((i32::MIN, i32::MIN), |(mx, my), &(x, y, _)| (mx.max(x), my.max(y)));
+    let mut image = RgbaImage::new((x1 - x0 + 1) as u32, (y1 - y0 + 1) as u32);
+    for px in image.pixels.chunks_exact_mut(4) { px[..3].fill(255); }
+    for (x, y, a) in points {
+        let i = ((y - y0) as usize * image.width as usize + (x - x0) as usize) * 4 + 3;
+        image.pixels[i] = image.pixels[i].max(a);
+    }
+    Some(((x0, y0), image))
+}
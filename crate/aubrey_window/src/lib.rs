@@ -97,7 +97,11 @@ impl Handler {
                 let attrs = WindowAttributes::default()
                     .with_title(d.title)
                     .with_inner_size(LogicalSize::new(d.width as f64, d.height as f64));
-                let window = event_loop.create_window(attrs).expect("create window");
+                let window = match event_loop.create_window(attrs) {
+                    Ok(w) => w,
+                    Err(err) => { log::error!("failed to create window for entity {}: {}", e.id(), err); continue; }
+                };
+                log::info!("window created for entity {}", e.id());
                 let id = window.id();
                 rev.insert(id, e);
                 map.insert(e, window);
@@ -115,6 +119,7 @@ impl ApplicationHandler for Handler {
             WindowEvent::CloseRequested => {
                 with_maps(|map, rev| {
                     if let Some(&entity) = rev.get(&window_id) {
+                        log::info!("window closed for entity {}", entity.id());
                         map.remove(&entity);
                         rev.remove(&window_id);
                     }
@@ -174,7 +179,10 @@ pub fn register(app: &mut App) { app.add_systems(aubrey_core::ecs::Stage::Update
 pub fn run(mut app: App) {
     // ensure our collector runs
     register(&mut app);
-    let event_loop = EventLoop::new().expect("event loop");
+    let event_loop = match EventLoop::new() {
+        Ok(l) => l,
+        Err(e) => { log::error!("failed to create event loop: {}", e); return; }
+    };
     let mut handler = Handler { app };
-    event_loop.run_app(&mut handler).expect("run app");
+    if let Err(e) = event_loop.run_app(&mut handler) { log::error!("event loop exited with error: {}", e); }
 }